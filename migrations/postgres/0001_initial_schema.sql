@@ -1,8 +1,9 @@
 -- Esquema de base de datos para Wolges Tournament Server
--- Migración del sistema de archivos JSON a PostgreSQL
+-- Idempotente: las bases creadas a mano con el antiguo supabase_schema.sql
+-- lo adoptan sin cambios.
 
 -- Tabla principal de torneos
-CREATE TABLE tournaments (
+CREATE TABLE IF NOT EXISTS tournaments (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
     name VARCHAR(255) NOT NULL,
     created_at TIMESTAMPTZ DEFAULT NOW(),
@@ -14,7 +15,7 @@ CREATE TABLE tournaments (
 );
 
 -- Tabla de jugadores
-CREATE TABLE players (
+CREATE TABLE IF NOT EXISTS players (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
     tournament_id UUID REFERENCES tournaments(id) ON DELETE CASCADE,
     name VARCHAR(255) NOT NULL,
@@ -26,7 +27,7 @@ CREATE TABLE players (
 );
 
 -- Tabla de rondas
-CREATE TABLE rounds (
+CREATE TABLE IF NOT EXISTS rounds (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
     tournament_id UUID REFERENCES tournaments(id) ON DELETE CASCADE,
     number INTEGER NOT NULL,
@@ -41,7 +42,7 @@ CREATE TABLE rounds (
 );
 
 -- Tabla de jugadas del Master
-CREATE TABLE master_plays (
+CREATE TABLE IF NOT EXISTS master_plays (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
     tournament_id UUID REFERENCES tournaments(id) ON DELETE CASCADE,
     round_number INTEGER NOT NULL,
@@ -54,14 +55,14 @@ CREATE TABLE master_plays (
 );
 
 -- Tabla de jugadas de jugadores
-CREATE TABLE player_plays (
+CREATE TABLE IF NOT EXISTS player_plays (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
     tournament_id UUID REFERENCES tournaments(id) ON DELETE CASCADE,
     player_id UUID REFERENCES players(id) ON DELETE CASCADE,
     round_number INTEGER NOT NULL,
     word VARCHAR(100),
     position_row INTEGER,
-    position_col INTEGER, 
+    position_col INTEGER,
     position_down BOOLEAN,
     score INTEGER DEFAULT 0,
     percentage_of_optimal REAL DEFAULT 0,
@@ -73,7 +74,7 @@ CREATE TABLE player_plays (
 );
 
 -- Tabla de estado del tablero por ronda
-CREATE TABLE board_states (
+CREATE TABLE IF NOT EXISTS board_states (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
     tournament_id UUID REFERENCES tournaments(id) ON DELETE CASCADE,
     round_number INTEGER NOT NULL,
@@ -83,12 +84,12 @@ CREATE TABLE board_states (
 );
 
 -- Índices para optimizar consultas
-CREATE INDEX idx_tournaments_status ON tournaments(status);
-CREATE INDEX idx_players_tournament ON players(tournament_id);
-CREATE INDEX idx_rounds_tournament ON rounds(tournament_id, number);
-CREATE INDEX idx_master_plays_tournament ON master_plays(tournament_id, round_number);
-CREATE INDEX idx_player_plays_tournament ON player_plays(tournament_id, round_number);
-CREATE INDEX idx_player_plays_player ON player_plays(player_id, round_number);
+CREATE INDEX IF NOT EXISTS idx_tournaments_status ON tournaments(status);
+CREATE INDEX IF NOT EXISTS idx_players_tournament ON players(tournament_id);
+CREATE INDEX IF NOT EXISTS idx_rounds_tournament ON rounds(tournament_id, number);
+CREATE INDEX IF NOT EXISTS idx_master_plays_tournament ON master_plays(tournament_id, round_number);
+CREATE INDEX IF NOT EXISTS idx_player_plays_tournament ON player_plays(tournament_id, round_number);
+CREATE INDEX IF NOT EXISTS idx_player_plays_player ON player_plays(player_id, round_number);
 
 -- Triggers para actualizar last_modified
 CREATE OR REPLACE FUNCTION update_last_modified()
@@ -101,18 +102,22 @@ BEGIN
 END;
 $$ LANGUAGE plpgsql;
 
+DROP TRIGGER IF EXISTS update_tournament_modified_players ON players;
 CREATE TRIGGER update_tournament_modified_players
     AFTER INSERT OR UPDATE OR DELETE ON players
     FOR EACH ROW EXECUTE FUNCTION update_last_modified();
 
+DROP TRIGGER IF EXISTS update_tournament_modified_rounds ON rounds;
 CREATE TRIGGER update_tournament_modified_rounds
     AFTER INSERT OR UPDATE OR DELETE ON rounds
     FOR EACH ROW EXECUTE FUNCTION update_last_modified();
 
+DROP TRIGGER IF EXISTS update_tournament_modified_master_plays ON master_plays;
 CREATE TRIGGER update_tournament_modified_master_plays
     AFTER INSERT OR UPDATE OR DELETE ON master_plays
     FOR EACH ROW EXECUTE FUNCTION update_last_modified();
 
+DROP TRIGGER IF EXISTS update_tournament_modified_player_plays ON player_plays;
 CREATE TRIGGER update_tournament_modified_player_plays
     AFTER INSERT OR UPDATE OR DELETE ON player_plays
-    FOR EACH ROW EXECUTE FUNCTION update_last_modified();
\ No newline at end of file
+    FOR EACH ROW EXECUTE FUNCTION update_last_modified();
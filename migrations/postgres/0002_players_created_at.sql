@@ -0,0 +1,4 @@
+-- Fecha de alta de los jugadores preinscritos al crear el torneo
+-- (antes fix_supabase_columns.sql)
+ALTER TABLE players
+ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ DEFAULT NOW();
@@ -0,0 +1,37 @@
+-- Ingesta de jugadas por LISTEN/NOTIFY con cursor persistido
+-- (ver src/supabase_poller.rs)
+ALTER TABLE player_plays ADD COLUMN IF NOT EXISTS ingest_seq BIGSERIAL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_player_plays_ingest_seq ON player_plays(ingest_seq);
+
+CREATE TABLE IF NOT EXISTS play_ingest_cursors (
+    consumer VARCHAR(64) PRIMARY KEY,
+    last_seq BIGINT NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+-- Una jugada reenviada pasa al final de la secuencia para volver a procesarse
+CREATE OR REPLACE FUNCTION bump_player_play_seq()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.ingest_seq := nextval(pg_get_serial_sequence('player_plays', 'ingest_seq'));
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION notify_player_play()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('player_plays_inserted', NEW.ingest_seq::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS player_plays_bump_seq ON player_plays;
+CREATE TRIGGER player_plays_bump_seq
+    BEFORE UPDATE ON player_plays
+    FOR EACH ROW EXECUTE FUNCTION bump_player_play_seq();
+
+DROP TRIGGER IF EXISTS player_plays_notify ON player_plays;
+CREATE TRIGGER player_plays_notify
+    AFTER INSERT OR UPDATE ON player_plays
+    FOR EACH ROW EXECUTE FUNCTION notify_player_play();
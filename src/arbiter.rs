@@ -0,0 +1,140 @@
+// Cuentas de árbitro, roles y permisos
+//
+// Cada torneo puede tener varias cuentas de árbitro. Las acciones globales
+// (diccionario, modo de persistencia, mensajes muertos) exigen la credencial del
+// servidor. Un torneo sin cuentas solo queda abierto si el servidor arranca en
+// modo abierto explícito (compatibilidad con torneos antiguos).
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Cabecera HTTP donde el árbitro envía su token de sesión
+pub const ARBITER_TOKEN_HEADER: &str = "X-Arbiter-Token";
+
+/// Variable de entorno con la credencial del servidor; se envía en la misma cabecera
+pub const SERVER_TOKEN_ENV: &str = "SERVER_ADMIN_TOKEN";
+
+/// Variable de entorno que deja abiertos los torneos sin cuentas de árbitro
+pub const OPEN_MODE_ENV: &str = "ARBITER_OPEN_MODE";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ArbiterRole {
+    /// Árbitro principal - puede hacer todo
+    HeadArbiter,
+    /// Asistente - dirige rondas y captura planillas, pero no deshace ni termina
+    Assistant,
+    /// Pantalla de solo lectura
+    Display,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArbiterAction {
+    StartRound,
+    UpdateRack,
+    RejectRack,
+    StartTimer,
+    ViewRoundProgress,
+    NudgePlayers,
+    ViewMaster,
+    RevealMaster,
+    PlaceMaster,
+    EnterSlip,
+    ViewPlayerLogs,
+    ViewJournal,
+    AdjustScore,
+    ManagePlayers,
+    ReviewAlerts,
+    ConfigureTournament,
+    Undo,
+    Finish,
+    ManageArbiters,
+    ConfigureServer,
+}
+
+impl ArbiterRole {
+    pub fn allows(&self, action: ArbiterAction) -> bool {
+        use ArbiterAction::*;
+        match self {
+            ArbiterRole::HeadArbiter => true,
+            ArbiterRole::Assistant => !matches!(action, Undo | Finish | AdjustScore | ManageArbiters | ConfigureTournament | ConfigureServer),
+            ArbiterRole::Display => matches!(action, ViewPlayerLogs | ViewRoundProgress),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArbiterAccount {
+    pub name: String,
+    pub role: ArbiterRole,
+    pub passphrase_salt: String,
+    pub passphrase_hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ArbiterAccount {
+    pub fn new(name: String, role: ArbiterRole, passphrase: &str) -> Self {
+        let passphrase_salt = crate::auth::generate_token();
+        let passphrase_hash = crate::auth::hash_token(&format!("{}{}", passphrase_salt, passphrase));
+        Self {
+            name,
+            role,
+            passphrase_salt,
+            passphrase_hash,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn check_passphrase(&self, passphrase: &str) -> bool {
+        crate::auth::verify_token(&format!("{}{}", self.passphrase_salt, passphrase), &self.passphrase_hash)
+    }
+}
+
+/// Sesión de árbitro en memoria (se pierde al reiniciar el servidor)
+#[derive(Debug, Clone, Serialize)]
+pub struct ArbiterSession {
+    pub tournament_id: Uuid,
+    pub name: String,
+    pub role: ArbiterRole,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ArbiterLoginRequest {
+    pub name: Option<String>,
+    pub passphrase: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateArbiterRequest {
+    pub name: String,
+    pub role: ArbiterRole,
+    pub passphrase: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArbiterLoginResponse {
+    pub token: String,
+    pub name: String,
+    pub role: ArbiterRole,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_permissions() {
+        assert!(ArbiterRole::HeadArbiter.allows(ArbiterAction::Undo));
+        assert!(ArbiterRole::Assistant.allows(ArbiterAction::EnterSlip));
+        assert!(!ArbiterRole::Assistant.allows(ArbiterAction::Undo));
+        assert!(!ArbiterRole::Display.allows(ArbiterAction::StartRound));
+    }
+
+    #[test]
+    fn test_passphrase_check() {
+        let account = ArbiterAccount::new("Principal".to_string(), ArbiterRole::HeadArbiter, "secreto");
+        assert!(account.check_passphrase("secreto"));
+        assert!(!account.check_passphrase("otro"));
+    }
+}
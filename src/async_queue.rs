@@ -3,8 +3,15 @@ use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, Mutex};
 use tokio::time::sleep;
 use uuid::Uuid;
+use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 use log::{info, warn, error};
+use crate::dead_letter::{DeadLetter, DeadLetterStore};
+use crate::models::PlayerPlay;
+use crate::persistence_mode::PersistenceConfig;
+
+/// Cada cuánto se revisa la cola de mensajes muertos
+const DEAD_LETTER_SWEEP: Duration = Duration::from_secs(15);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayData {
@@ -20,6 +27,30 @@ pub struct PlayData {
     pub cumulative_score: i32,
     pub difference_from_optimal: i32,
     pub cumulative_difference: i32,
+    /// Momento en que el jugador envió la jugada; la nube no la aplica sobre
+    /// una más reciente (entradas antiguas sin este campo valen como las más viejas)
+    #[serde(default)]
+    pub submitted_at: DateTime<Utc>,
+}
+
+impl PlayData {
+    pub fn from_play(tournament_id: Uuid, player_id: Uuid, play: &PlayerPlay) -> Self {
+        PlayData {
+            tournament_id,
+            player_id,
+            round_number: play.round_number as i32,
+            word: play.word.clone(),
+            position_row: play.position.row as i32,
+            position_col: play.position.col as i32,
+            position_down: play.position.down,
+            score: play.score,
+            percentage_of_optimal: play.percentage_of_optimal,
+            cumulative_score: play.cumulative_score,
+            difference_from_optimal: play.difference_from_optimal,
+            cumulative_difference: play.cumulative_difference,
+            submitted_at: play.submitted_at,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -31,16 +62,29 @@ pub struct QueueMetrics {
     pub max_latency_ms: f64,
     pub queue_size: usize,
     pub last_error: Option<String>,
+    pub dead_letter_size: usize,
+    pub total_recovered: u64,
 }
 
 pub struct AsyncQueue {
     sender: mpsc::Sender<PlayData>,
     metrics: Arc<Mutex<QueueMetrics>>,
     database: Arc<crate::database::Database>,
+    dead_letters: Arc<DeadLetterStore>,
+    /// Serializa el reintento automático y el manual para no enviar dos veces la misma entrada
+    redelivery: Arc<Mutex<()>>,
+}
+
+async fn send_play(database: &crate::database::Database, play_data: &PlayData) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    database.submit_player_play(play_data).await
 }
 
 impl AsyncQueue {
-    pub async fn new(database: Arc<crate::database::Database>) -> Self {
+    pub async fn new(
+        database: Arc<crate::database::Database>,
+        dead_letters: Arc<DeadLetterStore>,
+        cloud: Arc<PersistenceConfig>,
+    ) -> Self {
         let (sender, mut receiver) = mpsc::channel::<PlayData>(1000); // Buffer for 1000 plays
         let metrics = Arc::new(Mutex::new(QueueMetrics {
             total_submitted: 0,
@@ -50,10 +94,13 @@ impl AsyncQueue {
             max_latency_ms: 0.0,
             queue_size: 0,
             last_error: None,
+            dead_letter_size: 0,
+            total_recovered: 0,
         }));
         
         let metrics_clone = metrics.clone();
         let db_clone = database.clone();
+        let dead_clone = dead_letters.clone();
         
         // Spawn background worker
         tokio::spawn(async move {
@@ -80,20 +127,7 @@ impl AsyncQueue {
                         info!("Retrying play submission (attempt {}/{})", retry_count + 1, max_retries);
                     }
                     
-                    match db_clone.submit_player_play(
-                        play_data.tournament_id,
-                        play_data.player_id,
-                        play_data.round_number,
-                        play_data.word.clone(),
-                        play_data.position_row,
-                        play_data.position_col,
-                        play_data.position_down,
-                        play_data.score,
-                        play_data.percentage_of_optimal,
-                        play_data.cumulative_score,
-                        play_data.difference_from_optimal,
-                        play_data.cumulative_difference,
-                    ).await {
+                    match send_play(&db_clone, &play_data).await {
                         Ok(_) => {
                             success = true;
                             let latency_ms = start.elapsed().as_millis() as f64;
@@ -116,9 +150,12 @@ impl AsyncQueue {
                                 let mut m = metrics_clone.lock().await;
                                 m.total_failed += 1;
                                 m.last_error = Some(error_string.clone());
+                                drop(m);
                                 
-                                // TODO: Store failed plays for later sync
-                                // For now, we just log the error
+                                // Guardar en disco para reintentar cuando vuelva la nube
+                                if let Err(e) = dead_clone.push(play_data.clone(), error_string).await {
+                                    error!("No se pudo guardar la jugada en la cola de mensajes muertos: {}", e);
+                                }
                             } else {
                                 warn!("Error submitting play (will retry): {}", error_string);
                             }
@@ -128,13 +165,78 @@ impl AsyncQueue {
             }
         });
         
+        // Reintento automático de mensajes muertos mientras la nube esté disponible
+        let sweep_metrics = metrics.clone();
+        let sweep_db = database.clone();
+        let sweep_dead = dead_letters.clone();
+        let redelivery = Arc::new(Mutex::new(()));
+        let sweep_lock = redelivery.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(DEAD_LETTER_SWEEP);
+            loop {
+                interval.tick().await;
+                if !cloud.cloud_ready() {
+                    continue;
+                }
+                for entry in sweep_dead.due(Utc::now()).await {
+                    let _guard = sweep_lock.lock().await;
+                    Self::redeliver(&sweep_db, &sweep_dead, &sweep_metrics, &entry.id).await.ok();
+                }
+            }
+        });
+        
         AsyncQueue {
             sender,
             metrics,
             database,
+            dead_letters,
+            redelivery,
+        }
+    }
+    
+    /// Reenvía una jugada muerta; si falla queda programada con más espera.
+    /// Se llama con `redelivery` tomado y relee la entrada, que puede haberse
+    /// entregado o descartado mientras se esperaba el turno.
+    async fn redeliver(
+        database: &crate::database::Database,
+        dead_letters: &DeadLetterStore,
+        metrics: &Mutex<QueueMetrics>,
+        id: &Uuid,
+    ) -> Result<(), String> {
+        let entry = dead_letters.get(id).await
+            .ok_or_else(|| format!("Dead letter {} not found", id))?;
+        match send_play(database, &entry.play_data).await {
+            Ok(_) => {
+                dead_letters.remove(&entry.id).await?;
+                metrics.lock().await.total_recovered += 1;
+                info!("Jugada recuperada de la cola de mensajes muertos (player: {}, round: {})",
+                      entry.play_data.player_id, entry.play_data.round_number);
+                Ok(())
+            }
+            Err(e) => {
+                let error_string = e.to_string();
+                warn!("Reintento de mensaje muerto {} fallido: {}", entry.id, error_string);
+                dead_letters.record_failure(&entry.id, error_string.clone()).await?;
+                Err(error_string)
+            }
         }
     }
     
+    pub async fn dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters.list().await
+    }
+    
+    /// Reintento manual inmediato, sin esperar el backoff
+    pub async fn retry_dead_letter(&self, id: &Uuid) -> Result<(), String> {
+        let _guard = self.redelivery.lock().await;
+        Self::redeliver(&self.database, &self.dead_letters, &self.metrics, id).await
+    }
+    
+    pub async fn discard_dead_letter(&self, id: &Uuid) -> Result<DeadLetter, String> {
+        self.dead_letters.remove(id).await?
+            .ok_or_else(|| format!("Dead letter {} not found", id))
+    }
+    
     pub async fn submit_play(&self, play_data: PlayData) -> Result<(), String> {
         // Update submitted count
         {
@@ -150,7 +252,9 @@ impl AsyncQueue {
     }
     
     pub async fn get_metrics(&self) -> QueueMetrics {
-        self.metrics.lock().await.clone()
+        let mut metrics = self.metrics.lock().await.clone();
+        metrics.dead_letter_size = self.dead_letters.len().await;
+        metrics
     }
     
     pub async fn health_check(&self) -> bool {
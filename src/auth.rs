@@ -0,0 +1,52 @@
+// Tokens de acceso por jugador
+//
+// El token en claro solo se entrega una vez (al inscribirse o al reemitirlo);
+// en la sesión del jugador se guarda únicamente su hash SHA-256.
+
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Cabecera HTTP donde el jugador envía su token
+pub const PLAYER_TOKEN_HEADER: &str = "X-Player-Token";
+
+/// Genera un token aleatorio de 256 bits codificado en hexadecimal
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn hash_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Compara el token recibido con el hash guardado sin cortocircuitar
+pub fn verify_token(token: &str, expected_hash: &str) -> bool {
+    constant_time_eq(&hash_token(token), expected_hash)
+}
+
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.bytes()
+        .zip(b.bytes())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_roundtrip() {
+        let token = generate_token();
+        assert_eq!(token.len(), 64);
+        let hash = hash_token(&token);
+        assert!(verify_token(&token, &hash));
+        assert!(!verify_token("otro", &hash));
+        assert_ne!(generate_token(), token);
+    }
+}
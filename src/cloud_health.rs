@@ -0,0 +1,93 @@
+// Monitor de salud de la nube
+//
+// Sondea la base de datos periódicamente y mantiene `cloud_available` de
+// PersistenceConfig al día. Al caer la nube los modos que dependen de ella
+// pasan a local primero; al volver se restaura el modo, se pide una
+// resincronización inmediata del cache local y se sube de nuevo cada torneo
+// cargado. Si la base no respondía al arrancar, el pool se conecta (y migra)
+// en el primer sondeo correcto. Cada transición se anuncia a
+// los árbitros por el canal de eventos y queda en el log de cada torneo.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use log::warn;
+use crate::database::Database;
+use crate::local_cache::LocalCache;
+use crate::persistence_mode::PersistenceConfig;
+use crate::tournament_manager::TournamentManager;
+
+/// Intervalo entre sondeos (configurable con CLOUD_HEALTH_INTERVAL_SECS)
+const DEFAULT_INTERVAL_SECS: u64 = 10;
+/// Tiempo máximo de espera de cada sondeo
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Fallos seguidos antes de declarar la nube caída, para no oscilar por un sondeo lento
+const FAILURES_BEFORE_DOWN: u32 = 2;
+
+/// Decide la transición a partir del estado actual y el resultado del sondeo.
+/// Devuelve `Some(nuevo_estado)` solo cuando hay cambio.
+fn next_status(available: bool, consecutive_failures: &mut u32, probe_ok: bool) -> Option<bool> {
+    if probe_ok {
+        *consecutive_failures = 0;
+        return (!available).then_some(true);
+    }
+    *consecutive_failures += 1;
+    (available && *consecutive_failures >= FAILURES_BEFORE_DOWN).then_some(false)
+}
+
+pub fn spawn(
+    database: Arc<Database>,
+    config: Arc<PersistenceConfig>,
+    local_cache: Arc<LocalCache>,
+    manager: Arc<RwLock<TournamentManager>>,
+) {
+    let interval_secs = std::env::var("CLOUD_HEALTH_INTERVAL_SECS").ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_INTERVAL_SECS);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        let mut consecutive_failures = 0;
+        loop {
+            interval.tick().await;
+            let probe_ok = match tokio::time::timeout(PROBE_TIMEOUT, database.ping()).await {
+                Ok(Ok(())) => true,
+                Ok(Err(e)) => {
+                    warn!("Sondeo de la nube fallido: {}", e);
+                    false
+                }
+                Err(_) => {
+                    warn!("Sondeo de la nube sin respuesta en {:?}", PROBE_TIMEOUT);
+                    false
+                }
+            };
+
+            let Some(available) = next_status(config.cloud_ready(), &mut consecutive_failures, probe_ok) else {
+                continue;
+            };
+            config.set_cloud_status(available).await;
+            let manager = manager.read().await;
+            if available {
+                local_cache.request_resync();
+                manager.resync_storage();
+            }
+            manager.announce_cloud_status(available, config.current_mode());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_status_needs_repeated_failures() {
+        let mut failures = 0;
+        assert_eq!(next_status(true, &mut failures, false), None);
+        assert_eq!(next_status(true, &mut failures, false), Some(false));
+        assert_eq!(next_status(false, &mut failures, false), None);
+        assert_eq!(next_status(false, &mut failures, true), Some(true));
+        assert_eq!(failures, 0);
+        assert_eq!(next_status(true, &mut failures, true), None);
+    }
+}
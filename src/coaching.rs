@@ -0,0 +1,232 @@
+// Análisis por equity para torneos de entrenamiento
+//
+// El duplicado se juega al máximo de puntos y el master oficial no cambia. En
+// entrenamiento se muestra además la mejor jugada por equity (puntos más el
+// valor del resto según el KLV) y se compara el resto del jugador con el del
+// master y el de esa jugada. Sin KLV los restos valen 0 y el análisis compara
+// solo puntos. Nada de esto se guarda en el torneo.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use uuid::Uuid;
+use crate::models::{BoardState, EquityPlay, PlayerPlay, Position};
+use crate::tournament_manager::format_coordinate;
+use crate::wolges_engine::WolgesEngine;
+
+/// Diferencias de equity menores se consideran empate
+const EQUITY_EPSILON: f32 = 0.05;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LeaveComparison {
+    pub play: EquityPlay,
+    pub leave_vs_master: f32,  // Valor del resto del jugador menos el del master
+    pub equity_behind_best: f32,
+    pub explanation: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundCoaching {
+    pub round_number: u32,
+    pub rack: String,
+    pub master: EquityPlay,  // Master oficial (máxima puntuación), con su resto
+    pub best_equity: EquityPlay,
+    pub master_is_best_equity: bool,
+    pub leave_values: bool,  // false sin KLV: los restos valen 0 y solo cuentan los puntos
+    pub summary: String,
+    pub player: Option<LeaveComparison>,
+    pub player_note: Option<String>,  // Por qué no hay comparación (sin jugada, inválida...)
+}
+
+/// Master y mejor jugada por equity de una ronda: la generación de jugadas se
+/// hace una vez por ronda y sirve mientras no cambien atril ni tablero
+pub struct RoundEquity {
+    rack: String,
+    board: Vec<String>,
+    master: EquityPlay,
+    best_equity: EquityPlay,
+}
+
+pub type CoachingCache = Arc<Mutex<HashMap<(Uuid, u32), RoundEquity>>>;
+
+/// Lo que el análisis necesita de la ronda. Se prepara con el lock del manager
+/// y se ejecuta después con el motor de análisis, sin bloquear el servidor.
+pub struct CoachingJob {
+    pub tournament_id: Uuid,
+    pub round_number: u32,
+    pub rack: String,
+    pub board: BoardState,  // Tablero anterior al master
+    pub master_word: String,
+    pub master_position: Position,
+    pub master_score: i32,
+    pub player_play: Option<Option<PlayerPlay>>,  // Solo si se pidió un jugador
+    pub engine: Arc<Mutex<WolgesEngine>>,
+    pub cache: CoachingCache,
+}
+
+impl CoachingJob {
+    fn equity_play(&self, engine: &WolgesEngine, word: String, position: Position, score: i32) -> Result<EquityPlay, String> {
+        let (leave, leave_value) = engine.play_leave(&self.board, &self.rack, &position, &word)?;
+        Ok(EquityPlay { word, position, score, leave, leave_value, equity: score as f32 + leave_value })
+    }
+
+    /// Genera las jugadas de la ronda (o las toma del caché) y compara la del jugador
+    pub fn run(self) -> Result<RoundCoaching, String> {
+        let mut engine = self.engine.lock().map_err(|_| "El motor de análisis no está disponible")?;
+        let key = (self.tournament_id, self.round_number);
+
+        let cached = self.cache.lock().unwrap().get(&key)
+            .filter(|e| e.rack == self.rack && e.board == self.board.tiles)
+            .map(|e| (e.master.clone(), e.best_equity.clone()));
+        let (master, best_equity) = match cached {
+            Some(cached) => cached,
+            None => {
+                let master = self.equity_play(&engine, self.master_word.clone(), self.master_position.clone(), self.master_score)?;
+                let best_equity = engine.best_equity_play(&self.board, &self.rack)?;
+                self.cache.lock().unwrap().insert(key, RoundEquity {
+                    rack: self.rack.clone(),
+                    board: self.board.tiles.clone(),
+                    master: master.clone(),
+                    best_equity: best_equity.clone(),
+                });
+                (master, best_equity)
+            }
+        };
+
+        let (player, player_note) = match self.player_play.clone() {
+            None => (None, None),
+            Some(None) => (None, Some("Sin jugada en esta ronda".to_string())),
+            Some(Some(play)) if play.is_late() => (None, Some("Jugada fuera de tiempo".to_string())),
+            Some(Some(play)) if play.is_invalid() => (None, Some("Jugada inválida".to_string())),
+            Some(Some(play)) => match self.equity_play(&engine, play.word, play.position, play.score) {
+                Ok(play) => (Some(compare(play, &master, &best_equity)), None),
+                Err(e) => (None, Some(e)),
+            },
+        };
+
+        let leave_values = engine.has_leave_values();
+        let mut summary = summary(&master, &best_equity);
+        if !leave_values {
+            summary.push_str(" (sin KLV cargado: se comparan solo puntos)");
+        }
+
+        Ok(RoundCoaching {
+            round_number: self.round_number,
+            rack: self.rack,
+            summary,
+            master_is_best_equity: master_is_best(&master, &best_equity),
+            leave_values,
+            master,
+            best_equity,
+            player,
+            player_note,
+        })
+    }
+}
+
+fn describe(play: &EquityPlay) -> String {
+    format!("{} en {}", play.word, format_coordinate(&play.position))
+}
+
+fn describe_leave(play: &EquityPlay) -> String {
+    if play.leave.is_empty() {
+        "sin resto".to_string()
+    } else {
+        format!("resto {} ({:+.1})", play.leave, play.leave_value)
+    }
+}
+
+/// El master empata por equity con la mejor jugada (dentro de EQUITY_EPSILON)
+pub fn master_is_best(master: &EquityPlay, best: &EquityPlay) -> bool {
+    best.equity - master.equity <= EQUITY_EPSILON
+}
+
+/// Master frente a la mejor jugada por equity
+pub fn summary(master: &EquityPlay, best: &EquityPlay) -> String {
+    if master_is_best(master, best) {
+        return format!(
+            "El master {} ({} puntos, {}) es también la mejor jugada por equity",
+            describe(master), master.score, describe_leave(master)
+        );
+    }
+    format!(
+        "Por equity la mejor es {} ({} puntos, {}, equity {:.1}): cede {} puntos frente al master {} ({}, equity {:.1}) a cambio de un resto mejor",
+        describe(best), best.score, describe_leave(best), best.equity,
+        master.score - best.score, describe(master), describe_leave(master), master.equity
+    )
+}
+
+/// Compara la jugada del jugador con el master y con la mejor por equity
+pub fn compare(player: EquityPlay, master: &EquityPlay, best: &EquityPlay) -> LeaveComparison {
+    let leave_vs_master = player.leave_value - master.leave_value;
+    let equity_behind_best = (best.equity - player.equity).max(0.0);
+
+    let mut explanation = format!(
+        "Tu jugada {} deja {} frente a {} del master",
+        describe(&player), describe_leave(&player), describe_leave(master)
+    );
+    if leave_vs_master.abs() <= EQUITY_EPSILON {
+        explanation.push_str(": el resto vale lo mismo.");
+    } else if leave_vs_master > 0.0 {
+        explanation.push_str(&format!(": tu resto vale {:.1} más", leave_vs_master));
+        let points_behind = master.score - player.score;
+        if points_behind > 0 {
+            explanation.push_str(&format!(" a cambio de {} puntos menos ahora.", points_behind));
+        } else {
+            explanation.push('.');
+        }
+    } else {
+        explanation.push_str(&format!(": tu resto vale {:.1} menos.", -leave_vs_master));
+    }
+
+    if equity_behind_best <= EQUITY_EPSILON {
+        explanation.push_str(" Es la mejor jugada por equity.");
+    } else {
+        explanation.push_str(&format!(
+            " Por equity quedas a {:.1} de {} (equity {:.1}).",
+            equity_behind_best, describe(best), best.equity
+        ));
+    }
+
+    LeaveComparison { play: player, leave_vs_master, equity_behind_best, explanation }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Position;
+
+    fn play(word: &str, col: u8, score: i32, leave: &str, leave_value: f32) -> EquityPlay {
+        EquityPlay {
+            word: word.to_string(),
+            position: Position { row: 7, col, down: false },
+            score,
+            leave: leave.to_string(),
+            leave_value,
+            equity: score as f32 + leave_value,
+        }
+    }
+
+    #[test]
+    fn test_compare_leaves_against_master_and_best() {
+        let master = play("QUESO", 7, 40, "UVV", -12.0);
+        let best = play("VOS", 6, 34, "EQU", 4.5);
+        assert!(summary(&master, &best).contains("cede 6 puntos"));
+        assert!(summary(&master, &master).contains("también la mejor"));
+        assert!(!master_is_best(&master, &best));
+
+        // Una diferencia dentro del margen cuenta como empate en ambos sitios
+        let close = play("VOS", 6, 34, "EQU", -5.97);
+        assert!(master_is_best(&master, &close));
+        assert!(summary(&master, &close).contains("también la mejor"));
+
+        let comparison = compare(play("VES", 7, 30, "OQU", 2.0), &master, &best);
+        assert!((comparison.leave_vs_master - 14.0).abs() < 0.01);
+        assert!((comparison.equity_behind_best - 6.5).abs() < 0.01);
+        assert!(comparison.explanation.contains("vale 14.0 más a cambio de 10 puntos menos"), "{}", comparison.explanation);
+        assert!(comparison.explanation.contains("quedas a 6.5 de VOS en H7"));
+
+        let comparison = compare(best.clone(), &master, &best);
+        assert!(comparison.explanation.ends_with("Es la mejor jugada por equity."));
+    }
+}
@@ -1,30 +1,72 @@
-use sqlx::{PgPool, Row, Executor};
+use sqlx::{PgPool, Postgres, Row, Executor, Transaction};
 use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::models::{MasterPlay, Player, Round, RoundStatus, Tournament};
+use crate::async_queue::PlayData;
 
 pub struct Database {
     pool: PgPool,
+    schema_ready: AtomicBool,  // Migraciones aplicadas en esta conexión
 }
 
 impl Database {
     pub async fn new() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        use sqlx::postgres::PgPoolOptions;
-        use std::time::Duration;
-        
         dotenv::dotenv().ok(); // Cargar .env si existe
         
         let database_url = env::var("DATABASE_URL")
             .expect("DATABASE_URL must be set in environment");
             
-        // Configurar pool con timeouts más largos
-        let pool = PgPoolOptions::new()
+        let pool = Self::pool_options()
+            .connect(&database_url)
+            .await?;
+        
+        // Esquema al día antes de aceptar escrituras
+        Self::migrate(&pool).await?;
+        
+        Ok(Database { pool, schema_ready: AtomicBool::new(true) })
+    }
+    
+    /// Pool sin conexión inicial, para arrancar con la base caída. El esquema
+    /// se migra en el primer `ping` que responda.
+    pub fn connect_lazy() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let database_url = env::var("DATABASE_URL")?;
+        let pool = Self::pool_options().connect_lazy(&database_url)?;
+        Ok(Database { pool, schema_ready: AtomicBool::new(false) })
+    }
+    
+    // Configurar pool con timeouts más largos
+    fn pool_options() -> sqlx::postgres::PgPoolOptions {
+        use std::time::Duration;
+        
+        sqlx::postgres::PgPoolOptions::new()
             .max_connections(5)
             .acquire_timeout(Duration::from_secs(10))  // Timeout de adquisición
             .idle_timeout(Duration::from_secs(10))     // Timeout idle
             .max_lifetime(Duration::from_secs(300))    // Vida máxima de conexión
-            .connect(&database_url)
-            .await?;
-        
-        Ok(Database { pool })
+    }
+    
+    async fn migrate(pool: &PgPool) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let applied = crate::db_migrations::migrate(pool).await?;
+        if !applied.is_empty() {
+            log::info!("Migraciones de base de datos aplicadas: {:?}", applied);
+        }
+        Ok(())
+    }
+    
+    /// Pool compartido (poller, cola asíncrona, consultas)
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+    
+    /// Consulta mínima para el monitor de salud de la nube
+    /// Si la base no respondía al arrancar, el primer sondeo correcto aplica las migraciones
+    pub async fn ping(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.pool.execute("SELECT 1").await?;
+        if !self.schema_ready.load(Ordering::Acquire) {
+            Self::migrate(&self.pool).await?;
+            self.schema_ready.store(true, Ordering::Release);
+        }
+        Ok(())
     }
     
     pub async fn test_connection(&self) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
@@ -96,23 +138,25 @@ impl Database {
     }
     
     pub async fn enroll_player(&self, tournament_id: uuid::Uuid, player_id: uuid::Uuid, name: String, ip_address: String, user_agent: String, hardware_id: String) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        use chrono::Utc;
-        
-        let enrolled_at = Utc::now();
-        
-        // Insert player with session info  
-        let query = format!(
-            "INSERT INTO players (id, tournament_id, name, total_score, enrolled_at, ip_address, user_agent, hardware_id) VALUES ('{}', '{}', '{}', 0, '{}', CAST('{}' AS INET), '{}', '{}')",
-            player_id, 
-            tournament_id, 
-            name.replace("'", "''"), 
-            enrolled_at.format("%Y-%m-%d %H:%M:%S%.3f%:z"),
-            ip_address.replace("'", "''"),
-            user_agent.replace("'", "''"),
-            hardware_id.replace("'", "''")
-        );
-        
-        self.pool.execute(&*query).await?;
+        // Si la IP no se conoce ("Unknown") la columna INET queda en NULL
+        let ip_address = ip_address.parse::<std::net::IpAddr>().ok().map(|ip| ip.to_string());
+        
+        // Re-sincronizar al jugador actualiza sus datos de sesión en lugar de fallar
+        sqlx::query(
+            "INSERT INTO players (id, tournament_id, name, total_score, enrolled_at, ip_address, user_agent, hardware_id) \
+             VALUES ($1, $2, $3, 0, NOW(), CAST($4 AS INET), $5, $6) \
+             ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name, ip_address = EXCLUDED.ip_address, \
+                 user_agent = EXCLUDED.user_agent, hardware_id = EXCLUDED.hardware_id",
+        )
+        .persistent(false)
+        .bind(player_id)
+        .bind(tournament_id)
+        .bind(&name)
+        .bind(ip_address)
+        .bind(&user_agent)
+        .bind(&hardware_id)
+        .execute(&self.pool)
+        .await?;
         
         Ok(format!("Player '{}' enrolled successfully in tournament {}", name, tournament_id))
     }
@@ -226,78 +270,377 @@ impl Database {
         Ok(Some(tournament_json))
     }
     
-    pub async fn submit_player_play(&self, tournament_id: uuid::Uuid, player_id: uuid::Uuid, round_number: i32, word: String, position_row: i32, position_col: i32, position_down: bool, score: i32, percentage: f32, cumulative_score: i32, difference_from_optimal: i32, cumulative_difference: i32) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        use chrono::Utc;
-        
-        let submitted_at = Utc::now();
-        let play_id = uuid::Uuid::new_v4();
+    /// Inserta o actualiza la jugada del jugador en la ronda. Es idempotente:
+    /// reenviar la misma jugada (resincronización, mensajes muertos) no duplica
+    /// filas ni modifica la existente, y una jugada anterior a la guardada no la pisa.
+    pub async fn submit_player_play(&self, play: &PlayData) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let mut tx = self.pool.begin().await?;
+        let changed = upsert_play(&mut tx, play).await?;
+        
+        if changed == 0 {
+            tx.commit().await?;
+            return Ok("Player play already up to date".to_string());
+        }
         
-        // Check if player play already exists for this round
-        let check_query = format!(
-            "SELECT id FROM player_plays WHERE tournament_id = '{}' AND player_id = '{}' AND round_number = {} /* {} */",
-            tournament_id, player_id, round_number, uuid::Uuid::new_v4()
-        );
+        // Solo la jugada aplicada actualiza el total del jugador
+        sqlx::query("UPDATE players SET total_score = $1 WHERE id = $2")
+            .persistent(false)
+            .bind(play.cumulative_score)
+            .bind(play.player_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok("Player play submitted successfully".to_string())
+    }
+    
+    /// Sincroniza el torneo completo (tablas relacionales) en una transacción:
+    /// rondas con su estado, tableros, jugadas maestras, jugadas y totales.
+    /// Las rondas deshechas se eliminan.
+    pub async fn sync_tournament(&self, tournament: &Tournament) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut tx = self.pool.begin().await?;
+        
+        upsert_tournament_row(&mut tx, tournament).await?;
+        for player in &tournament.players {
+            upsert_player(&mut tx, &tournament.id, player).await?;
+            for play in &player.plays {
+                upsert_play(&mut tx, &PlayData::from_play(tournament.id, player.id, play)).await?;
+            }
+        }
+        for round in &tournament.rounds {
+            upsert_round(&mut tx, &tournament.id, round).await?;
+        }
+        for master in &tournament.master_plays {
+            upsert_master_play(&mut tx, &tournament.id, master).await?;
+        }
         
-        let existing_rows = sqlx::query(&check_query)
-            .fetch_all(&self.pool)
+        let last_round = tournament.rounds.len() as i32;
+        for table in ["player_plays", "master_plays", "board_states"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE tournament_id = $1 AND round_number > $2", table))
+                .persistent(false)
+                .bind(tournament.id)
+                .bind(last_round)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("DELETE FROM rounds WHERE tournament_id = $1 AND number > $2")
+            .persistent(false)
+            .bind(tournament.id)
+            .bind(last_round)
+            .execute(&mut *tx)
             .await?;
         
-        if !existing_rows.is_empty() {
-            // Update existing play
-            let existing_id: uuid::Uuid = existing_rows[0].get("id");
-            let update_query = format!(
-                "UPDATE player_plays SET word = '{}', position_row = {}, position_col = {}, position_down = {}, score = {}, percentage_of_optimal = {}, submitted_at = '{}', cumulative_score = {}, difference_from_optimal = {}, cumulative_difference = {} WHERE id = '{}'",
-                word.replace("'", "''"),
-                position_row,
-                position_col,
-                position_down,
-                score,
-                percentage,
-                submitted_at.format("%Y-%m-%d %H:%M:%S%.3f%:z"),
-                cumulative_score,
-                difference_from_optimal,
-                cumulative_difference,
-                existing_id
-            );
-            self.pool.execute(&*update_query).await?;
-            
-            // Update player total score
-            let update_player_query = format!(
-                "UPDATE players SET total_score = {} WHERE id = '{}'",
-                cumulative_score, player_id
-            );
-            self.pool.execute(&*update_player_query).await?;
-            
-            Ok("Player play updated successfully".to_string())
-        } else {
-            // Insert new play
-            let insert_query = format!(
-                "INSERT INTO player_plays (id, tournament_id, player_id, round_number, word, position_row, position_col, position_down, score, percentage_of_optimal, submitted_at, cumulative_score, difference_from_optimal, cumulative_difference) VALUES ('{}', '{}', '{}', {}, '{}', {}, {}, {}, {}, {}, '{}', {}, {}, {})",
-                play_id,
-                tournament_id,
-                player_id,
-                round_number,
-                word.replace("'", "''"),
-                position_row,
-                position_col,
-                position_down,
-                score,
-                percentage,
-                submitted_at.format("%Y-%m-%d %H:%M:%S%.3f%:z"),
-                cumulative_score,
-                difference_from_optimal,
-                cumulative_difference
-            );
-            self.pool.execute(&*insert_query).await?;
-            
-            // Update player total score
-            let update_player_query = format!(
-                "UPDATE players SET total_score = {} WHERE id = '{}'",
-                cumulative_score, player_id
-            );
-            self.pool.execute(&*update_player_query).await?;
-            
-            Ok("Player play submitted successfully".to_string())
+        tx.commit().await?;
+        Ok(())
+    }
+    
+    /// Ronda (estado, atril, rechazo, revelación), tablero y jugada maestra
+    pub async fn sync_round(&self, tournament: &Tournament, round_number: u32) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let round = tournament.rounds.iter()
+            .find(|r| r.number == round_number)
+            .ok_or_else(|| format!("Ronda {} no encontrada", round_number))?;
+        
+        let mut tx = self.pool.begin().await?;
+        upsert_tournament_row(&mut tx, tournament).await?;
+        upsert_round(&mut tx, &tournament.id, round).await?;
+        if let Some(master) = tournament.master_plays.iter().find(|m| m.round_number == round_number) {
+            upsert_master_play(&mut tx, &tournament.id, master).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+    
+    /// Estado del torneo y totales finales de cada jugador
+    pub async fn sync_standings(&self, tournament: &Tournament) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut tx = self.pool.begin().await?;
+        upsert_tournament_row(&mut tx, tournament).await?;
+        for player in &tournament.players {
+            upsert_player(&mut tx, &tournament.id, player).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+// Consultas con parámetros y sin sentencias preparadas persistentes (el pooler
+// de Supabase en modo transacción no las admite)
+
+/// Estados de ronda del servidor a los del esquema SQL
+fn round_status(status: &RoundStatus) -> &'static str {
+    match status {
+        RoundStatus::Pending => "Generated",
+        RoundStatus::Active => "InProgress",
+        RoundStatus::Completed => "Completed",
+    }
+}
+
+async fn upsert_tournament_row(tx: &mut Transaction<'_, Postgres>, tournament: &Tournament) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO tournaments (id, name, created_at, status, tiles_remaining, current_round) \
+         VALUES ($1, $2, $3, $4, $5, $6) \
+         ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name, status = EXCLUDED.status, \
+             tiles_remaining = EXCLUDED.tiles_remaining, current_round = EXCLUDED.current_round, \
+             last_modified = NOW()",
+    )
+    .persistent(false)
+    .bind(tournament.id)
+    .bind(&tournament.name)
+    .bind(tournament.created_at)
+    .bind(format!("{:?}", tournament.status))
+    .bind(tournament.tiles_remaining as i32)
+    .bind(tournament.rounds.len() as i32)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Alta o total del jugador; los datos de la sesión (IP, dispositivo) solo se
+/// escriben en la inscripción y aquí no se tocan
+async fn upsert_player(tx: &mut Transaction<'_, Postgres>, tournament_id: &uuid::Uuid, player: &Player) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO players (id, tournament_id, name, total_score) VALUES ($1, $2, $3, $4) \
+         ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name, total_score = EXCLUDED.total_score",
+    )
+    .persistent(false)
+    .bind(player.id)
+    .bind(tournament_id)
+    .bind(&player.name)
+    .bind(player.total_score)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+async fn upsert_play(tx: &mut Transaction<'_, Postgres>, play: &PlayData) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO player_plays (id, tournament_id, player_id, round_number, word, position_row, position_col, \
+             position_down, score, percentage_of_optimal, submitted_at, cumulative_score, difference_from_optimal, \
+             cumulative_difference) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14) \
+         ON CONFLICT (tournament_id, player_id, round_number) DO UPDATE SET word = EXCLUDED.word, \
+             position_row = EXCLUDED.position_row, position_col = EXCLUDED.position_col, \
+             position_down = EXCLUDED.position_down, score = EXCLUDED.score, \
+             percentage_of_optimal = EXCLUDED.percentage_of_optimal, submitted_at = EXCLUDED.submitted_at, \
+             cumulative_score = EXCLUDED.cumulative_score, \
+             difference_from_optimal = EXCLUDED.difference_from_optimal, \
+             cumulative_difference = EXCLUDED.cumulative_difference \
+         WHERE player_plays.submitted_at <= EXCLUDED.submitted_at \
+             AND (player_plays.word, player_plays.position_row, player_plays.position_col, player_plays.position_down, \
+                player_plays.score, player_plays.cumulative_score, player_plays.cumulative_difference) \
+             IS DISTINCT FROM (EXCLUDED.word, EXCLUDED.position_row, EXCLUDED.position_col, EXCLUDED.position_down, \
+                EXCLUDED.score, EXCLUDED.cumulative_score, EXCLUDED.cumulative_difference)",
+    )
+    .persistent(false)
+    .bind(uuid::Uuid::new_v4())
+    .bind(play.tournament_id)
+    .bind(play.player_id)
+    .bind(play.round_number)
+    .bind(&play.word)
+    .bind(play.position_row)
+    .bind(play.position_col)
+    .bind(play.position_down)
+    .bind(play.score)
+    .bind(play.percentage_of_optimal)
+    .bind(play.submitted_at)
+    .bind(play.cumulative_score)
+    .bind(play.difference_from_optimal)
+    .bind(play.cumulative_difference)
+    .execute(&mut **tx)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+async fn upsert_round(tx: &mut Transaction<'_, Postgres>, tournament_id: &uuid::Uuid, round: &Round) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    sqlx::query(
+        "INSERT INTO rounds (id, tournament_id, number, rack, status, optimal_revealed, rack_rejected, start_time) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+         ON CONFLICT (tournament_id, number) DO UPDATE SET rack = EXCLUDED.rack, status = EXCLUDED.status, \
+             optimal_revealed = EXCLUDED.optimal_revealed, rack_rejected = EXCLUDED.rack_rejected, \
+             start_time = EXCLUDED.start_time",
+    )
+    .persistent(false)
+    .bind(uuid::Uuid::new_v4())
+    .bind(tournament_id)
+    .bind(round.number as i32)
+    .bind(&round.rack)
+    .bind(round_status(&round.status))
+    .bind(round.optimal_revealed)
+    .bind(round.rack_rejected)
+    .bind(round.timer_started)
+    .execute(&mut **tx)
+    .await?;
+    
+    sqlx::query(
+        "INSERT INTO board_states (tournament_id, round_number, board_data) VALUES ($1, $2, $3::jsonb) \
+         ON CONFLICT (tournament_id, round_number) DO UPDATE SET board_data = EXCLUDED.board_data",
+    )
+    .persistent(false)
+    .bind(tournament_id)
+    .bind(round.number as i32)
+    .bind(serde_json::to_string(&round.board_state)?)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+async fn upsert_master_play(tx: &mut Transaction<'_, Postgres>, tournament_id: &uuid::Uuid, master: &MasterPlay) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO master_plays (tournament_id, round_number, word, coord, score, direction) \
+         VALUES ($1, $2, $3, $4, $5, $6) \
+         ON CONFLICT (tournament_id, round_number) DO UPDATE SET word = EXCLUDED.word, coord = EXCLUDED.coord, \
+             score = EXCLUDED.score, direction = EXCLUDED.direction",
+    )
+    .persistent(false)
+    .bind(tournament_id)
+    .bind(master.round_number as i32)
+    .bind(&master.word)
+    .bind(crate::tournament_manager::format_coordinate(&master.position))
+    .bind(master.score)
+    .bind(if master.position.down { "down" } else { "across" })
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Postgres de pruebas: esquema temporal con todas las migraciones
+#[cfg(test)]
+pub mod testing {
+    use sqlx::postgres::{PgPool, PgPoolOptions};
+    use sqlx::Executor;
+    use uuid::Uuid;
+
+    /// Crea un esquema temporal en el Postgres de TEST_DATABASE_URL; devuelve el pool y su nombre
+    pub async fn temp_schema_pool(prefix: &str) -> (PgPool, String) {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let schema = format!("{}_{}", prefix, Uuid::new_v4().simple());
+        let admin = PgPool::connect(&url).await.unwrap();
+        sqlx::raw_sql(&format!("CREATE SCHEMA {}", schema)).execute(&admin).await.unwrap();
+
+        let search_path = schema.clone();
+        let pool = PgPoolOptions::new()
+            .max_connections(2)
+            .after_connect(move |conn, _| {
+                let sql = format!("SET search_path TO {}, public", search_path);
+                Box::pin(async move {
+                    conn.execute(sql.as_str()).await?;
+                    Ok(())
+                })
+            })
+            .connect(&url)
+            .await
+            .unwrap();
+        crate::db_migrations::migrate(&pool).await.unwrap();
+        (pool, schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use crate::models::{OptimalPlay, PlayerPlay, Position, TournamentStatus};
+    use crate::models::test_support::active_round;
+
+    fn tournament() -> Tournament {
+        let position = Position { row: 7, col: 7, down: false };
+        let play = PlayerPlay {
+            round_number: 1,
+            word: "CASERO".to_string(),
+            position: position.clone(),
+            score: 24,
+            percentage_of_optimal: 100.0,
+            submitted_at: Utc::now(),
+            cumulative_score: 24,
+            difference_from_optimal: 0,
+            cumulative_difference: 0,
+            rank: None,
+        };
+        let round = |number: u32, status: RoundStatus| Round {
+            optimal_play: Some(OptimalPlay {
+                word: "CASERO".to_string(),
+                position: position.clone(),
+                score: 24,
+                tiles_used: Vec::new(),
+                play_bytes: None,
+                blank_positions: Vec::new(),
+            }),
+            optimal_revealed: status == RoundStatus::Completed,
+            status,
+            timer_started: Some(Utc::now()),
+            ..active_round(number, "ACEORS?")
+        };
+        Tournament {
+            id: Uuid::new_v4(),
+            name: "Relacional".to_string(),
+            created_at: Utc::now(),
+            status: TournamentStatus::InProgress,
+            rounds: vec![round(1, RoundStatus::Completed), round(2, RoundStatus::Active)],
+            players: vec![Player {
+                id: Uuid::new_v4(),
+                name: "Ana".to_string(),
+                total_score: 24,
+                plays: vec![play],
+                category: None,
+                club: None,
+                federation_id: None,
+            }],
+            tiles_remaining: 86,
+            master_plays: vec![MasterPlay { round_number: 1, word: "CASERO".to_string(), position, score: 24, cumulative_score: 24 }],
+            training: false,
         }
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL apuntando a un Postgres local"]
+    async fn test_sync_covers_full_lifecycle() {
+        let (pool, schema) = testing::temp_schema_pool("sync_test").await;
+        let database = Database { pool: pool.clone(), schema_ready: AtomicBool::new(true) };
+        let mut tournament = tournament();
+
+        database.sync_tournament(&tournament).await.unwrap();
+        // Reenviar es idempotente
+        database.sync_tournament(&tournament).await.unwrap();
+
+        let count = |sql: &'static str| {
+            let pool = pool.clone();
+            async move { sqlx::query_scalar::<_, i64>(sql).fetch_one(&pool).await.unwrap() }
+        };
+        assert_eq!(count("SELECT COUNT(*) FROM rounds").await, 2);
+        assert_eq!(count("SELECT COUNT(*) FROM board_states").await, 2);
+        assert_eq!(count("SELECT COUNT(*) FROM master_plays").await, 1);
+        assert_eq!(count("SELECT COUNT(*) FROM player_plays").await, 1);
+
+        // Rechazo del atril y cierre de la ronda 2
+        tournament.rounds[1].rack_rejected = true;
+        tournament.rounds[1].status = RoundStatus::Completed;
+        database.sync_round(&tournament, 2).await.unwrap();
+        let (status, rejected): (String, bool) = sqlx::query_as("SELECT status, rack_rejected FROM rounds WHERE number = 2")
+            .fetch_one(&pool).await.unwrap();
+        assert_eq!((status.as_str(), rejected), ("Completed", true));
+
+        // Deshacer la ronda 2 y terminar el torneo
+        tournament.rounds.pop();
+        tournament.status = TournamentStatus::Finished;
+        tournament.players[0].total_score = 30;
+        database.sync_tournament(&tournament).await.unwrap();
+        database.sync_standings(&tournament).await.unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM rounds").await, 1);
+        assert_eq!(count("SELECT COUNT(*) FROM board_states").await, 1);
+        let (status, total): (String, i32) = sqlx::query_as(
+            "SELECT t.status, p.total_score FROM tournaments t JOIN players p ON p.tournament_id = t.id",
+        ).fetch_one(&pool).await.unwrap();
+        assert_eq!((status.as_str(), total), ("Finished", 30));
+
+        // Inscripción con IP desconocida y comillas en el nombre; repetirla actualiza la sesión
+        let player_id = tournament.players[0].id;
+        database.enroll_player(tournament.id, player_id, "O'Neil'); DROP TABLE players; --".to_string(),
+            "Unknown".to_string(), "Firefox".to_string(), "unknown".to_string()).await.unwrap();
+        database.enroll_player(tournament.id, player_id, "O'Neil".to_string(),
+            "192.168.1.20".to_string(), "Firefox".to_string(), "hw-1".to_string()).await.unwrap();
+        let (name, ip): (String, Option<String>) = sqlx::query_as("SELECT name, host(ip_address) FROM players WHERE id = $1")
+            .bind(player_id).fetch_one(&pool).await.unwrap();
+        assert_eq!((name.as_str(), ip.as_deref()), ("O'Neil", Some("192.168.1.20")));
+
+        sqlx::raw_sql(&format!("DROP SCHEMA {} CASCADE", schema)).execute(&pool).await.unwrap();
+    }
+}
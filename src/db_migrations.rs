@@ -0,0 +1,143 @@
+// Migraciones del esquema Postgres/Supabase
+//
+// Las migraciones viven en migrations/postgres y se incluyen en el binario.
+// Database::new las aplica al conectar (o el primer sondeo de salud, si la base
+// no respondía al arrancar), en una transacción protegida por un
+// candado consultivo (varios servidores pueden arrancar a la vez). Las
+// versiones aplicadas quedan en MIGRATIONS_TABLE; si la base tiene una versión
+// más nueva que las conocidas, el servidor se niega a arrancar.
+
+use std::fmt;
+use sha2::{Digest, Sha256};
+use sqlx::{Executor, PgPool, Row};
+
+pub const MIGRATIONS_TABLE: &str = "server_schema_migrations";
+
+/// Clave del candado consultivo durante la migración
+const MIGRATION_LOCK_KEY: i64 = 0x776f_6c67_6573; // "wolges"
+
+/// (versión, nombre, SQL)
+const MIGRATIONS: &[(i64, &str, &str)] = &[
+    (1, "initial_schema", include_str!("../migrations/postgres/0001_initial_schema.sql")),
+    (2, "players_created_at", include_str!("../migrations/postgres/0002_players_created_at.sql")),
+    (3, "play_ingest_notify", include_str!("../migrations/postgres/0003_play_ingest_notify.sql")),
+];
+
+/// La base fue migrada por un servidor más nuevo
+#[derive(Debug)]
+pub struct SchemaTooNew {
+    pub found: i64,
+    pub supported: i64,
+}
+
+impl fmt::Display for SchemaTooNew {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "La base de datos usa la migración {} y este servidor solo conoce hasta la {}; actualice el servidor",
+            self.found, self.supported
+        )
+    }
+}
+
+impl std::error::Error for SchemaTooNew {}
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|(version, _, _)| *version).unwrap_or(0)
+}
+
+fn checksum(sql: &str) -> String {
+    format!("{:x}", Sha256::digest(sql.as_bytes()))
+}
+
+/// Aplica las migraciones pendientes; devuelve las versiones aplicadas
+pub async fn migrate(pool: &PgPool) -> Result<Vec<i64>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .persistent(false)
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *tx)
+        .await?;
+
+    // Executor::execute devuelve un futuro Send; RawSql::execute no lo es para
+    // el compilador y migrate también se llama desde el monitor de salud
+    let create_table = format!(
+        "CREATE TABLE IF NOT EXISTS {} (\
+             version BIGINT PRIMARY KEY, \
+             name VARCHAR(100) NOT NULL, \
+             checksum VARCHAR(64) NOT NULL, \
+             applied_at TIMESTAMPTZ DEFAULT NOW())",
+        MIGRATIONS_TABLE
+    );
+    tx.execute(sqlx::raw_sql(&create_table)).await?;
+
+    let applied: Vec<(i64, String)> = sqlx::query(&format!("SELECT version, checksum FROM {} ORDER BY version", MIGRATIONS_TABLE))
+        .persistent(false)
+        .fetch_all(&mut *tx)
+        .await?
+        .iter()
+        .map(|row| (row.get("version"), row.get("checksum")))
+        .collect();
+
+    if let Some((found, _)) = applied.last().filter(|(v, _)| *v > latest_version()) {
+        return Err(Box::new(SchemaTooNew { found: *found, supported: latest_version() }));
+    }
+
+    let mut newly_applied = Vec::new();
+    for (version, name, sql) in MIGRATIONS {
+        if let Some((_, stored)) = applied.iter().find(|(v, _)| v == version) {
+            if *stored != checksum(sql) {
+                log::warn!("La migración {} ({}) cambió después de aplicarse", version, name);
+            }
+            continue;
+        }
+
+        tx.execute(sqlx::raw_sql(sql)).await
+            .map_err(|e| format!("Migración {} ({}) fallida: {}", version, name, e))?;
+        sqlx::query(&format!("INSERT INTO {} (version, name, checksum) VALUES ($1, $2, $3)", MIGRATIONS_TABLE))
+            .persistent(false)
+            .bind(version)
+            .bind(name)
+            .bind(checksum(sql))
+            .execute(&mut *tx)
+            .await?;
+        log::info!("Migración {} ({}) aplicada", version, name);
+        newly_applied.push(*version);
+    }
+
+    tx.commit().await?;
+    Ok(newly_applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::testing::temp_schema_pool;
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        for pair in MIGRATIONS.windows(2) {
+            assert!(pair[0].0 < pair[1].0);
+        }
+        assert_eq!(latest_version(), MIGRATIONS.len() as i64);
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL apuntando a un Postgres local"]
+    async fn test_migrate_is_idempotent_and_rejects_newer_schema() {
+        let (pool, schema) = temp_schema_pool("migrate_test").await;
+
+        // temp_schema_pool ya migró
+        assert!(migrate(&pool).await.unwrap().is_empty());
+
+        sqlx::query(&format!("INSERT INTO {} (version, name, checksum) VALUES ($1, 'futura', '')", MIGRATIONS_TABLE))
+            .bind(latest_version() + 1)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let err = migrate(&pool).await.unwrap_err();
+        assert!(err.downcast_ref::<SchemaTooNew>().is_some());
+
+        sqlx::raw_sql(&format!("DROP SCHEMA {} CASCADE", schema)).execute(&pool).await.unwrap();
+    }
+}
@@ -0,0 +1,221 @@
+// Cola de mensajes muertos para jugadas que no se pudieron subir a la nube
+//
+// Cuando AsyncQueue agota sus reintentos, la jugada se guarda en
+// tournaments/dead_letter.json para no perderla. Un reintento en segundo plano
+// la vuelve a enviar con espera exponencial mientras la nube esté disponible;
+// el árbitro puede inspeccionar, reintentar o descartar entradas a mano.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use crate::async_queue::PlayData;
+
+pub const DEFAULT_DEAD_LETTER_PATH: &str = "tournaments/dead_letter.json";
+
+/// Espera inicial y máxima entre reintentos automáticos
+const BASE_BACKOFF_SECS: i64 = 30;
+const MAX_BACKOFF_SECS: i64 = 30 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub id: Uuid,
+    pub play_data: PlayData,
+    pub last_error: String,
+    /// Reintentos desde que la jugada entró a la cola de muertos
+    pub attempts: u32,
+    pub failed_at: DateTime<Utc>,
+    pub next_retry_at: DateTime<Utc>,
+}
+
+impl DeadLetter {
+    fn backoff(attempts: u32) -> Duration {
+        let secs = BASE_BACKOFF_SECS.saturating_mul(1_i64 << attempts.min(16));
+        Duration::seconds(secs.min(MAX_BACKOFF_SECS))
+    }
+}
+
+pub struct DeadLetterStore {
+    path: PathBuf,
+    entries: Mutex<Vec<DeadLetter>>,
+}
+
+impl DeadLetterStore {
+    /// Abre el archivo de mensajes muertos; si no existe se empieza vacío
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let entries = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| format!("No se pudo leer {}: {}", path.display(), e))?;
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Cola de mensajes muertos corrupta ({}): {}", path.display(), e))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self { path, entries: Mutex::new(entries) })
+    }
+
+    /// Ruta por defecto (o DEAD_LETTER_PATH). Un archivo ilegible se aparta
+    /// con otro nombre para revisarlo a mano y se empieza con la cola vacía.
+    pub fn open_default() -> Self {
+        let path = PathBuf::from(std::env::var("DEAD_LETTER_PATH").unwrap_or_else(|_| DEFAULT_DEAD_LETTER_PATH.to_string()));
+        match Self::open(&path) {
+            Ok(store) => store,
+            Err(e) => {
+                let aside = path.with_extension(format!("corrupt-{}", Utc::now().format("%Y%m%d_%H%M%S")));
+                log::error!("{}; se aparta como {}", e, aside.display());
+                if let Err(e) = fs::rename(&path, &aside) {
+                    log::error!("No se pudo apartar {}: {}", path.display(), e);
+                }
+                Self { path, entries: Mutex::new(Vec::new()) }
+            }
+        }
+    }
+
+    /// Escribe a un temporal y renombra, para no dejar el archivo a medias
+    fn flush(&self, entries: &[DeadLetter]) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(entries).map_err(|e| e.to_string())?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, json).map_err(|e| e.to_string())?;
+        fs::rename(&tmp, &self.path).map_err(|e| e.to_string())
+    }
+
+    /// Guarda una jugada fallida. Las entradas anteriores del mismo jugador y
+    /// ronda quedan reemplazadas: reenviarlas solo podría pisar la más reciente.
+    pub async fn push(&self, play_data: PlayData, error: String) -> Result<DeadLetter, String> {
+        let now = Utc::now();
+        let entry = DeadLetter {
+            id: Uuid::new_v4(),
+            play_data,
+            last_error: error,
+            attempts: 0,
+            failed_at: now,
+            next_retry_at: now + DeadLetter::backoff(0),
+        };
+
+        let mut entries = self.entries.lock().await;
+        entries.retain(|e| !Self::superseded_by(&e.play_data, &entry.play_data));
+        entries.push(entry.clone());
+        self.flush(&entries)?;
+        Ok(entry)
+    }
+
+    fn superseded_by(old: &PlayData, new: &PlayData) -> bool {
+        old.tournament_id == new.tournament_id
+            && old.player_id == new.player_id
+            && old.round_number == new.round_number
+            && old.submitted_at <= new.submitted_at
+    }
+
+    pub async fn list(&self) -> Vec<DeadLetter> {
+        self.entries.lock().await.clone()
+    }
+
+    pub async fn get(&self, id: &Uuid) -> Option<DeadLetter> {
+        self.entries.lock().await.iter().find(|e| e.id == *id).cloned()
+    }
+
+    pub async fn len(&self) -> usize {
+        self.entries.lock().await.len()
+    }
+
+    /// Entradas cuyo próximo reintento ya venció
+    pub async fn due(&self, now: DateTime<Utc>) -> Vec<DeadLetter> {
+        self.entries.lock().await.iter()
+            .filter(|e| e.next_retry_at <= now)
+            .cloned()
+            .collect()
+    }
+
+    /// Quita una entrada (subida con éxito o descartada por el árbitro)
+    pub async fn remove(&self, id: &Uuid) -> Result<Option<DeadLetter>, String> {
+        let mut entries = self.entries.lock().await;
+        let Some(pos) = entries.iter().position(|e| e.id == *id) else {
+            return Ok(None);
+        };
+        let removed = entries.remove(pos);
+        self.flush(&entries)?;
+        Ok(Some(removed))
+    }
+
+    /// Registra un reintento fallido y programa el siguiente con espera exponencial
+    pub async fn record_failure(&self, id: &Uuid, error: String) -> Result<(), String> {
+        let mut entries = self.entries.lock().await;
+        if let Some(entry) = entries.iter_mut().find(|e| e.id == *id) {
+            entry.attempts += 1;
+            entry.last_error = error;
+            entry.next_retry_at = Utc::now() + DeadLetter::backoff(entry.attempts);
+        }
+        self.flush(&entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play() -> PlayData {
+        PlayData {
+            tournament_id: Uuid::new_v4(),
+            player_id: Uuid::new_v4(),
+            round_number: 1,
+            word: "CASA".to_string(),
+            position_row: 7,
+            position_col: 7,
+            position_down: false,
+            score: 12,
+            percentage_of_optimal: 50.0,
+            cumulative_score: 12,
+            difference_from_optimal: 12,
+            cumulative_difference: 12,
+            submitted_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_survive_reopen() {
+        let path = std::env::temp_dir().join(format!("dead_letter_{}.json", Uuid::new_v4()));
+        let store = DeadLetterStore::open(&path).unwrap();
+        let first = store.push(play(), "timeout".to_string()).await.unwrap();
+        let second = store.push(play(), "timeout".to_string()).await.unwrap();
+        assert!(store.due(Utc::now()).await.is_empty());
+
+        store.record_failure(&first.id, "connection refused".to_string()).await.unwrap();
+        store.remove(&second.id).await.unwrap();
+
+        let reopened = DeadLetterStore::open(&path).unwrap();
+        let entries = reopened.list().await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].attempts, 1);
+        assert_eq!(entries[0].last_error, "connection refused");
+        assert!(entries[0].next_retry_at >= first.next_retry_at);
+
+        fs::remove_file(&path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_newer_play_supersedes_dead_letter() {
+        let path = std::env::temp_dir().join(format!("dead_letter_{}.json", Uuid::new_v4()));
+        let store = DeadLetterStore::open(&path).unwrap();
+        let first = play();
+        let mut resubmitted = first.clone();
+        resubmitted.word = "CASAS".to_string();
+        resubmitted.submitted_at = first.submitted_at + Duration::seconds(5);
+
+        store.push(first.clone(), "timeout".to_string()).await.unwrap();
+        store.push(play(), "timeout".to_string()).await.unwrap();
+        store.push(resubmitted, "timeout".to_string()).await.unwrap();
+
+        let entries = store.list().await;
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.play_data.word == "CASAS" || e.play_data.player_id != first.player_id));
+
+        fs::remove_file(&path).ok();
+    }
+}
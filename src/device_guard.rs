@@ -0,0 +1,96 @@
+// Detección de dispositivos compartidos entre jugadores
+//
+// Un dispositivo se identifica por el hardware_id del navegador o, si no
+// existe, por la combinación IP + User-Agent.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct DevicePolicy {
+    /// Alertar cuando un dispositivo inscribe o juega por varios jugadores
+    pub detect_shared_device: bool,
+    /// Alertar cuando un jugador envía desde varios dispositivos en una ronda
+    pub detect_multi_device_player: bool,
+    /// Rechazar la inscripción o jugada además de alertar
+    pub block: bool,
+}
+
+impl Default for DevicePolicy {
+    fn default() -> Self {
+        Self {
+            detect_shared_device: true,
+            detect_multi_device_player: true,
+            block: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum DeviceAlertKind {
+    /// Un dispositivo inscribió a varios jugadores
+    SharedEnrollment,
+    /// Un dispositivo envió jugadas de varios jugadores en la misma ronda
+    SharedSubmission,
+    /// Un jugador envió desde varios dispositivos en la misma ronda
+    MultiDevicePlayer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceAlert {
+    pub id: Uuid,
+    pub kind: DeviceAlertKind,
+    pub player_ids: Vec<Uuid>,
+    pub round_number: Option<u32>,
+    pub device: String,
+    pub message: String,
+    pub blocked: bool,
+    pub acknowledged: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl DeviceAlert {
+    pub fn new(kind: DeviceAlertKind, player_ids: Vec<Uuid>, round_number: Option<u32>, device: &str, message: String, blocked: bool) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind,
+            player_ids,
+            round_number,
+            device: device.to_string(),
+            message,
+            blocked,
+            acknowledged: false,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Dispositivos desde los que un jugador envió en una ronda (se guardan en el snapshot)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RoundDevices {
+    pub player_id: Uuid,
+    pub round_number: u32,
+    pub devices: Vec<String>,
+}
+
+/// Construye la clave del dispositivo; `None` si no hay datos para identificarlo
+pub fn device_key(hardware_id: Option<&str>, ip_address: &str, user_agent: &str) -> Option<String> {
+    match hardware_id.map(str::trim) {
+        Some(hw) if !hw.is_empty() && !hw.eq_ignore_ascii_case("unknown") => Some(format!("hw:{}", hw)),
+        _ if ip_address != "Unknown" => Some(format!("ip:{}|{}", ip_address, user_agent)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_key() {
+        assert_eq!(device_key(Some("abc"), "10.0.0.2", "Firefox"), Some("hw:abc".to_string()));
+        assert_eq!(device_key(Some(""), "10.0.0.2", "Firefox"), Some("ip:10.0.0.2|Firefox".to_string()));
+        assert_eq!(device_key(None, "Unknown", "Unknown"), None);
+    }
+}
@@ -0,0 +1,217 @@
+// Eventos en tiempo real del torneo
+//
+// Cada torneo tiene un canal broadcast; el WebSocket /ws/tournament/{id}
+// se suscribe y reenvía los eventos como JSON con la forma
+// {"type": "...", "data": {...}}.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast;
+use tokio::task::AbortHandle;
+use uuid::Uuid;
+use crate::models::{BoardState, OptimalPlay, Round, RoundStatus, TournamentStatus};
+use crate::persistence_mode::PersistenceMode;
+use crate::round_stats::RoundStats;
+
+/// Duración de la ronda en segundos
+pub const ROUND_TIMER_SECS: i64 = 180;
+/// Segundos restantes en los que se emite el aviso de fin de ronda
+pub const TIMER_WARNING_SECS: i64 = 30;
+
+const CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub enum TimerPhase {
+    Started,
+    Warning,
+    Expired,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LeaderboardEntry {
+    pub player_id: Uuid,
+    pub name: String,
+    pub total_score: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundView {
+    pub number: u32,
+    pub rack: String,
+    pub status: RoundStatus,
+    pub rack_rejected: bool,
+    pub rejection_reason: Option<String>,
+    pub timer_started: Option<DateTime<Utc>>,
+    pub optimal_revealed: bool,
+    pub master: Option<OptimalPlay>,  // Solo si ya fue revelada
+}
+
+impl RoundView {
+    pub fn from_round(round: &Round) -> Self {
+        Self {
+            number: round.number,
+            rack: round.rack.clone(),
+            status: round.status.clone(),
+            rack_rejected: round.rack_rejected,
+            rejection_reason: round.rejection_reason.clone(),
+            timer_started: round.timer_started,
+            optimal_revealed: round.optimal_revealed,
+            master: if round.optimal_revealed { round.optimal_play.clone() } else { None },
+        }
+    }
+}
+
+/// Estado completo enviado al conectar o reconectar
+#[derive(Debug, Clone, Serialize)]
+pub struct StateSnapshot {
+    pub tournament_id: Uuid,
+    pub name: String,
+    pub status: TournamentStatus,
+    pub tiles_remaining: u8,
+    pub current_round: Option<RoundView>,
+    pub board: BoardState,
+    pub submitted: usize,
+    pub total_players: usize,
+    pub leaderboard: Vec<LeaderboardEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum TournamentEvent {
+    State(StateSnapshot),
+    RoundStarted { round: RoundView },
+    RackUpdated { round_number: u32, rack: String },
+    RackRejected { round_number: u32, rack: String, reason: Option<String> },
+    TimerPhase { round_number: u32, phase: TimerPhase, started_at: DateTime<Utc>, seconds_remaining: i64 },
+    SubmissionCount { round_number: u32, submitted: usize, total: usize },
+    Nudge { round_number: u32, player_ids: Vec<Uuid>, seconds_remaining: i64 },
+    MasterRevealed { round_number: u32, master: OptimalPlay },
+    BoardUpdated { round_number: u32, board: BoardState },
+    /// Estadísticas de la ronda tras revelar el master
+    RoundStats { stats: RoundStats },
+    LeaderboardChanged { leaderboard: Vec<LeaderboardEntry> },
+    TournamentFinished { reason: Option<String> },
+    Heartbeat { timestamp: DateTime<Utc> },
+    /// Cambio en la disponibilidad de la nube (monitor de salud)
+    CloudStatus { available: bool, mode: PersistenceMode, at: DateTime<Utc> },
+}
+
+/// Canales broadcast por torneo
+#[derive(Clone, Default)]
+pub struct EventHub {
+    channels: Arc<Mutex<HashMap<Uuid, broadcast::Sender<TournamentEvent>>>>,
+    timers: Arc<Mutex<HashMap<(Uuid, u32), AbortHandle>>>,  // Avisos pendientes por (torneo, ronda)
+}
+
+impl EventHub {
+    pub fn subscribe(&self, tournament_id: &Uuid) -> broadcast::Receiver<TournamentEvent> {
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        channels.entry(*tournament_id)
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Publica un evento; sin suscriptores no hace nada
+    pub fn publish(&self, tournament_id: &Uuid, event: TournamentEvent) {
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(sender) = channels.get(tournament_id) {
+            if sender.send(event).is_err() {
+                // Todos los suscriptores se desconectaron
+                channels.remove(tournament_id);
+            }
+        }
+    }
+
+    /// Programa los avisos de aviso y fin del temporizador de una ronda,
+    /// reemplazando los que hubiera de un inicio anterior
+    pub fn spawn_timer_phases(&self, tournament_id: Uuid, round_number: u32, started_at: DateTime<Utc>) {
+        self.cancel_timer(&tournament_id, round_number);
+        // Fuera de un runtime (p. ej. en pruebas) no hay avisos programados
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let hub = self.clone();
+        let task = runtime.spawn(async move {
+            for (phase, at_secs) in [
+                (TimerPhase::Warning, ROUND_TIMER_SECS - TIMER_WARNING_SECS),
+                (TimerPhase::Expired, ROUND_TIMER_SECS),
+            ] {
+                let elapsed = Utc::now().signed_duration_since(started_at).num_milliseconds();
+                let wait_ms = (at_secs * 1000 - elapsed).max(0) as u64;
+                tokio::time::sleep(std::time::Duration::from_millis(wait_ms)).await;
+                hub.publish(&tournament_id, TournamentEvent::TimerPhase {
+                    round_number,
+                    phase,
+                    started_at,
+                    seconds_remaining: ROUND_TIMER_SECS - at_secs,
+                });
+            }
+            // Quitar la entrada solo si sigue siendo la de esta tarea
+            let mut timers = hub.timers.lock().unwrap_or_else(|e| e.into_inner());
+            if timers.get(&(tournament_id, round_number)).is_some_and(|t| t.id() == tokio::task::id()) {
+                timers.remove(&(tournament_id, round_number));
+            }
+        });
+        self.timers.lock().unwrap_or_else(|e| e.into_inner())
+            .insert((tournament_id, round_number), task.abort_handle());
+    }
+
+    #[cfg(test)]
+    pub fn timer_pending(&self, tournament_id: &Uuid, round_number: u32) -> bool {
+        self.timers.lock().unwrap_or_else(|e| e.into_inner()).contains_key(&(*tournament_id, round_number))
+    }
+
+    /// Cancela los avisos pendientes del temporizador de una ronda
+    pub fn cancel_timer(&self, tournament_id: &Uuid, round_number: u32) {
+        if let Some(task) = self.timers.lock().unwrap_or_else(|e| e.into_inner()).remove(&(*tournament_id, round_number)) {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_publish_reaches_subscribers() {
+        let hub = EventHub::default();
+        let id = Uuid::new_v4();
+        hub.publish(&id, TournamentEvent::TournamentFinished { reason: None });
+
+        let mut rx = hub.subscribe(&id);
+        hub.publish(&id, TournamentEvent::RackUpdated { round_number: 1, rack: "AEIOUST".to_string() });
+        match rx.recv().await {
+            Ok(TournamentEvent::RackUpdated { round_number, .. }) => assert_eq!(round_number, 1),
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_event_json_shape() {
+        let event = TournamentEvent::SubmissionCount { round_number: 3, submitted: 2, total: 5 };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "SubmissionCount");
+        assert_eq!(json["data"]["submitted"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_restarted_timer_replaces_pending_phases() {
+        let hub = EventHub::default();
+        let id = Uuid::new_v4();
+        let pending = |hub: &EventHub| hub.timers.lock().unwrap().get(&(id, 1)).cloned();
+
+        hub.spawn_timer_phases(id, 1, Utc::now());
+        let first = pending(&hub).unwrap();
+        hub.spawn_timer_phases(id, 1, Utc::now());
+        let second = pending(&hub).unwrap();
+        assert_ne!(first.id(), second.id());
+
+        hub.cancel_timer(&id, 1);
+        tokio::task::yield_now().await;
+        assert!(first.is_finished() && second.is_finished());
+        assert!(pending(&hub).is_none());
+    }
+}
@@ -0,0 +1,328 @@
+// Exportación de partidas en formato GCG
+//
+// La partida master se escribe como una partida de un solo jugador: una línea
+// ">Master: ATRIL COORD PALABRA +PTS ACUM" por cada ronda con master colocado.
+// En la partida de un jugador el tablero sigue siendo el del master (así es el
+// duplicado), y su jugada de cada ronda se anota como #note bajo la del master.
+//
+// Las fichas usan las etiquetas del alfabeto español de wolges: dígrafos como
+// [CH]/[LL]/[RR], comodines en minúscula y '.' para las fichas que ya estaban
+// en el tablero; las coordenadas siguen la convención GCG (8H horizontal, H8
+// vertical), no la de format_coordinate.
+
+use uuid::Uuid;
+use wolges::display;
+use crate::models::{BoardState, MasterPlay, Player, Position, Round, Tournament};
+
+const MASTER_NICK: &str = "Master";
+const BOARD_DIM: usize = 15;
+
+/// Ronda con master colocado, con el tablero tal como estaba antes de la jugada
+pub(crate) struct MasterTurn<'a> {
+    pub round: &'a Round,
+    pub master: &'a MasterPlay,
+    /// Palabra en notación GCG ('.' para las fichas que ya estaban)
+    pub word: String,
+    board_before: Vec<String>,
+}
+
+impl MasterTurn<'_> {
+    /// Fichas que el master sacó del atril (comodines en minúscula)
+    pub fn placed_tiles(&self) -> Vec<String> {
+        split_tiles(&self.word).into_iter().filter(|t| t != ".").collect()
+    }
+
+    /// Tablero tal como estaba antes del master
+    pub fn board_before(&self) -> BoardState {
+        BoardState { tiles: self.board_before.clone() }
+    }
+
+    /// Fichas que pondría otra jugada sobre el mismo tablero; None si se sale
+    pub fn tiles_placed_by(&self, position: &Position, word: &str) -> Option<Vec<String>> {
+        let word = gcg_word(&self.board_before, position, word)?;
+        Some(split_tiles(&word).into_iter().filter(|t| t != ".").collect())
+    }
+}
+
+/// Separa una palabra en fichas; ignora los paréntesis con que wolges marca
+/// las fichas que ya estaban en el tablero
+pub(crate) fn split_tiles(word: &str) -> Vec<String> {
+    let mut tiles = Vec::new();
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' | ')' => {}
+            '[' => {
+                let digraph: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                tiles.push(format!("[{}]", digraph));
+            }
+            c => tiles.push(c.to_string()),
+        }
+    }
+    tiles
+}
+
+fn square(position: &Position, offset: usize) -> Option<usize> {
+    let (row, col) = if position.down {
+        (position.row as usize + offset, position.col as usize)
+    } else {
+        (position.row as usize, position.col as usize + offset)
+    };
+    (row < BOARD_DIM && col < BOARD_DIM).then_some(row * BOARD_DIM + col)
+}
+
+/// Coordenada GCG: fila + columna en horizontal, columna + fila en vertical
+pub fn gcg_coordinate(position: &Position) -> String {
+    let column = display::column(position.col as i8);
+    if position.down {
+        format!("{}{}", column, position.row + 1)
+    } else {
+        format!("{}{}", position.row + 1, column)
+    }
+}
+
+/// Palabra en notación GCG sobre el tablero dado; None si se sale del tablero
+pub(crate) fn gcg_word(board: &[String], position: &Position, word: &str) -> Option<String> {
+    let mut gcg = String::new();
+    for (offset, tile) in split_tiles(word).iter().enumerate() {
+        let idx = square(position, offset)?;
+        if board[idx].is_empty() {
+            gcg.push_str(tile);
+        } else {
+            gcg.push('.');
+        }
+    }
+    Some(gcg)
+}
+
+pub(crate) fn place_word(board: &mut [String], position: &Position, word: &str) {
+    for (offset, tile) in split_tiles(word).into_iter().enumerate() {
+        if let Some(idx) = square(position, offset) {
+            if board[idx].is_empty() {
+                board[idx] = tile;
+            }
+        }
+    }
+}
+
+/// Reconstruye la partida master a partir de sus jugadas, sin depender de
+/// los board_state guardados en cada ronda
+pub(crate) fn master_turns(tournament: &Tournament) -> Vec<MasterTurn<'_>> {
+    let mut master_plays: Vec<_> = tournament.master_plays.iter().collect();
+    master_plays.sort_by_key(|mp| mp.round_number);
+
+    let mut board = vec![String::new(); BOARD_DIM * BOARD_DIM];
+    let mut turns = Vec::new();
+    for master in master_plays {
+        let Some(round) = tournament.rounds.iter().find(|r| r.number == master.round_number) else {
+            continue;
+        };
+        let word = gcg_word(&board, &master.position, &master.word)
+            .unwrap_or_else(|| master.word.clone());
+        turns.push(MasterTurn { round, master, word, board_before: board.clone() });
+        place_word(&mut board, &master.position, &master.word);
+    }
+    turns
+}
+
+/// Los campos de la cabecera GCG no admiten saltos de línea
+pub(crate) fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Apodo GCG: una sola palabra
+fn nickname(name: &str) -> String {
+    let nick = name.split_whitespace().collect::<Vec<_>>().join("_");
+    if nick.is_empty() { "Jugador".to_string() } else { nick }
+}
+
+fn header(tournament: &Tournament, description: &str) -> String {
+    format!(
+        "#character-encoding UTF-8\n#player1 {} {}\n#title {}\n#description {}\n",
+        MASTER_NICK,
+        MASTER_NICK,
+        single_line(&tournament.name),
+        single_line(description),
+    )
+}
+
+fn master_line(turn: &MasterTurn) -> String {
+    format!(
+        ">{}: {} {} {} +{} {}\n",
+        MASTER_NICK,
+        turn.round.rack,
+        gcg_coordinate(&turn.master.position),
+        turn.word,
+        turn.master.score,
+        turn.master.cumulative_score
+    )
+}
+
+/// Partida master completa (solo rondas con master colocado)
+pub fn master_game(tournament: &Tournament) -> String {
+    let mut gcg = header(tournament, &format!("Partida master, {} rondas", tournament.master_plays.len()));
+    for turn in master_turns(tournament) {
+        gcg.push_str(&master_line(&turn));
+    }
+    gcg
+}
+
+fn player_note(player: &Player, nick: &str, turn: &MasterTurn) -> String {
+    let Some(play) = player.plays.iter().find(|p| p.round_number == turn.master.round_number) else {
+        return format!("#note Ronda {} - {}: sin jugada\n", turn.master.round_number, nick);
+    };
+
+    let word = play.played_word();
+    let word = gcg_word(&turn.board_before, &play.position, word).unwrap_or_else(|| word.to_string());
+    let detail = if play.is_late() {
+        "fuera de tiempo".to_string()
+    } else if play.is_invalid() {
+        "inválida".to_string()
+    } else {
+        format!("{:.1}% del master, {:+}", play.percentage_of_optimal, -play.difference_from_optimal)
+    };
+    format!(
+        "#note Ronda {} - {}: {} {} +{} {} ({})\n",
+        turn.master.round_number,
+        nick,
+        gcg_coordinate(&play.position),
+        word,
+        play.score,
+        play.cumulative_score,
+        detail
+    )
+}
+
+/// Partida master con la jugada del jugador anotada en cada ronda
+pub fn player_game(tournament: &Tournament, player_id: &Uuid) -> Result<String, String> {
+    let player = tournament.players.iter()
+        .find(|p| &p.id == player_id)
+        .ok_or("Player not found")?;
+    let nick = nickname(&player.name);
+
+    let mut gcg = header(
+        tournament,
+        &format!("Partida de {} ({} puntos); sus jugadas van como notas de cada ronda", player.name, player.total_score),
+    );
+    for turn in master_turns(tournament) {
+        gcg.push_str(&master_line(&turn));
+        gcg.push_str(&player_note(player, &nick, &turn));
+    }
+    Ok(gcg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use wolges::alphabet;
+    use crate::models::{PlayerPlay, TournamentStatus};
+    use crate::models::test_support::{completed_round as round, master};
+
+    fn tournament() -> Tournament {
+        let play = PlayerPlay {
+            round_number: 2,
+            word: "[ch]ARCOS".to_string(),
+            position: Position { row: 3, col: 7, down: true },
+            score: 20,
+            percentage_of_optimal: 80.0,
+            submitted_at: Utc::now(),
+            cumulative_score: 40,
+            difference_from_optimal: 5,
+            cumulative_difference: 5,
+            rank: None,
+        };
+        Tournament {
+            id: Uuid::new_v4(),
+            name: "Abierto de\nprueba".to_string(),
+            created_at: Utc::now(),
+            status: TournamentStatus::InProgress,
+            rounds: vec![round(1, "[CH]ARCOS"), round(2, "A?ESTA[RR]"), round(3, "[LL]AVEROS")],
+            players: vec![Player { id: Uuid::new_v4(), name: "Ana Pérez".to_string(), total_score: 40, plays: vec![play], category: None, club: None, federation_id: None }],
+            tiles_remaining: 80,
+            master_plays: vec![
+                master(1, "[CH]ARCO", 7, 7, false, 28, 28),
+                // wolges marca entre paréntesis las fichas ya colocadas
+                master(2, "[rr]AS(C)ATE", 4, 10, true, 25, 53),
+            ],
+            training: false,
+        }
+    }
+
+    /// Lee una línea de jugada con los lectores de alfabeto de wolges y la
+    /// aplica al tablero
+    fn replay_line(line: &str, board: &mut [u8]) {
+        let alphabet = alphabet::make_spanish_alphabet();
+        let racks = alphabet::AlphabetReader::new_for_racks(&alphabet);
+        let plays = alphabet::AlphabetReader::new_for_plays(&alphabet);
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        assert_eq!(tokens.len(), 6, "{}", line);
+        let mut rack = Vec::new();
+        racks.set_word(tokens[1], &mut rack).unwrap();
+        assert!(rack.len() <= 7);
+
+        let coord = tokens[2].as_bytes();
+        let (down, row, col) = if coord[0].is_ascii_digit() {
+            let digits = coord.iter().take_while(|c| c.is_ascii_digit()).count();
+            let col = display::str_to_column_usize_ignore_case(&coord[digits..]).unwrap();
+            (false, tokens[2][..digits].parse::<usize>().unwrap() - 1, col)
+        } else {
+            let col = display::str_to_column_usize_ignore_case(&coord[..1]).unwrap();
+            (true, tokens[2][1..].parse::<usize>().unwrap() - 1, col)
+        };
+
+        let word = tokens[3].as_bytes();
+        let (mut ix, mut offset) = (0, 0);
+        while ix < word.len() {
+            let idx = if down { (row + offset) * 15 + col } else { row * 15 + col + offset };
+            if word[ix] == b'.' {
+                assert_ne!(board[idx], 0, "'.' sobre casilla vacía en {}", line);
+                ix += 1;
+            } else {
+                let (tile, next) = plays.next_tile(word, ix).unwrap();
+                assert_eq!(board[idx], 0, "ficha sobre casilla ocupada en {}", line);
+                board[idx] = tile;
+                ix = next;
+            }
+            offset += 1;
+        }
+        assert!(tokens[4].starts_with('+'));
+        tokens[5].parse::<i32>().unwrap();
+    }
+
+    #[test]
+    fn test_master_game_round_trips_with_wolges_readers() {
+        let tournament = tournament();
+        let gcg = master_game(&tournament);
+        assert!(gcg.contains("#title Abierto de prueba\n"));
+
+        let moves: Vec<&str> = gcg.lines().filter(|l| l.starts_with('>')).collect();
+        assert_eq!(moves, vec![
+            ">Master: [CH]ARCOS 8H [CH]ARCO +28 28",
+            ">Master: A?ESTA[RR] K5 [rr]AS.ATE +25 53",
+        ]);
+
+        let mut board = vec![0u8; 225];
+        for line in &moves {
+            replay_line(line, &mut board);
+        }
+        let alphabet = alphabet::make_spanish_alphabet();
+        assert_eq!(alphabet.of_board(board[7 * 15 + 7]), Some("[CH]"));
+        assert_eq!(alphabet.of_board(board[4 * 15 + 10]), Some("[rr]"));
+    }
+
+    #[test]
+    fn test_player_game_annotates_each_round() {
+        let tournament = tournament();
+        let player_id = tournament.players[0].id;
+        let gcg = player_game(&tournament, &player_id).unwrap();
+
+        let notes: Vec<&str> = gcg.lines().filter(|l| l.starts_with("#note")).collect();
+        assert_eq!(notes, vec![
+            "#note Ronda 1 - Ana_Pérez: sin jugada",
+            "#note Ronda 2 - Ana_Pérez: H4 [ch]ARC.S +20 40 (80.0% del master, -5)",
+        ]);
+        assert!(player_game(&tournament, &Uuid::new_v4()).is_err());
+    }
+}
@@ -0,0 +1,378 @@
+// Diario de eventos del torneo (append-only)
+//
+// Cada cambio de estado se agrega como una línea JSON en
+// tournaments/<dir>/journal.jsonl. El snapshot tournament.json pasa a ser un
+// punto de control: al cargar se aplican las entradas posteriores a su
+// `journal_seq`. Reproducir el diario desde el inicio reconstruye el torneo.
+// Con clave de firma cada entrada lleva su HMAC, y al cargar se rechazan las
+// entradas alteradas, sin firma o con huecos en la secuencia.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use crate::models::{BoardState, MasterPlay, OptimalPlay, Player, PlayerPlay, Round, RoundStatus, Tournament, TournamentStatus};
+
+pub const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// Cada cuántas entradas sin snapshot se escribe un punto de control
+pub const CHECKPOINT_INTERVAL: u64 = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum JournalEvent {
+    /// Estado base: creación del torneo o primer registro de un torneo antiguo
+    Checkpoint { tournament: Tournament },
+    PlayerAdded { player: Player },
+    RoundStarted { round: Round, tiles_remaining: u8 },
+    RackUpdated { round_number: u32, rack: String, tiles_remaining: u8 },
+    RackRejected { round_number: u32, rack: String, rejection_reason: Option<String>, tiles_remaining: u8 },
+    TimerStarted { round_number: u32, started_at: DateTime<Utc> },
+    PlaySubmitted { player_id: Uuid, play: PlayerPlay },
+    MasterRevealed { round_number: u32 },
+    MasterPlaced { round_number: u32, optimal_play: OptimalPlay, master_play: MasterPlay, board: BoardState, finished: bool },
+    TournamentFinished,
+    RoundUndone { round_number: u32 },
+    ScoreAdjusted { player_id: Uuid, round_number: u32, score: i32, reason: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub seq: u64,
+    pub recorded_at: DateTime<Utc>,
+    pub event: JournalEvent,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mac: Option<String>,  // HMAC-SHA256 de la entrada con la clave de los snapshots
+}
+
+/// Campos que cubre el HMAC: la entrada tal como se serializa sin el propio HMAC
+#[derive(Serialize)]
+struct SignedEntry<'a> {
+    seq: u64,
+    recorded_at: &'a DateTime<Utc>,
+    event: &'a JournalEvent,
+}
+
+impl JournalEntry {
+    fn signed_content(&self) -> Result<String, String> {
+        serde_json::to_string(&SignedEntry { seq: self.seq, recorded_at: &self.recorded_at, event: &self.event })
+            .map_err(|e| e.to_string())
+    }
+
+    /// Calcula el HMAC de la entrada con la clave del servidor
+    pub fn sign(&mut self, key: &str) -> Result<(), String> {
+        self.mac = Some(hmac_hex(key, &self.signed_content()?)?);
+        Ok(())
+    }
+
+    fn verify_mac(&self, key: &str) -> Result<bool, String> {
+        let expected = hmac_hex(key, &self.signed_content()?)?;
+        Ok(self.mac.as_deref().is_some_and(|mac| crate::auth::constant_time_eq(&expected, mac)))
+    }
+}
+
+fn hmac_hex(key: &str, content: &str) -> Result<String, String> {
+    use hmac::{Hmac, Mac};
+
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(key.as_bytes()).map_err(|e| e.to_string())?;
+    mac.update(content.as_bytes());
+    Ok(format!("{:x}", mac.finalize().into_bytes()))
+}
+
+/// Entradas posteriores a `after_seq` que se pueden aplicar sobre el snapshot: deben
+/// seguir la secuencia sin huecos y, con clave, llevar un HMAC válido. Las entradas
+/// sin firma solo se aceptan con `allow_unsigned` (importación explícita); el bool
+/// devuelto indica si se aceptó alguna
+pub fn verified_after(
+    entries: Vec<JournalEntry>,
+    after_seq: u64,
+    key: Option<&str>,
+    allow_unsigned: bool,
+) -> Result<(Vec<JournalEntry>, bool), String> {
+    let mut accepted_unsigned = false;
+    let mut verified = Vec::new();
+    for (expected_seq, entry) in (after_seq + 1..).zip(entries.into_iter().filter(|e| e.seq > after_seq)) {
+        if entry.seq != expected_seq {
+            return Err(format!("falta la entrada {} del diario (la siguiente es la {})", expected_seq, entry.seq));
+        }
+        if let Some(key) = key {
+            match entry.mac {
+                Some(_) if !entry.verify_mac(key)? => {
+                    return Err(format!("HMAC inválido en la entrada {} del diario", entry.seq));
+                }
+                Some(_) => {}
+                None if allow_unsigned => accepted_unsigned = true,
+                None => return Err(format!("la entrada {} del diario no está firmada", entry.seq)),
+            }
+        }
+        verified.push(entry);
+    }
+    Ok((verified, accepted_unsigned))
+}
+
+pub fn append(dir: &Path, entry: &JournalEntry) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(JOURNAL_FILE))?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    file.sync_data()?;
+    Ok(())
+}
+
+/// Lee el diario completo; una última línea incompleta (corte a mitad de escritura) se descarta
+pub fn read(dir: &Path) -> Result<Vec<JournalEntry>, Box<dyn std::error::Error>> {
+    let path = dir.join(JOURNAL_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let lines: Vec<String> = BufReader::new(fs::File::open(path)?)
+        .lines()
+        .collect::<Result<_, _>>()?;
+    let last = lines.len().saturating_sub(1);
+
+    let mut entries = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<JournalEntry>(line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) if i == last => eprintln!("Ignorando entrada final incompleta del diario: {}", e),
+            Err(e) => return Err(format!("Entrada {} del diario corrupta: {}", i + 1, e).into()),
+        }
+    }
+    Ok(entries)
+}
+
+/// Reconstruye el torneo aplicando el diario hasta `up_to_seq` (incluido)
+pub fn replay(entries: &[JournalEntry], up_to_seq: Option<u64>) -> Result<Tournament, String> {
+    let mut tournament: Option<Tournament> = None;
+    for entry in entries.iter().filter(|e| up_to_seq.is_none_or(|max| e.seq <= max)) {
+        match (&mut tournament, &entry.event) {
+            (_, JournalEvent::Checkpoint { tournament: base }) => tournament = Some(base.clone()),
+            (Some(t), event) => apply(t, event)?,
+            (None, _) => return Err(format!("El diario no tiene punto de partida antes de la entrada {}", entry.seq)),
+        }
+    }
+    tournament.ok_or_else(|| "El diario está vacío".to_string())
+}
+
+/// Aplica un evento sobre el estado del torneo
+pub fn apply(tournament: &mut Tournament, event: &JournalEvent) -> Result<(), String> {
+    match event {
+        JournalEvent::Checkpoint { tournament: base } => {
+            *tournament = base.clone();
+        }
+        JournalEvent::PlayerAdded { player } => {
+            if !tournament.players.iter().any(|p| p.id == player.id) {
+                tournament.players.push(player.clone());
+            }
+        }
+        JournalEvent::RoundStarted { round, tiles_remaining } => {
+            if tournament.status == TournamentStatus::Created {
+                tournament.status = TournamentStatus::InProgress;
+            }
+            tournament.rounds.push(round.clone());
+            tournament.tiles_remaining = *tiles_remaining;
+        }
+        JournalEvent::RackUpdated { round_number, rack, tiles_remaining } => {
+            let round = find_round(tournament, *round_number)?;
+            round.rack = rack.clone();
+            round.optimal_play = None;
+            round.rack_rejected = false;
+            round.rejection_reason = None;
+            tournament.tiles_remaining = *tiles_remaining;
+        }
+        JournalEvent::RackRejected { round_number, rack, rejection_reason, tiles_remaining } => {
+            let round = find_round(tournament, *round_number)?;
+            round.rack = rack.clone();
+            round.rack_rejected = true;
+            round.rejection_reason = rejection_reason.clone();
+            round.optimal_play = None;
+            tournament.tiles_remaining = *tiles_remaining;
+        }
+        JournalEvent::TimerStarted { round_number, started_at } => {
+            find_round(tournament, *round_number)?.timer_started = Some(*started_at);
+        }
+        JournalEvent::PlaySubmitted { player_id, play } => {
+            let player = find_player(tournament, player_id)?;
+            player.plays.retain(|p| p.round_number != play.round_number);
+            player.plays.push(play.clone());
+            player.total_score = play.cumulative_score;
+        }
+        JournalEvent::MasterRevealed { round_number } => {
+            find_round(tournament, *round_number)?.optimal_revealed = true;
+        }
+        JournalEvent::MasterPlaced { round_number, optimal_play, master_play, board, finished } => {
+            let round = find_round(tournament, *round_number)?;
+            round.optimal_play = Some(optimal_play.clone());
+            round.optimal_revealed = true;
+            round.status = RoundStatus::Completed;
+            round.board_state = board.clone();
+            tournament.master_plays.push(master_play.clone());
+            if *finished {
+                tournament.status = TournamentStatus::Finished;
+            }
+        }
+        JournalEvent::TournamentFinished => {
+            tournament.status = TournamentStatus::Finished;
+        }
+        JournalEvent::RoundUndone { round_number } => {
+            if tournament.master_plays.last().is_some_and(|mp| mp.round_number == *round_number) {
+                tournament.master_plays.pop();
+            }
+            if tournament.rounds.last().is_some_and(|r| r.number == *round_number) {
+                tournament.rounds.pop();
+            }
+        }
+        JournalEvent::ScoreAdjusted { player_id, round_number, score, .. } => {
+            let player = find_player(tournament, player_id)?;
+            let play = player.plays.iter_mut()
+                .find(|p| p.round_number == *round_number)
+                .ok_or_else(|| format!("El jugador no tiene jugada en la ronda {}", round_number))?;
+
+            let delta = score - play.score;
+            let optimal_score = play.score + play.difference_from_optimal;
+            play.score = *score;
+            play.difference_from_optimal -= delta;
+            if delta != 0 {
+                // La puntuación corregida ya no es la de una jugada generada
+                play.rank = None;
+            }
+            play.percentage_of_optimal = if optimal_score > 0 {
+                (*score as f32 / optimal_score as f32) * 100.0
+            } else {
+                100.0
+            };
+
+            // Los acumulados de esta ronda en adelante se desplazan
+            for later in player.plays.iter_mut().filter(|p| p.round_number >= *round_number) {
+                later.cumulative_score += delta;
+                later.cumulative_difference -= delta;
+            }
+            player.total_score += delta;
+        }
+    }
+    Ok(())
+}
+
+fn find_round(tournament: &mut Tournament, round_number: u32) -> Result<&mut Round, String> {
+    tournament.rounds.iter_mut()
+        .find(|r| r.number == round_number)
+        .ok_or_else(|| format!("Ronda {} no encontrada en el diario", round_number))
+}
+
+fn find_player<'a>(tournament: &'a mut Tournament, player_id: &Uuid) -> Result<&'a mut Player, String> {
+    tournament.players.iter_mut()
+        .find(|p| &p.id == player_id)
+        .ok_or_else(|| format!("Jugador {} no encontrado en el diario", player_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Position;
+    use crate::models::test_support::active_round;
+
+    fn entry(seq: u64, event: JournalEvent) -> JournalEntry {
+        JournalEntry { seq, recorded_at: Utc::now(), event, mac: None }
+    }
+
+    fn base_tournament(player_id: Uuid) -> Tournament {
+        Tournament {
+            id: Uuid::new_v4(),
+            name: "Prueba".to_string(),
+            created_at: Utc::now(),
+            status: TournamentStatus::Created,
+            rounds: Vec::new(),
+            players: vec![Player { id: player_id, name: "Ana".to_string(), total_score: 0, plays: Vec::new(), category: None, club: None, federation_id: None }],
+            tiles_remaining: 100,
+            master_plays: Vec::new(),
+            training: false,
+        }
+    }
+
+    fn round(number: u32) -> Round {
+        active_round(number, "AEIOUST")
+    }
+
+    fn play(round_number: u32, score: i32, cumulative_score: i32) -> PlayerPlay {
+        PlayerPlay {
+            round_number,
+            word: "SAETA".to_string(),
+            position: Position { row: 7, col: 7, down: false },
+            score,
+            percentage_of_optimal: 50.0,
+            submitted_at: Utc::now(),
+            cumulative_score,
+            difference_from_optimal: 20 - score,
+            cumulative_difference: 20 - score,
+            rank: None,
+        }
+    }
+
+    #[test]
+    fn test_replay_rebuilds_state() {
+        let player_id = Uuid::new_v4();
+        let entries = vec![
+            entry(1, JournalEvent::Checkpoint { tournament: base_tournament(player_id) }),
+            entry(2, JournalEvent::RoundStarted { round: round(1), tiles_remaining: 93 }),
+            entry(3, JournalEvent::PlaySubmitted { player_id, play: play(1, 8, 8) }),
+            entry(4, JournalEvent::PlaySubmitted { player_id, play: play(1, 10, 10) }),
+            entry(5, JournalEvent::ScoreAdjusted { player_id, round_number: 1, score: 12, reason: "Planilla".to_string() }),
+        ];
+
+        let tournament = replay(&entries, None).unwrap();
+        assert_eq!(tournament.status, TournamentStatus::InProgress);
+        assert_eq!(tournament.tiles_remaining, 93);
+        let player = &tournament.players[0];
+        assert_eq!(player.plays.len(), 1);
+        assert_eq!(player.total_score, 12);
+        assert_eq!(player.plays[0].difference_from_optimal, 8);
+
+        // Recuperación a un punto anterior
+        let earlier = replay(&entries, Some(3)).unwrap();
+        assert_eq!(earlier.players[0].total_score, 8);
+    }
+
+    #[test]
+    fn test_replay_requires_checkpoint() {
+        let entries = vec![entry(1, JournalEvent::MasterRevealed { round_number: 1 })];
+        assert!(replay(&entries, None).is_err());
+    }
+
+    #[test]
+    fn test_verified_after_rejects_tampering_and_gaps() {
+        const KEY: Option<&str> = Some("journal-key");
+        let player_id = Uuid::new_v4();
+        let signed = |seq, event| {
+            let mut entry = entry(seq, event);
+            entry.sign(KEY.unwrap()).unwrap();
+            entry
+        };
+        let entries = vec![
+            signed(5, JournalEvent::RoundStarted { round: round(1), tiles_remaining: 93 }),
+            signed(6, JournalEvent::PlaySubmitted { player_id, play: play(1, 8, 8) }),
+        ];
+        let (verified, unsigned) = verified_after(entries.clone(), 4, KEY, false).unwrap();
+        assert_eq!(verified.len(), 2);
+        assert!(!unsigned);
+        // Las entradas ya incluidas en el snapshot no se aplican
+        assert_eq!(verified_after(entries.clone(), 5, KEY, false).unwrap().0.len(), 1);
+
+        let mut tampered = entries.clone();
+        tampered[1].event = JournalEvent::PlaySubmitted { player_id, play: play(1, 80, 80) };
+        assert!(verified_after(tampered, 4, KEY, false).unwrap_err().contains("HMAC inválido"));
+
+        let gap = vec![entries[1].clone()];
+        assert!(verified_after(gap, 4, KEY, false).unwrap_err().contains("falta la entrada 5"));
+
+        let unsigned_entries = vec![entry(5, JournalEvent::TournamentFinished)];
+        assert!(verified_after(unsigned_entries.clone(), 4, KEY, false).unwrap_err().contains("no está firmada"));
+        assert!(verified_after(unsigned_entries, 4, KEY, true).unwrap().1);
+    }
+}
@@ -1,22 +1,75 @@
 use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Notify, RwLock};
 use uuid::Uuid;
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
-use log::{info, warn};
+use log::{error, info, warn};
+use crate::database::Database;
+use crate::persistence_mode::PersistenceConfig;
+
+pub const DEFAULT_CACHE_PATH: &str = "tournaments/local_cache.jsonl";
+
+/// Cada cuánto el sincronizador revisa jugadas pendientes
+const RESYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Margen para que la cola asíncrona entregue la jugada antes de que la
+/// resincronización la reenvíe
+const RESYNC_GRACE_SECS: i64 = 30;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CachedPlay {
     pub play_data: crate::async_queue::PlayData,
     pub timestamp: DateTime<Utc>,
     pub synced: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,  // Último fallo al subirla; se reintenta en la próxima pasada
+}
+
+/// Resultado de una pasada de resincronización: una jugada que falla no
+/// detiene a las demás
+#[derive(Debug, Default, Serialize)]
+pub struct CacheSyncReport {
+    pub synced: usize,
+    pub failed: usize,
+    pub last_error: Option<String>,
+}
+
+/// Cambio del cache tal como se agrega al archivo en disco
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op")]
+enum CacheOp {
+    Store { key: String, play: CachedPlay },
+    Synced { key: String },
+    Failed { key: String, error: String },
+    Evict { key: String },
+    ClearSynced,
+}
+
+/// Archivo de cambios abierto. Las líneas se escriben con el lock del cache y el
+/// fsync se hace después, sin él: varias escrituras seguidas comparten un fsync
+#[derive(Debug, Default)]
+struct CacheFile {
+    file: Option<fs::File>,
+    unsynced: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct LocalCache {
     cache: Arc<RwLock<HashMap<String, CachedPlay>>>,
     max_size: usize,
+    /// Archivo de cambios; `None` para un cache solo en memoria
+    path: Option<PathBuf>,
+    writer: Arc<Mutex<CacheFile>>,
+    resync: Arc<Notify>,
+}
+
+fn cache_key(tournament_id: Uuid, player_id: Uuid, round_number: i32) -> String {
+    format!("{}-{}-{}", tournament_id, player_id, round_number)
 }
 
 impl LocalCache {
@@ -24,19 +77,139 @@ impl LocalCache {
         LocalCache {
             cache: Arc::new(RwLock::new(HashMap::new())),
             max_size,
+            path: None,
+            writer: Arc::default(),
+            resync: Arc::new(Notify::new()),
+        }
+    }
+    
+    /// Cache respaldado en disco: reproduce el archivo de cambios y lo compacta.
+    /// Una última línea incompleta (corte a mitad de escritura) se descarta.
+    pub fn open(path: impl AsRef<Path>, max_size: usize) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = HashMap::new();
+        
+        if path.exists() {
+            let file = fs::File::open(&path).map_err(|e| format!("No se pudo abrir {}: {}", path.display(), e))?;
+            let lines: Vec<String> = BufReader::new(file).lines()
+                .collect::<Result<_, _>>()
+                .map_err(|e| e.to_string())?;
+            let last = lines.len().saturating_sub(1);
+            
+            for (i, line) in lines.iter().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<CacheOp>(line) {
+                    Ok(CacheOp::Store { key, play }) => { entries.insert(key, play); }
+                    Ok(CacheOp::Synced { key }) => {
+                        if let Some(play) = entries.get_mut(&key) {
+                            play.synced = true;
+                            play.last_error = None;
+                        }
+                    }
+                    Ok(CacheOp::Failed { key, error }) => {
+                        if let Some(play) = entries.get_mut(&key) {
+                            play.last_error = Some(error);
+                        }
+                    }
+                    Ok(CacheOp::Evict { key }) => { entries.remove(&key); }
+                    Ok(CacheOp::ClearSynced) => entries.retain(|_, v: &mut CachedPlay| !v.synced),
+                    Err(e) if i == last => warn!("Ignorando línea final incompleta del cache: {}", e),
+                    Err(e) => return Err(format!("Línea {} del cache corrupta: {}", i + 1, e)),
+                }
+            }
+        }
+        
+        Self::compact(&path, &entries)?;
+        Ok(LocalCache {
+            cache: Arc::new(RwLock::new(entries)),
+            max_size,
+            path: Some(path),
+            writer: Arc::default(),
+            resync: Arc::new(Notify::new()),
+        })
+    }
+    
+    pub fn open_default(max_size: usize) -> Result<Self, String> {
+        let path = std::env::var("LOCAL_CACHE_PATH").unwrap_or_else(|_| DEFAULT_CACHE_PATH.to_string());
+        Self::open(path, max_size)
+    }
+    
+    /// Agrega el cambio al archivo sin esperar al disco; llamar a `sync` después
+    /// de soltar el lock del cache
+    fn append(&self, op: &CacheOp) {
+        let Some(ref path) = self.path else { return };
+        let mut writer = self.writer.lock().unwrap();
+        let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+            if writer.file.is_none() {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                writer.file = Some(fs::OpenOptions::new().create(true).append(true).open(path)?);
+            }
+            if let Some(file) = writer.file.as_mut() {
+                writeln!(file, "{}", serde_json::to_string(op)?)?;
+            }
+            writer.unsynced = true;
+            Ok(())
+        })();
+        if let Err(e) = result {
+            writer.file = None;
+            error!("No se pudo escribir el cache en disco: {}", e);
+        }
+    }
+    
+    /// Lleva al disco los cambios escritos hasta ahora con un solo fsync
+    fn sync(&self) {
+        let file = {
+            let mut writer = self.writer.lock().unwrap();
+            if !writer.unsynced {
+                return;
+            }
+            writer.unsynced = false;
+            writer.file.as_ref().and_then(|f| f.try_clone().ok())
+        };
+        if let Some(Err(e)) = file.map(|f| f.sync_data()) {
+            error!("No se pudo sincronizar el cache en disco: {}", e);
+        }
+    }
+    
+    /// Reescribe el archivo con el estado actual para que no crezca sin límite
+    fn compact_file(&self, cache: &HashMap<String, CachedPlay>) -> Result<(), String> {
+        let Some(ref path) = self.path else { return Ok(()) };
+        let mut writer = self.writer.lock().unwrap();
+        Self::compact(path, cache)?;
+        // El archivo anterior se reemplazó: la próxima escritura abre el nuevo
+        *writer = CacheFile::default();
+        Ok(())
+    }
+    
+    /// Reescribe el archivo con solo el estado actual
+    fn compact(path: &Path, cache: &HashMap<String, CachedPlay>) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut content = String::new();
+        for (key, play) in cache {
+            let op = CacheOp::Store { key: key.clone(), play: play.clone() };
+            content.push_str(&serde_json::to_string(&op).map_err(|e| e.to_string())?);
+            content.push('\n');
         }
+        let tmp = path.with_extension("jsonl.tmp");
+        let mut file = fs::File::create(&tmp).map_err(|e| e.to_string())?;
+        file.write_all(content.as_bytes()).and_then(|_| file.sync_data()).map_err(|e| e.to_string())?;
+        fs::rename(&tmp, path).map_err(|e| e.to_string())
     }
     
     pub async fn store_play(&self, play_data: crate::async_queue::PlayData) -> Result<(), String> {
-        let key = format!("{}-{}-{}", 
-                         play_data.tournament_id, 
-                         play_data.player_id, 
-                         play_data.round_number);
+        let key = cache_key(play_data.tournament_id, play_data.player_id, play_data.round_number);
         
         let cached_play = CachedPlay {
             play_data: play_data.clone(),
             timestamp: Utc::now(),
             synced: false,
+            last_error: None,
         };
         
         let mut cache = self.cache.write().await;
@@ -51,8 +224,9 @@ impl LocalCache {
             
             if !synced_keys.is_empty() {
                 synced_keys.sort_by_key(|(_, t)| *t);
-                let key_to_remove = &synced_keys[0].0;
-                cache.remove(key_to_remove);
+                let key_to_remove = synced_keys[0].0.clone();
+                cache.remove(&key_to_remove);
+                self.append(&CacheOp::Evict { key: key_to_remove.clone() });
                 info!("Evicted old synced entry from cache: {}", key_to_remove);
             } else {
                 warn!("Cache is full with unsynced entries!");
@@ -60,24 +234,45 @@ impl LocalCache {
             }
         }
         
+        self.append(&CacheOp::Store { key: key.clone(), play: cached_play.clone() });
         cache.insert(key.clone(), cached_play);
+        drop(cache);
+        self.sync();
         info!("Cached play for key: {}", key);
         Ok(())
     }
     
     pub async fn get_play(&self, tournament_id: Uuid, player_id: Uuid, round_number: i32) -> Option<CachedPlay> {
-        let key = format!("{}-{}-{}", tournament_id, player_id, round_number);
+        let key = cache_key(tournament_id, player_id, round_number);
         let cache = self.cache.read().await;
         cache.get(&key).cloned()
     }
     
-    pub async fn mark_as_synced(&self, tournament_id: Uuid, player_id: Uuid, round_number: i32) {
-        let key = format!("{}-{}-{}", tournament_id, player_id, round_number);
+    /// Marca la jugada como subida solo si sigue siendo la versión `cached_at`;
+    /// si el jugador la reenvió mientras se subía, la versión nueva queda pendiente
+    pub async fn mark_as_synced(&self, tournament_id: Uuid, player_id: Uuid, round_number: i32, cached_at: DateTime<Utc>) {
+        let key = cache_key(tournament_id, player_id, round_number);
         let mut cache = self.cache.write().await;
-        if let Some(play) = cache.get_mut(&key) {
+        if let Some(play) = cache.get_mut(&key).filter(|p| p.timestamp == cached_at) {
             play.synced = true;
+            play.last_error = None;
+            self.append(&CacheOp::Synced { key: key.clone() });
             info!("Marked play as synced: {}", key);
         }
+        drop(cache);
+        self.sync();
+    }
+    
+    /// Anota el fallo al subir la jugada; sigue pendiente para la próxima pasada
+    async fn mark_as_failed(&self, tournament_id: Uuid, player_id: Uuid, round_number: i32, cached_at: DateTime<Utc>, error: String) {
+        let key = cache_key(tournament_id, player_id, round_number);
+        let mut cache = self.cache.write().await;
+        if let Some(play) = cache.get_mut(&key).filter(|p| p.timestamp == cached_at) {
+            self.append(&CacheOp::Failed { key, error: error.clone() });
+            play.last_error = Some(error);
+        }
+        drop(cache);
+        self.sync();
     }
     
     pub async fn get_unsynced_plays(&self) -> Vec<CachedPlay> {
@@ -98,6 +293,12 @@ impl LocalCache {
     pub async fn clear_synced(&self) {
         let mut cache = self.cache.write().await;
         cache.retain(|_, v| !v.synced);
+        if let Err(e) = self.compact_file(&cache) {
+            error!("No se pudo compactar el cache en disco: {}", e);
+            self.append(&CacheOp::ClearSynced);
+        }
+        drop(cache);
+        self.sync();
         info!("Cleared all synced entries from cache");
     }
     
@@ -115,16 +316,145 @@ impl LocalCache {
         let mut imported = 0;
         
         for play in plays {
-            let key = format!("{}-{}-{}", 
-                            play.play_data.tournament_id, 
-                            play.play_data.player_id, 
+            let key = cache_key(
+                            play.play_data.tournament_id,
+                            play.play_data.player_id,
                             play.play_data.round_number);
             if !cache.contains_key(&key) {
+                self.append(&CacheOp::Store { key: key.clone(), play: play.clone() });
                 cache.insert(key, play);
                 imported += 1;
             }
         }
+        drop(cache);
+        self.sync();
         
         Ok(imported)
     }
-}
\ No newline at end of file
+    
+    /// Sube las jugadas pendientes con al menos `min_age_secs` de antigüedad.
+    /// Las escrituras son upserts idempotentes, así que reenviar no duplica.
+    /// Cada jugada queda marcada como subida o con su error; tras una pasada que
+    /// subió algo el archivo se compacta.
+    pub async fn sync_to_database(&self, database: &Database, min_age_secs: i64) -> CacheSyncReport {
+        let cutoff = Utc::now() - chrono::Duration::seconds(min_age_secs);
+        let mut report = CacheSyncReport::default();
+        
+        for play in self.get_unsynced_plays().await {
+            if play.timestamp > cutoff {
+                continue;
+            }
+            let p = &play.play_data;
+            match database.submit_player_play(p).await {
+                Ok(_) => {
+                    self.mark_as_synced(p.tournament_id, p.player_id, p.round_number, play.timestamp).await;
+                    report.synced += 1;
+                }
+                Err(e) => {
+                    let error = e.to_string();
+                    warn!("No se pudo subir la jugada {}-{}-{} del cache: {}", p.tournament_id, p.player_id, p.round_number, error);
+                    self.mark_as_failed(p.tournament_id, p.player_id, p.round_number, play.timestamp, error.clone()).await;
+                    report.failed += 1;
+                    report.last_error = Some(error);
+                }
+            }
+        }
+        
+        if report.synced > 0 {
+            let cache = self.cache.read().await;
+            if let Err(e) = self.compact_file(&cache) {
+                error!("No se pudo compactar el cache en disco: {}", e);
+            }
+        }
+        report
+    }
+    
+    /// Pide una resincronización inmediata (p. ej. al recuperarse la nube)
+    pub fn request_resync(&self) {
+        self.resync.notify_one();
+    }
+    
+    /// Sincronizador en segundo plano: drena el cache cuando la nube está disponible
+    pub fn spawn_resync(&self, database: Arc<Database>, config: Arc<PersistenceConfig>) {
+        let cache = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RESYNC_INTERVAL);
+            loop {
+                let min_age = tokio::select! {
+                    _ = interval.tick() => RESYNC_GRACE_SECS,
+                    _ = cache.resync.notified() => 0,
+                };
+                if !config.cloud_ready() {
+                    continue;
+                }
+                let report = cache.sync_to_database(&database, min_age).await;
+                if report.synced > 0 {
+                    info!("Resincronizadas {} jugadas del cache local", report.synced);
+                }
+                if report.failed > 0 {
+                    warn!("{} jugadas del cache siguen pendientes: {}", report.failed, report.last_error.unwrap_or_default());
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::async_queue::PlayData;
+
+    fn play(round_number: i32) -> PlayData {
+        PlayData {
+            tournament_id: Uuid::nil(),
+            player_id: Uuid::nil(),
+            round_number,
+            word: "CASA".to_string(),
+            position_row: 7,
+            position_col: 7,
+            position_down: false,
+            score: 12,
+            percentage_of_optimal: 50.0,
+            cumulative_score: 12,
+            difference_from_optimal: 12,
+            cumulative_difference: 12,
+            submitted_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_reloads_from_disk() {
+        let path = std::env::temp_dir().join(format!("local_cache_{}.jsonl", Uuid::new_v4()));
+        let cache = LocalCache::open(&path, 10).unwrap();
+        cache.store_play(play(1)).await.unwrap();
+        cache.store_play(play(2)).await.unwrap();
+        let first = cache.get_play(Uuid::nil(), Uuid::nil(), 1).await.unwrap();
+        cache.mark_as_synced(Uuid::nil(), Uuid::nil(), 1, first.timestamp).await;
+
+        // Simula un corte a mitad de escritura
+        fs::OpenOptions::new().append(true).open(&path).unwrap()
+            .write_all(b"{\"op\":\"Store\",\"key\":").unwrap();
+
+        let reloaded = LocalCache::open(&path, 10).unwrap();
+        assert_eq!(reloaded.get_stats().await, (2, 1));
+        let unsynced = reloaded.get_unsynced_plays().await;
+        assert_eq!(unsynced.len(), 1);
+        assert_eq!(unsynced[0].play_data.round_number, 2);
+
+        // Un fallo al subir queda anotado y la jugada sigue pendiente
+        let second = unsynced[0].timestamp;
+        reloaded.mark_as_failed(Uuid::nil(), Uuid::nil(), 2, second, "sin conexión".to_string()).await;
+        let reopened = LocalCache::open(&path, 10).unwrap();
+        let pending = reopened.get_unsynced_plays().await;
+        assert_eq!(pending[0].last_error.as_deref(), Some("sin conexión"));
+
+        reloaded.clear_synced().await;
+        assert_eq!(LocalCache::open(&path, 10).unwrap().get_stats().await, (1, 0));
+        // Tras compactar, las escrituras siguen yendo al archivo nuevo
+        reloaded.mark_as_synced(Uuid::nil(), Uuid::nil(), 2, second).await;
+        assert_eq!(LocalCache::open(&path, 10).unwrap().get_stats().await, (1, 1));
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+
+        fs::remove_file(&path).ok();
+    }
+}
@@ -16,10 +16,29 @@ mod tournament_manager;
 mod wolges_engine;
 mod persistence;
 mod database;
+mod db_migrations;
 mod async_queue;
+mod cloud_health;
+mod dead_letter;
+mod gcg;
+mod player_import;
+mod record;
+mod round_stats;
+mod player_analytics;
+mod coaching;
+mod standings;
 mod local_cache;
 mod supabase_poller;
 mod persistence_mode;
+mod auth;
+mod arbiter;
+mod device_guard;
+mod events;
+mod journal;
+mod recovery;
+mod migrations;
+mod sqlite_store;
+mod storage;
 
 use tournament_manager::TournamentManager;
 
@@ -59,45 +78,123 @@ async fn main() -> std::io::Result<()> {
     // Using HTTP for mobile testing (previously HTTPS with TLS)
 
     // Initialize database connection (optional for now)
+    let mut cloud_connected = false;
     let database = match database::Database::new().await {
         Ok(db) => {
             log::info!("Database connection established");
+            cloud_connected = true;
             Some(Arc::new(db))
         },
+        Err(e) if e.downcast_ref::<db_migrations::SchemaTooNew>().is_some() => {
+            // Escribir con un esquema desconocido podría corromper la base
+            log::error!("{}", e);
+            std::process::exit(1);
+        },
         Err(e) => {
             log::error!("Failed to connect to database: {}", e);
-            log::warn!("Running in offline mode - data will not persist to Supabase");
-            None
+            log::warn!("Running in offline mode until Supabase responds - plays are cached locally");
+            // Pool perezoso: el monitor de salud lo conecta (y migra) cuando la base vuelva
+            match database::Database::connect_lazy() {
+                Ok(db) => Some(Arc::new(db)),
+                Err(e) => {
+                    log::error!("Supabase deshabilitado: {}", e);
+                    None
+                }
+            }
         }
     };
     
+    // Initialize persistence configuration
+    let persistence_config = Arc::new(persistence_mode::PersistenceConfig::new());
+    
+    // Set cloud availability based on database connection
+    if cloud_connected {
+        persistence_config.set_cloud_status(true).await;
+    }
+    
     // Initialize async queue and poller only if database is available
     let async_queue = if let Some(ref db) = database {
-        Some(Arc::new(async_queue::AsyncQueue::new(db.clone()).await))
+        // Jugadas que agotan los reintentos quedan en disco hasta que vuelva la nube
+        let dead_letters = Arc::new(dead_letter::DeadLetterStore::open_default());
+        Some(Arc::new(async_queue::AsyncQueue::new(db.clone(), dead_letters, persistence_config.clone()).await))
     } else {
         None
     };
     
-    // Initialize local cache (store up to 10,000 plays)
-    let local_cache = Arc::new(local_cache::LocalCache::new(10000));
-    
-    // Initialize persistence configuration
-    let persistence_config = Arc::new(persistence_mode::PersistenceConfig::new());
+    // Initialize local cache (store up to 10,000 plays), respaldado en disco
+    let local_cache = Arc::new(match local_cache::LocalCache::open_default(10000) {
+        Ok(cache) => cache,
+        Err(e) => {
+            log::error!("No se pudo cargar el cache local: {}; se usa solo en memoria", e);
+            local_cache::LocalCache::new(10000)
+        }
+    });
+    if let Some(ref db) = database {
+        local_cache.spawn_resync(db.clone(), persistence_config.clone());
+    }
     
-    // Set cloud availability based on database connection
-    if database.is_some() {
-        persistence_config.set_cloud_status(true).await;
+    // Base SQLite local (archivo consultable); se usa en modo LocalSqlite
+    let sqlite_store = match sqlite_store::SqliteStore::open_default().await {
+        Ok(store) => Some(Arc::new(store)),
+        Err(e) => {
+            log::warn!("Base SQLite no disponible: {}", e);
+            None
+        }
+    };
+    if sqlite_store.is_some() && std::env::var("PERSISTENCE_MODE").is_ok_and(|m| m == "LocalSqlite") {
+        persistence_config.set_mode(persistence_mode::PersistenceMode::LocalSqlite).await;
     }
     
     // Initialize tournament manager
     let mut manager = TournamentManager::new(local_ip);
     
-    // Auto-load dictionary on startup
-    match manager.load_dictionary("FISE2016_converted.kwg", None) {
+    // Acceso de árbitros: credencial del servidor y modo abierto explícito
+    let server_token = std::env::var(arbiter::SERVER_TOKEN_ENV).ok();
+    let open_mode = std::env::var(arbiter::OPEN_MODE_ENV).is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"));
+    if server_token.is_none() {
+        log::warn!("{} no configurado - las acciones globales de servidor quedan deshabilitadas", arbiter::SERVER_TOKEN_ENV);
+    }
+    if open_mode {
+        log::warn!("⚠️ Modo abierto: los torneos sin cuentas de árbitro no exigen sesión");
+    }
+    manager.configure_access(server_token.as_deref(), open_mode);
+    
+    // Todas las escrituras del manager pasan por el almacenamiento según el modo de persistencia
+    let mut storage = storage::ModeStorage::new(persistence_config.clone(), Arc::new(storage::JsonStorage));
+    if let Some(ref db) = database {
+        storage = storage.with_cloud(Arc::new(storage::cloud_storage(db.clone(), async_queue.clone())))
+            .with_cache(Arc::new(storage::CacheStorage::spawn(local_cache.clone())));
+    }
+    if let Some(ref store) = sqlite_store {
+        storage = storage.with_sqlite(Arc::new(storage::sqlite_storage(store.clone())));
+    }
+    manager.set_storage(Arc::new(storage));
+    
+    // Auto-load dictionary on startup; el KLV es opcional (sin él, el análisis de
+    // entrenamiento compara solo puntos)
+    let klv_path = std::env::var(wolges_engine::KLV_PATH_ENV).ok().filter(|p| !p.is_empty());
+    if klv_path.is_none() {
+        log::warn!("{} no configurado - el análisis por equity se limita a puntos", wolges_engine::KLV_PATH_ENV);
+    }
+    let loaded = manager.load_dictionary("FISE2016_converted.kwg", klv_path.as_deref())
+        .or_else(|e| match klv_path {
+            Some(ref path) => {
+                log::error!("No se pudo cargar el KLV {}: {}; se carga el diccionario sin él", path, e);
+                manager.load_dictionary("FISE2016_converted.kwg", None)
+            }
+            None => Err(e),
+        });
+    match loaded {
         Ok(_) => log::info!("Dictionary FISE2016_converted.kwg loaded successfully on startup"),
         Err(e) => log::error!("Failed to load dictionary on startup: {}", e),
     }
     
+    // Recuperar torneos que estaban en curso cuando se detuvo el servidor
+    let report = recovery::recover_in_progress(&mut manager);
+    log::info!("Recuperación al iniciar: {} torneo(s) restaurado(s), {} con errores",
+        report.recovered.len(), report.failed.len());
+    manager.recovery_report = Some(report);
+    
     let tournament_manager = Arc::new(RwLock::new(manager));
     
     // Iniciar Supabase Poller solo si hay database
@@ -105,7 +202,7 @@ async fn main() -> std::io::Result<()> {
         let poller = Arc::new(supabase_poller::SupabasePoller::new(
             db.clone(),
             tournament_manager.clone(),
-            500, // Poll cada 500ms
+            5000, // Sondeo de respaldo; las jugadas llegan por LISTEN/NOTIFY
         ));
         
         // Spawn el poller en background
@@ -114,7 +211,10 @@ async fn main() -> std::io::Result<()> {
             poller_clone.start_polling().await;
         });
         
-        log::info!("🔄 Supabase Poller iniciado - leyendo jugadas cada 500ms");
+        log::info!("🔄 Supabase Poller iniciado - escuchando jugadas nuevas");
+        
+        // Monitor de salud: mantiene cloud_available y el modo de persistencia al día
+        cloud_health::spawn(db.clone(), persistence_config.clone(), local_cache.clone(), tournament_manager.clone());
     } else {
         log::warn!("⚠️ Supabase Poller deshabilitado - modo offline");
     }
@@ -135,6 +235,9 @@ async fn main() -> std::io::Result<()> {
         if let Some(ref db) = database {
             app = app.app_data(web::Data::new(db.clone()));
         }
+        if let Some(ref store) = sqlite_store {
+            app = app.app_data(web::Data::new(store.clone()));
+        }
         if let Some(ref queue) = async_queue {
             app = app.app_data(web::Data::new(queue.clone()));
         }
@@ -151,34 +254,70 @@ async fn main() -> std::io::Result<()> {
             .service(routes::test_validate_word)
             .service(routes::load_dictionary)
             .service(routes::create_tournament)
+            .service(routes::import_record)
             .service(routes::get_tournament)
             .service(routes::start_round)
             .service(routes::start_manual_round)
+            .service(routes::start_template_round)
+            .service(routes::get_template)
             .service(routes::update_current_round_rack)
             .service(routes::submit_play)
             .service(routes::get_optimal_play)
             .service(routes::get_round_feedback)
             .service(routes::get_leaderboard)
             .service(routes::get_player_log)
+            .service(routes::get_player_analytics)
+            .service(routes::export_master_gcg)
+            .service(routes::export_player_gcg)
+            .service(routes::export_record)
+            .service(routes::export_standings_csv)
+            .service(routes::export_rounds_csv)
+            .service(routes::export_standings_report)
             .service(routes::reject_rack)
             .service(routes::start_round_timer)
+            .service(routes::get_round_progress)
+            .service(routes::get_round_stats)
+            .service(routes::get_round_stats_history)
+            .service(routes::get_round_coaching)
+            .service(routes::nudge_pending_players)
             .service(routes::reveal_optimal_play)
             .service(routes::place_optimal_play)
             .service(routes::finish_tournament_manually)
             .service(routes::get_bag_tiles)
             .service(routes::check_game_end)
             .service(routes::undo_last_round)
+            .service(routes::adjust_player_score)
+            .service(routes::get_journal)
+            .service(routes::replay_journal)
             .service(routes::ws_tournament_updates)
             .service(routes::list_tournaments)
+            .service(routes::get_recovery_report)
             .service(routes::load_tournament)
             .service(routes::enroll_player)
+            .service(routes::import_players)
+            .service(routes::reissue_player_token)
+            .service(routes::issue_missing_player_tokens)
+            .service(routes::revoke_player_token)
+            .service(routes::get_device_alerts)
+            .service(routes::acknowledge_device_alert)
+            .service(routes::get_device_policy)
+            .service(routes::set_device_policy)
+            .service(routes::set_training_mode)
+            .service(routes::arbiter_login)
+            .service(routes::arbiter_logout)
+            .service(routes::create_arbiter_account)
             .service(routes::get_queue_metrics)
+            .service(routes::list_dead_letters)
+            .service(routes::retry_dead_letter)
+            .service(routes::discard_dead_letter)
             .service(routes::system_health_check)
             .service(routes::get_cache_stats)
             .service(routes::sync_cache_to_database)
             .service(routes::clear_synced_cache)
             .service(routes::get_persistence_mode)
             .service(routes::set_persistence_mode)
+            .service(routes::list_archived_tournaments)
+            .service(routes::get_player_history)
             .service(fs::Files::new("/", ".")
                 .index_file("index.html"))
     })
@@ -0,0 +1,219 @@
+// Migraciones del esquema de snapshots
+//
+// Cada paso transforma el JSON de una versión a la siguiente. Los snapshots
+// se verifican (checksum) sobre el JSON original y después se migran paso a
+// paso hasta SCHEMA_VERSION antes de deserializarlos.
+
+use serde_json::{json, Value};
+
+pub const SCHEMA_VERSION: &str = "1.2.0";
+
+/// Desde esta versión el servidor firma siempre los snapshots
+const FIRST_SIGNED_VERSION: &str = "1.1.0";
+
+type MigrationStep = fn(&mut Value) -> Result<(), String>;
+
+/// (versión origen, versión destino, paso)
+const MIGRATIONS: &[(&str, &str, MigrationStep)] = &[
+    ("1.0.0", "1.1.0", migrate_1_0_0_to_1_1_0),
+    ("1.1.0", "1.2.0", migrate_1_1_0_to_1_2_0),
+];
+
+/// Lleva el snapshot a la versión actual; devuelve las versiones aplicadas
+pub fn migrate(snapshot: &mut Value) -> Result<Vec<String>, String> {
+    let mut version = schema_version(snapshot).to_string();
+
+    let current = parse_version(SCHEMA_VERSION)?;
+    if parse_version(&version)? > current {
+        return Err(format!(
+            "El snapshot usa el esquema {} y este servidor solo soporta hasta {}; actualice el servidor",
+            version, SCHEMA_VERSION
+        ));
+    }
+
+    let mut applied = Vec::new();
+    while version != SCHEMA_VERSION {
+        let (_, to, step) = MIGRATIONS.iter()
+            .find(|(from, _, _)| *from == version)
+            .ok_or_else(|| format!("No hay migración desde la versión de esquema {}", version))?;
+        step(snapshot).map_err(|e| format!("Migración {} -> {} fallida: {}", version, to, e))?;
+        snapshot["metadata"]["schema_version"] = json!(to);
+        applied.push(to.to_string());
+        version = to.to_string();
+    }
+    Ok(applied)
+}
+
+fn schema_version(snapshot: &Value) -> &str {
+    snapshot["metadata"]["schema_version"]
+        .as_str()
+        .unwrap_or("1.0.0")  // Los primeros snapshots siempre usaron 1.0.0
+}
+
+/// Snapshot de un esquema anterior a la firma, que puede no estar firmado
+pub fn predates_signing(snapshot: &Value) -> bool {
+    match (parse_version(schema_version(snapshot)), parse_version(FIRST_SIGNED_VERSION)) {
+        (Ok(version), Ok(first_signed)) => version < first_signed,
+        _ => false,
+    }
+}
+
+fn parse_version(version: &str) -> Result<(u32, u32, u32), String> {
+    let parts: Vec<u32> = version.split('.')
+        .map(|p| p.parse::<u32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("Versión de esquema inválida: {}", version))?;
+    match parts.as_slice() {
+        [major, minor, patch] => Ok((*major, *minor, *patch)),
+        _ => Err(format!("Versión de esquema inválida: {}", version)),
+    }
+}
+
+/// 1.1.0: `blank_positions` explícito en las jugadas óptimas y campos de token
+/// en las sesiones de jugador
+fn migrate_1_0_0_to_1_1_0(snapshot: &mut Value) -> Result<(), String> {
+    let rounds = snapshot["tournament"]["rounds"].as_array_mut()
+        .ok_or("tournament.rounds no es una lista")?;
+    for round in rounds {
+        if let Some(optimal) = round.get_mut("optimal_play").and_then(Value::as_object_mut) {
+            optimal.entry("blank_positions").or_insert_with(|| json!([]));
+        }
+    }
+
+    if let Some(sessions) = snapshot["player_sessions"].as_array_mut() {
+        for session in sessions.iter_mut().filter_map(Value::as_object_mut) {
+            session.entry("token_hash").or_insert(Value::Null);
+            session.entry("token_issued_at").or_insert(Value::Null);
+        }
+    }
+    Ok(())
+}
+
+/// 1.2.0: torneos de entrenamiento, datos de inscripción del jugador
+/// (categoría, club, ID federativo), puesto de cada jugada y dispositivos por
+/// ronda.
+fn migrate_1_1_0_to_1_2_0(snapshot: &mut Value) -> Result<(), String> {
+    let tournament = snapshot["tournament"].as_object_mut()
+        .ok_or("tournament no es un objeto")?;
+    tournament.entry("training").or_insert(json!(false));
+
+    let players = snapshot["tournament"]["players"].as_array_mut()
+        .ok_or("tournament.players no es una lista")?;
+    for player in players.iter_mut().filter_map(Value::as_object_mut) {
+        for field in ["category", "club", "federation_id"] {
+            player.entry(field).or_insert(Value::Null);
+        }
+        if let Some(plays) = player.get_mut("plays").and_then(Value::as_array_mut) {
+            for play in plays.iter_mut().filter_map(Value::as_object_mut) {
+                play.entry("rank").or_insert(Value::Null);
+            }
+        }
+    }
+
+    snapshot.as_object_mut()
+        .ok_or("el snapshot no es un objeto JSON")?
+        .entry("round_devices")
+        .or_insert_with(|| json!([]));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::PersistenceManager;
+    use sha2::Digest;
+
+    const V1_0_0: &str = include_str!("../fixtures/snapshots/v1.0.0.json");
+    const V1_1_0: &str = include_str!("../fixtures/snapshots/v1.1.0.json");
+    const V1_2_0: &str = include_str!("../fixtures/snapshots/v1.2.0.json");
+    /// Clave con la que están firmados los fixtures
+    const FIXTURE_KEY: Option<&str> = Some("fixture-signing-key");
+
+    fn parse(content: &str) -> Result<crate::persistence::TournamentSnapshot, String> {
+        PersistenceManager::parse_snapshot_with_key(content, FIXTURE_KEY)
+    }
+
+    #[test]
+    fn test_v1_0_0_fixture_migrates() {
+        // Sin firma: con clave solo entra por la importación explícita
+        assert!(parse(V1_0_0).unwrap_err().contains("no está firmado"));
+        let snapshot = PersistenceManager::import_unsigned_snapshot_with_key(V1_0_0, FIXTURE_KEY).unwrap();
+        assert_eq!(snapshot.metadata.schema_version, SCHEMA_VERSION);
+        let optimal = snapshot.tournament.rounds[0].optimal_play.as_ref().unwrap();
+        assert!(optimal.blank_positions.is_empty());
+        assert!(snapshot.player_sessions[0].token_hash.is_none());
+    }
+
+    #[test]
+    fn test_v1_1_0_fixture_migrates() {
+        let snapshot = parse(V1_1_0).unwrap();
+        assert_eq!(snapshot.metadata.schema_version, SCHEMA_VERSION);
+        assert!(!snapshot.tournament.training);
+        assert!(snapshot.tournament.players.iter().all(|p| p.category.is_none() && p.club.is_none()));
+        assert!(snapshot.tournament.players[0].plays[0].rank.is_none());
+        assert!(snapshot.round_devices.is_empty());
+        assert_eq!(snapshot.journal_seq, 7);
+    }
+
+    #[test]
+    fn test_current_fixture_loads() {
+        let snapshot = parse(V1_2_0).unwrap();
+        assert_eq!(snapshot.tournament.players.len(), 2);
+        assert_eq!(snapshot.journal_seq, 7);
+        assert!(snapshot.tournament.training);
+        let player = &snapshot.tournament.players[0];
+        assert_eq!(player.category.as_deref(), Some("Élite"));
+        assert_eq!(player.federation_id.as_deref(), Some("ESP-1234"));
+        assert_eq!(player.plays[0].rank.as_ref().map(|r| r.total_plays), Some(1820));
+        assert_eq!(snapshot.round_devices.len(), 1);
+        // Al guardar se obtiene el mismo checksum que se verificó sobre el JSON original
+        assert_eq!(PersistenceManager::calculate_checksum(&snapshot), snapshot.checksum);
+    }
+
+    #[test]
+    fn test_tampered_fixture_rejected() {
+        let tampered = V1_0_0.replacen("\"total_score\": 23", "\"total_score\": 99", 1);
+        assert_ne!(tampered, V1_0_0);
+        assert!(parse(&tampered).unwrap_err().contains("checksum"));
+    }
+
+    #[test]
+    fn test_unsigned_current_snapshot_rejected() {
+        // Quitar la firma deja el checksum válido, pero el esquema actual siempre se firma
+        let mut snapshot: Value = serde_json::from_str(V1_2_0).unwrap();
+        snapshot.as_object_mut().unwrap().shift_remove("signature");
+        let unsigned = serde_json::to_string(&snapshot).unwrap();
+        assert!(parse(&unsigned).unwrap_err().contains("no está firmado"));
+        snapshot["signature"] = json!("0".repeat(64));
+        assert!(parse(&serde_json::to_string(&snapshot).unwrap()).unwrap_err().contains("firma inválida"));
+
+        // Sin clave en el servidor no hay con qué comparar
+        assert!(PersistenceManager::parse_snapshot_with_key(&unsigned, None).is_ok());
+        // Los snapshots 1.0.0 son anteriores a la firma
+        assert!(predates_signing(&serde_json::from_str(V1_0_0).unwrap()));
+        assert!(!predates_signing(&snapshot));
+    }
+
+    #[test]
+    fn test_forged_legacy_snapshot_rejected() {
+        // Etiquetar como 1.0.0 un snapshot editado, quitarle la firma y recalcular el checksum
+        let mut forged: Value = serde_json::from_str(V1_2_0).unwrap();
+        forged["metadata"]["schema_version"] = json!("1.0.0");
+        forged["tournament"]["players"][0]["total_score"] = json!(9999);
+        forged.as_object_mut().unwrap().shift_remove("signature");
+        forged["checksum"] = json!("");
+        let checksum = format!("{:x}", sha2::Sha256::digest(serde_json::to_string(&forged).unwrap()));
+        forged["checksum"] = json!(checksum);
+        let forged = serde_json::to_string(&forged).unwrap();
+
+        assert!(parse(&forged).unwrap_err().contains("no está firmado"));
+    }
+
+    #[test]
+    fn test_newer_version_rejected() {
+        let mut snapshot: Value = serde_json::from_str(V1_2_0).unwrap();
+        snapshot["metadata"]["schema_version"] = json!("9.0.0");
+        let err = migrate(&mut snapshot).unwrap_err();
+        assert!(err.contains("9.0.0"));
+    }
+}
@@ -12,6 +12,8 @@ pub struct Tournament {
     pub players: Vec<Player>,
     pub tiles_remaining: u8,  // Fichas restantes en la bolsa
     pub master_plays: Vec<MasterPlay>,  // Historial del jugador Master
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub training: bool,  // Torneo de entrenamiento: habilita el análisis por equity
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -27,6 +29,12 @@ pub struct Player {
     pub name: String,
     pub total_score: i32,
     pub plays: Vec<PlayerPlay>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,  // Categoría de la clasificación (p. ej. Élite, Sub-18)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub club: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub federation_id: Option<String>,  // Licencia o ID federativo
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -84,6 +92,65 @@ pub struct PlayerPlay {
     pub cumulative_score: i32,  // Puntuación acumulada hasta esta ronda
     pub difference_from_optimal: i32,  // Diferencia con la jugada óptima
     pub cumulative_difference: i32,  // Diferencia acumulada
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rank: Option<PlayRank>,  // Solo jugadas válidas enviadas a tiempo
+}
+
+/// Marcas que se añaden a la palabra de una jugada fuera de tiempo o inválida
+pub const LATE_MARK: &str = " (TIEMPO EXCEDIDO)";
+pub const INVALID_MARK: &str = " (INVÁLIDA)";
+
+impl PlayerPlay {
+    pub fn is_late(&self) -> bool {
+        self.word.ends_with(LATE_MARK)
+    }
+
+    pub fn is_invalid(&self) -> bool {
+        self.word.ends_with(INVALID_MARK)
+    }
+
+    /// Válida y a tiempo: la única que puntúa y entra en estadísticas
+    pub fn is_valid(&self) -> bool {
+        !self.is_late() && !self.is_invalid()
+    }
+
+    /// Palabra tal como la envió el jugador, sin marca
+    pub fn played_word(&self) -> &str {
+        self.word.strip_suffix(LATE_MARK)
+            .or_else(|| self.word.strip_suffix(INVALID_MARK))
+            .unwrap_or(&self.word)
+    }
+}
+
+/// Puesto de una jugada entre todas las válidas con el tablero y atril de la ronda
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PlayRank {
+    pub rank: usize,  // 1 + jugadas con más puntos (los empates comparten puesto)
+    pub plays_higher: usize,
+    pub total_plays: usize,
+}
+
+impl PlayRank {
+    /// `scores` son todas las puntuaciones posibles, de mayor a menor
+    pub fn among(scores: &[i32], score: i32) -> Self {
+        let plays_higher = scores.partition_point(|s| *s > score);
+        Self {
+            rank: plays_higher + 1,
+            plays_higher,
+            total_plays: scores.len(),
+        }
+    }
+}
+
+/// Jugada valorada por equity: puntos más el valor del resto del atril (KLV)
+#[derive(Debug, Clone, Serialize)]
+pub struct EquityPlay {
+    pub word: String,
+    pub position: Position,
+    pub score: i32,
+    pub leave: String,
+    pub leave_value: f32,
+    pub equity: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,12 +167,58 @@ pub struct MasterPlay {
 pub struct CreateTournamentRequest {
     pub name: String,
     pub player_names: Vec<String>,
+    #[serde(default)]
+    pub arbiter_passphrase: Option<String>,  // Crea la cuenta del árbitro principal
+}
+
+// Torneo que rejuega una partida a partir de su registro tabular
+#[derive(Debug, Deserialize)]
+pub struct ImportRecordRequest {
+    pub record: String,
+    #[serde(default)]
+    pub name: Option<String>,  // Por defecto, el nombre que trae el registro
+    #[serde(default)]
+    pub player_names: Vec<String>,
+    #[serde(default)]
+    pub arbiter_passphrase: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct CreateTournamentResponse {
     pub tournament: Tournament,
     pub player_url: String,
+    pub player_tokens: Vec<PlayerToken>,
+}
+
+// Token de acceso emitido para un jugador (solo se muestra una vez)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerToken {
+    pub player_id: Uuid,
+    pub name: String,
+    pub token: String,
+}
+
+// Inscripción masiva: CSV con nombre, club, categoría e ID federativo
+#[derive(Debug, Deserialize)]
+pub struct ImportPlayersRequest {
+    pub csv: String,
+}
+
+// Jugador inscrito por CSV, con su token para imprimir la credencial
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedPlayer {
+    pub player_id: Uuid,
+    pub name: String,
+    pub club: Option<String>,
+    pub category: Option<String>,
+    pub federation_id: Option<String>,
+    pub token: String,
+    pub player_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrainingModeRequest {
+    pub enabled: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -121,6 +234,8 @@ pub struct SubmitPlayRequest {
     pub round_number: u32,
     pub word: String,
     pub position: Position,
+    #[serde(default)]
+    pub hardware_id: Option<String>,  // Fingerprint del navegador
 }
 
 #[derive(Debug, Deserialize)]
@@ -172,6 +287,53 @@ pub struct RoundFeedback {
     pub optimal_score: i32,
     pub feedback_message: String,
     pub late_submission: bool,
+    #[serde(default)]
+    pub rank: Option<PlayRank>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdjustScoreRequest {
+    pub score: i32,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JournalReplayQuery {
+    pub up_to: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CoachingQuery {
+    pub player_id: Option<Uuid>,
+}
+
+// Progreso de envíos de la ronda para el árbitro (sin revelar palabras)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum SubmissionState {
+    Pending,
+    Submitted,
+    Late,
+    Invalid,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlayerSubmissionStatus {
+    pub player_id: Uuid,
+    pub name: String,
+    pub state: SubmissionState,
+    pub submitted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoundProgress {
+    pub round_number: u32,
+    pub timer_started: Option<DateTime<Utc>>,
+    pub seconds_remaining: Option<i64>,
+    pub submitted: usize,
+    pub pending: usize,
+    pub late: usize,
+    pub invalid: usize,
+    pub players: Vec<PlayerSubmissionStatus>,
 }
 
 // Log structures for detailed player performance
@@ -197,4 +359,81 @@ pub struct PlayerLogEntry {
     pub master_word: String,
     pub master_score: i32,
     pub master_cumulative: i32,
-}
\ No newline at end of file
+    #[serde(default)]
+    pub rank: Option<PlayRank>,
+}
+
+/// Fixtures compartidos por las pruebas de los módulos que trabajan con torneos
+#[cfg(test)]
+pub mod test_support {
+    use super::*;
+
+    pub fn empty_board() -> BoardState {
+        BoardState { tiles: vec![String::new(); 225] }
+    }
+
+    /// Ronda en curso sobre el tablero vacío, sin óptima calculada
+    pub fn active_round(number: u32, rack: &str) -> Round {
+        Round {
+            number,
+            rack: rack.to_string(),
+            board_state: empty_board(),
+            optimal_play: None,
+            optimal_revealed: false,
+            status: RoundStatus::Active,
+            rack_rejected: false,
+            rejection_reason: None,
+            timer_started: None,
+        }
+    }
+
+    /// Ronda terminada con el master revelado
+    pub fn completed_round(number: u32, rack: &str) -> Round {
+        Round { optimal_revealed: true, status: RoundStatus::Completed, ..active_round(number, rack) }
+    }
+
+    pub fn master(round_number: u32, word: &str, row: u8, col: u8, down: bool, score: i32, cumulative_score: i32) -> MasterPlay {
+        MasterPlay { round_number, word: word.to_string(), position: Position { row, col, down }, score, cumulative_score }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_play_rank_shares_ties() {
+        let scores = [42, 30, 30, 30, 12, 8];
+        assert_eq!(PlayRank::among(&scores, 42), PlayRank { rank: 1, plays_higher: 0, total_plays: 6 });
+        assert_eq!(PlayRank::among(&scores, 30), PlayRank { rank: 2, plays_higher: 1, total_plays: 6 });
+        assert_eq!(PlayRank::among(&scores, 12).rank, 5);
+        // Una puntuación que no generó el motor queda entre las vecinas
+        assert_eq!(PlayRank::among(&scores, 20).rank, 5);
+        assert_eq!(PlayRank::among(&scores, 50).rank, 1);
+        assert_eq!(PlayRank::among(&[], 10), PlayRank { rank: 1, plays_higher: 0, total_plays: 0 });
+    }
+
+    #[test]
+    fn test_play_marks() {
+        let play = |word: &str| PlayerPlay {
+            round_number: 1,
+            word: word.to_string(),
+            position: Position { row: 7, col: 7, down: false },
+            score: 0,
+            percentage_of_optimal: 0.0,
+            submitted_at: Utc::now(),
+            cumulative_score: 0,
+            difference_from_optimal: 0,
+            cumulative_difference: 0,
+            rank: None,
+        };
+        let late = play(&format!("CASA{}", LATE_MARK));
+        let invalid = play(&format!("SACOD{}", INVALID_MARK));
+        assert!(late.is_late() && !late.is_invalid() && !late.is_valid());
+        assert!(invalid.is_invalid() && !invalid.is_late());
+        assert_eq!(late.played_word(), "CASA");
+        assert_eq!(invalid.played_word(), "SACOD");
+        assert!(play("CASA").is_valid());
+        assert_eq!(play("CASA").played_word(), "CASA");
+    }
+}
@@ -1,6 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::{Arc, RwLock};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum PersistenceMode {
@@ -15,6 +14,9 @@ pub enum PersistenceMode {
     
     /// Ambos con prioridad cloud - escribe cloud primero, local como backup
     DualCloudFirst,
+    
+    /// Archivos JSON más base SQLite local - archivo consultable, sin red
+    LocalSqlite,
 }
 
 impl Default for PersistenceMode {
@@ -27,6 +29,8 @@ impl Default for PersistenceMode {
 pub struct PersistenceConfig {
     mode: Arc<RwLock<PersistenceMode>>,
     cloud_available: Arc<RwLock<bool>>,
+    /// Modo elegido antes de caer a local por una caída de la nube
+    fallback_from: Arc<RwLock<Option<PersistenceMode>>>,
 }
 
 impl PersistenceConfig {
@@ -34,55 +38,81 @@ impl PersistenceConfig {
         Self {
             mode: Arc::new(RwLock::new(PersistenceMode::default())),
             cloud_available: Arc::new(RwLock::new(false)),
+            fallback_from: Arc::new(RwLock::new(None)),
         }
     }
     
+    /// Cambio explícito de modo; descarta cualquier modo pendiente de restaurar
     pub async fn set_mode(&self, mode: PersistenceMode) {
-        let mut current_mode = self.mode.write().await;
-        *current_mode = mode;
+        *self.fallback_from.write().unwrap_or_else(|e| e.into_inner()) = None;
+        self.switch_mode(mode);
+    }
+    
+    fn switch_mode(&self, mode: PersistenceMode) {
+        *self.mode.write().unwrap_or_else(|e| e.into_inner()) = mode;
         log::info!("🔄 Persistence mode changed to: {:?}", mode);
     }
     
     pub async fn get_mode(&self) -> PersistenceMode {
-        *self.mode.read().await
+        self.current_mode()
+    }
+    
+    /// Lectura sin await para el almacenamiento, que escribe desde código síncrono
+    pub fn current_mode(&self) -> PersistenceMode {
+        *self.mode.read().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    pub fn cloud_ready(&self) -> bool {
+        *self.cloud_available.read().unwrap_or_else(|e| e.into_inner())
     }
     
     pub async fn set_cloud_status(&self, available: bool) {
-        let mut status = self.cloud_available.write().await;
-        *status = available;
+        *self.cloud_available.write().unwrap_or_else(|e| e.into_inner()) = available;
         
         if !available {
             log::warn!("☁️ Cloud persistence unavailable - falling back to local");
-            // Auto-switch to LocalOnly if cloud fails
-            let current_mode = *self.mode.read().await;
-            if current_mode == PersistenceMode::CloudOnly {
-                self.set_mode(PersistenceMode::LocalOnly).await;
+            // Los modos que dependen de la nube pasan a local primero hasta que vuelva
+            let current_mode = self.current_mode();
+            if matches!(current_mode, PersistenceMode::CloudOnly | PersistenceMode::DualCloudFirst) {
+                *self.fallback_from.write().unwrap_or_else(|e| e.into_inner()) = Some(current_mode);
+                self.switch_mode(PersistenceMode::DualLocalFirst);
             }
         } else {
             log::info!("✅ Cloud persistence available");
+            let previous = self.fallback_from.write().unwrap_or_else(|e| e.into_inner()).take();
+            if let Some(mode) = previous {
+                self.switch_mode(mode);
+            }
         }
     }
     
     pub async fn is_cloud_available(&self) -> bool {
-        *self.cloud_available.read().await
+        self.cloud_ready()
     }
     
-    pub async fn should_write_local(&self) -> bool {
-        let mode = self.get_mode().await;
-        matches!(mode, PersistenceMode::LocalOnly | PersistenceMode::DualLocalFirst | PersistenceMode::DualCloudFirst)
+    pub fn should_write_local(&self) -> bool {
+        let mode = self.current_mode();
+        matches!(mode, PersistenceMode::LocalOnly | PersistenceMode::DualLocalFirst | PersistenceMode::DualCloudFirst | PersistenceMode::LocalSqlite)
     }
     
-    pub async fn should_write_cloud(&self) -> bool {
-        let mode = self.get_mode().await;
-        let cloud_available = self.is_cloud_available().await;
-        
-        cloud_available && matches!(mode, PersistenceMode::CloudOnly | PersistenceMode::DualLocalFirst | PersistenceMode::DualCloudFirst)
+    pub fn should_write_sqlite(&self) -> bool {
+        self.current_mode() == PersistenceMode::LocalSqlite
+    }
+    
+    /// El modo sube datos a la nube, esté disponible o no en este momento
+    pub fn uses_cloud(&self) -> bool {
+        let mode = self.current_mode();
+        matches!(mode, PersistenceMode::CloudOnly | PersistenceMode::DualLocalFirst | PersistenceMode::DualCloudFirst)
+    }
+    
+    pub fn should_write_cloud(&self) -> bool {
+        self.cloud_ready() && self.uses_cloud()
     }
     
-    pub async fn get_write_priority(&self) -> WritePriority {
-        let mode = self.get_mode().await;
+    pub fn get_write_priority(&self) -> WritePriority {
+        let mode = self.current_mode();
         match mode {
-            PersistenceMode::LocalOnly => WritePriority::LocalOnly,
+            PersistenceMode::LocalOnly | PersistenceMode::LocalSqlite => WritePriority::LocalOnly,
             PersistenceMode::CloudOnly => WritePriority::CloudOnly,
             PersistenceMode::DualLocalFirst => WritePriority::LocalThenCloud,
             PersistenceMode::DualCloudFirst => WritePriority::CloudThenLocal,
@@ -96,4 +126,30 @@ pub enum WritePriority {
     CloudOnly,
     LocalThenCloud,
     CloudThenLocal,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cloud_outage_falls_back_and_restores_mode() {
+        let config = PersistenceConfig::new();
+        config.set_cloud_status(true).await;
+        config.set_mode(PersistenceMode::CloudOnly).await;
+
+        config.set_cloud_status(false).await;
+        assert_eq!(config.current_mode(), PersistenceMode::DualLocalFirst);
+        assert!(config.should_write_local());
+        assert!(!config.should_write_cloud());
+
+        config.set_cloud_status(true).await;
+        assert_eq!(config.current_mode(), PersistenceMode::CloudOnly);
+
+        // Un cambio manual durante la caída gana sobre el modo anterior
+        config.set_cloud_status(false).await;
+        config.set_mode(PersistenceMode::LocalOnly).await;
+        config.set_cloud_status(true).await;
+        assert_eq!(config.current_mode(), PersistenceMode::LocalOnly);
+    }
 }
\ No newline at end of file
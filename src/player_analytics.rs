@@ -0,0 +1,228 @@
+// Análisis del rendimiento de un jugador a lo largo de la partida
+//
+// Amplía el log del jugador con lo que piden los entrenadores: masters
+// encontrados, scrabbles jugados y perdidos, manejo de comodines, tasa de
+// jugadas inválidas, tiempo medio de envío y la tendencia del porcentaje. Solo
+// cuentan las rondas con master colocado: en ellas se conoce el tablero previo
+// (reconstruido con gcg::master_turns) y qué fichas puso cada jugada.
+
+use serde::Serialize;
+use uuid::Uuid;
+use crate::gcg::{self, MasterTurn};
+use crate::models::{PlayerPlay, Tournament};
+
+/// Fichas de un atril completo; ponerlas todas es un scrabble
+const BINGO_TILES: usize = 7;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BlankStats {
+    pub rounds_with_blank: usize,  // Rondas con al menos un comodín en el atril
+    pub used_by_player: usize,
+    pub used_by_master: usize,
+    pub average_percentage: f32,  // Porcentaje medio en esas rondas
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundAnalysis {
+    pub round_number: u32,
+    pub percentage: f32,
+    pub cumulative_percentage: f32,
+    pub found_master: bool,
+    pub bingo: bool,
+    pub master_bingo: bool,
+    pub submission_secs: Option<i64>,  // Desde el inicio del temporizador
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerAnalytics {
+    pub player_name: String,
+    pub rounds: usize,
+    pub masters_found: usize,
+    pub bingos_played: usize,
+    pub bingos_missed: usize,  // El master fue scrabble y el jugador no lo hizo
+    pub blanks: BlankStats,
+    pub invalid_plays: usize,
+    pub late_plays: usize,
+    pub missed_rounds: usize,
+    pub invalid_rate: f32,  // Inválidas sobre rondas jugadas, en porcentaje
+    pub average_submission_secs: Option<f32>,
+    pub percentage_trend: f32,  // Puntos de porcentaje por ronda (recta de mínimos cuadrados)
+    pub by_round: Vec<RoundAnalysis>,
+}
+
+fn has_blank(tiles: &[String]) -> bool {
+    tiles.iter().any(|t| t.chars().any(|c| c.is_lowercase()))
+}
+
+/// Pendiente de la recta que mejor ajusta (x, y); 0 con menos de dos puntos
+fn slope(points: &[(f32, f32)]) -> f32 {
+    let n = points.len() as f32;
+    if points.len() < 2 {
+        return 0.0;
+    }
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f32>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f32>() / n;
+    let numerator: f32 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let denominator: f32 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if denominator == 0.0 { 0.0 } else { numerator / denominator }
+}
+
+fn analyse_round(turn: &MasterTurn, play: Option<&PlayerPlay>, player_cumulative: i32) -> RoundAnalysis {
+    let valid = play.filter(|p| p.is_valid());
+    let placed = valid.and_then(|p| turn.tiles_placed_by(&p.position, &p.word)).unwrap_or_default();
+    RoundAnalysis {
+        round_number: turn.master.round_number,
+        percentage: play.map(|p| p.percentage_of_optimal).unwrap_or(0.0),
+        cumulative_percentage: if turn.master.cumulative_score > 0 {
+            player_cumulative as f32 / turn.master.cumulative_score as f32 * 100.0
+        } else {
+            100.0
+        },
+        found_master: valid.is_some_and(|p| p.score >= turn.master.score),
+        bingo: placed.len() == BINGO_TILES,
+        master_bingo: turn.placed_tiles().len() == BINGO_TILES,
+        submission_secs: play.zip(turn.round.timer_started)
+            .map(|(p, started)| p.submitted_at.signed_duration_since(started).num_seconds()),
+    }
+}
+
+pub fn compute(tournament: &Tournament, player_id: &Uuid) -> Result<PlayerAnalytics, String> {
+    let player = tournament.players.iter()
+        .find(|p| &p.id == player_id)
+        .ok_or("Player not found")?;
+
+    let turns = gcg::master_turns(tournament);
+    let mut by_round = Vec::with_capacity(turns.len());
+    let mut blanks = BlankStats { rounds_with_blank: 0, used_by_player: 0, used_by_master: 0, average_percentage: 0.0 };
+    let (mut invalid_plays, mut late_plays, mut missed_rounds) = (0, 0, 0);
+    let mut cumulative = 0;
+
+    for turn in &turns {
+        let play = player.plays.iter().find(|p| p.round_number == turn.master.round_number);
+        match play {
+            None => missed_rounds += 1,
+            Some(p) if p.is_late() => late_plays += 1,
+            Some(p) if p.is_invalid() => invalid_plays += 1,
+            Some(_) => {}
+        }
+        cumulative += play.map(|p| p.score).unwrap_or(0);
+        let analysis = analyse_round(turn, play, cumulative);
+
+        if turn.round.rack.contains('?') {
+            blanks.rounds_with_blank += 1;
+            blanks.average_percentage += analysis.percentage;
+            let placed = play.filter(|p| p.is_valid())
+                .and_then(|p| turn.tiles_placed_by(&p.position, &p.word))
+                .unwrap_or_default();
+            if has_blank(&placed) {
+                blanks.used_by_player += 1;
+            }
+            if has_blank(&turn.placed_tiles()) {
+                blanks.used_by_master += 1;
+            }
+        }
+        by_round.push(analysis);
+    }
+    if blanks.rounds_with_blank > 0 {
+        blanks.average_percentage /= blanks.rounds_with_blank as f32;
+    }
+
+    let played = turns.len() - missed_rounds;
+    let submission_times: Vec<i64> = by_round.iter().filter_map(|r| r.submission_secs).collect();
+    let trend_points: Vec<(f32, f32)> = by_round.iter().map(|r| (r.round_number as f32, r.percentage)).collect();
+
+    Ok(PlayerAnalytics {
+        player_name: player.name.clone(),
+        rounds: turns.len(),
+        masters_found: by_round.iter().filter(|r| r.found_master).count(),
+        bingos_played: by_round.iter().filter(|r| r.bingo).count(),
+        bingos_missed: by_round.iter().filter(|r| r.master_bingo && !r.bingo).count(),
+        blanks,
+        invalid_plays,
+        late_plays,
+        missed_rounds,
+        invalid_rate: if played > 0 { invalid_plays as f32 / played as f32 * 100.0 } else { 0.0 },
+        average_submission_secs: if submission_times.is_empty() {
+            None
+        } else {
+            Some(submission_times.iter().sum::<i64>() as f32 / submission_times.len() as f32)
+        },
+        percentage_trend: slope(&trend_points),
+        by_round,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use crate::models::{Player, Position, Round, TournamentStatus};
+    use crate::models::test_support::{completed_round, master};
+
+    fn round(number: u32, rack: &str) -> Round {
+        Round { timer_started: Some(Utc::now() - Duration::seconds(600)), ..completed_round(number, rack) }
+    }
+
+    fn play(round_number: u32, word: &str, row: u8, col: u8, down: bool, score: i32, percentage: f32) -> PlayerPlay {
+        PlayerPlay {
+            round_number,
+            word: word.to_string(),
+            position: Position { row, col, down },
+            score,
+            percentage_of_optimal: percentage,
+            submitted_at: Utc::now(),
+            cumulative_score: 0,
+            difference_from_optimal: 0,
+            cumulative_difference: 0,
+            rank: None,
+        }
+    }
+
+    #[test]
+    fn test_player_analytics_bingos_blanks_and_trend() {
+        let mut rounds = vec![round(1, "CASADOS"), round(2, "?ERRO[LL]A"), round(3, "MESITAS")];
+        rounds[0].timer_started = Some(Utc::now() - Duration::seconds(60));
+        let player = Player {
+            id: Uuid::new_v4(),
+            name: "Ana".to_string(),
+            total_score: 0,
+            plays: vec![
+                // Scrabble como el master
+                play(1, "CASADOS", 7, 4, false, 80, 100.0),
+                // Usa el comodín a través de la C del master, pero no es scrabble
+                play(2, "CeRO", 7, 4, true, 10, 50.0),
+                play(3, "MESAS (INVÁLIDA)", 0, 0, false, 0, 0.0),
+            ],
+            category: None,
+            club: None,
+            federation_id: None,
+        };
+        let tournament = Tournament {
+            id: Uuid::new_v4(),
+            name: "Análisis".to_string(),
+            created_at: Utc::now(),
+            status: TournamentStatus::InProgress,
+            rounds,
+            players: vec![player.clone()],
+            tiles_remaining: 70,
+            master_plays: vec![
+                master(1, "CASADOS", 7, 4, false, 80, 80),
+                // (C) ya estaba en el tablero: siete fichas del atril, con comodín
+                master(2, "(C)eERRO[LL]A", 7, 4, true, 20, 100),
+                master(3, "MESITAS", 1, 0, false, 20, 120),
+            ],
+            training: false,
+        };
+
+        let analytics = compute(&tournament, &player.id).unwrap();
+        assert_eq!(analytics.rounds, 3);
+        assert_eq!(analytics.masters_found, 1);
+        assert_eq!((analytics.bingos_played, analytics.bingos_missed), (1, 2));
+        assert_eq!(analytics.blanks, BlankStats { rounds_with_blank: 1, used_by_player: 1, used_by_master: 1, average_percentage: 50.0 });
+        assert_eq!(analytics.invalid_plays, 1);
+        assert!((analytics.invalid_rate - 33.33).abs() < 0.01);
+        assert!(analytics.percentage_trend < 0.0);
+        assert_eq!(analytics.by_round[0].submission_secs, Some(60));
+        assert!((analytics.by_round[1].cumulative_percentage - 90.0).abs() < 0.01);
+    }
+}
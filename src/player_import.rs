@@ -0,0 +1,260 @@
+// Inscripción masiva de jugadores desde un CSV
+//
+// Columnas: nombre, club, categoría e ID federativo. Si la primera fila es una
+// cabecera reconocible se usa para ordenar las columnas; si no, se toman en ese
+// orden. Se admite `,` o `;` como separador (la hoja de cálculo en español
+// exporta con `;`). Todas las filas se validan antes de inscribir a nadie: si
+// alguna falla se devuelven los errores de cada fila y el torneo no cambia.
+
+use serde::Serialize;
+use crate::models::Player;
+
+const MAX_NAME_LEN: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
+    Name,
+    Club,
+    Category,
+    FederationId,
+}
+
+const POSITIONAL: [Column; 4] = [Column::Name, Column::Club, Column::Category, Column::FederationId];
+
+/// Una fila válida del CSV
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerRow {
+    pub line: usize,
+    pub name: String,
+    pub club: Option<String>,
+    pub category: Option<String>,
+    pub federation_id: Option<String>,
+}
+
+/// Error asociado a una fila del CSV (línea del fichero, empezando en 1)
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct RowError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl RowError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self { line, message: message.into() }
+    }
+}
+
+/// Importación rechazada; `rows` trae el detalle por fila cuando lo hay
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerImportError {
+    pub message: String,
+    pub rows: Vec<RowError>,
+}
+
+impl From<&str> for PlayerImportError {
+    fn from(message: &str) -> Self {
+        Self { message: message.to_string(), rows: Vec::new() }
+    }
+}
+
+fn header_column(field: &str) -> Option<Column> {
+    let normalized: String = field.trim().to_lowercase().chars()
+        .map(|c| match c {
+            'á' => 'a', 'é' => 'e', 'í' => 'i', 'ó' => 'o', 'ú' => 'u',
+            ' ' | '-' => '_',
+            c => c,
+        })
+        .collect();
+    match normalized.as_str() {
+        "nombre" | "name" | "jugador" | "player" => Some(Column::Name),
+        "club" => Some(Column::Club),
+        "categoria" | "category" => Some(Column::Category),
+        "federacion" | "id_federativo" | "licencia" | "federation_id" | "federation" => Some(Column::FederationId),
+        _ => None,
+    }
+}
+
+fn detect_delimiter(text: &str) -> char {
+    let first = text.lines().next().unwrap_or("");
+    if first.matches(';').count() > first.matches(',').count() { ';' } else { ',' }
+}
+
+/// Separa el texto en registros (línea inicial, campos); respeta comillas
+/// dobles, incluidos saltos de línea dentro de un campo
+fn records(text: &str, delimiter: char) -> Result<Vec<(usize, Vec<String>)>, RowError> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                in_quotes = true;
+            }
+            '\n' if in_quotes => {
+                line += 1;
+                field.push(c);
+            }
+            '\r' if !in_quotes => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                records.push((start, std::mem::take(&mut fields)));
+                line += 1;
+                start = line;
+            }
+            c if c == delimiter && !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(RowError::new(start, "Comillas sin cerrar"));
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push((start, fields));
+    }
+
+    // Las filas en blanco no cuentan
+    records.retain(|(_, fields)| fields.iter().any(|f| !f.trim().is_empty()));
+    Ok(records)
+}
+
+fn optional(value: Option<&String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+fn name_key(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Lee y valida el CSV. `existing` son los jugadores ya inscritos en el torneo,
+/// para rechazar nombres o IDs federativos repetidos.
+pub fn parse(text: &str, existing: &[Player]) -> Result<Vec<PlayerRow>, Vec<RowError>> {
+    let mut records = records(text, detect_delimiter(text)).map_err(|e| vec![e])?;
+
+    let columns: Vec<Option<Column>> = match records.first() {
+        Some((_, header)) if header.iter().any(|f| header_column(f) == Some(Column::Name)) => {
+            let (line, header) = records.remove(0);
+            let columns: Vec<Option<Column>> = header.iter().map(|f| header_column(f)).collect();
+            let unknown: Vec<&str> = header.iter()
+                .zip(&columns)
+                .filter(|(f, c)| c.is_none() && !f.trim().is_empty())
+                .map(|(f, _)| f.trim())
+                .collect();
+            if !unknown.is_empty() {
+                return Err(vec![RowError::new(line, format!("Columnas desconocidas: {}", unknown.join(", ")))]);
+            }
+            columns
+        }
+        _ => POSITIONAL.iter().copied().map(Some).collect(),
+    };
+
+    if records.is_empty() {
+        return Err(vec![RowError::new(1, "El CSV no contiene jugadores")]);
+    }
+
+    let mut rows: Vec<PlayerRow> = Vec::new();
+    let mut errors = Vec::new();
+    for (line, fields) in records {
+        if fields.len() > columns.len() {
+            errors.push(RowError::new(line, format!("{} columnas; se esperaban como máximo {}", fields.len(), columns.len())));
+            continue;
+        }
+        let value = |column: Column| columns.iter()
+            .position(|c| *c == Some(column))
+            .and_then(|i| fields.get(i));
+
+        let name = optional(value(Column::Name)).map(|n| n.split_whitespace().collect::<Vec<_>>().join(" "));
+        let row = PlayerRow {
+            line,
+            name: name.unwrap_or_default(),
+            club: optional(value(Column::Club)),
+            category: optional(value(Column::Category)),
+            federation_id: optional(value(Column::FederationId)),
+        };
+
+        if row.name.is_empty() {
+            errors.push(RowError::new(line, "Falta el nombre del jugador"));
+            continue;
+        }
+        if row.name.chars().count() > MAX_NAME_LEN {
+            errors.push(RowError::new(line, format!("El nombre supera los {} caracteres", MAX_NAME_LEN)));
+            continue;
+        }
+
+        let key = name_key(&row.name);
+        if existing.iter().any(|p| name_key(&p.name) == key) {
+            errors.push(RowError::new(line, format!("'{}' ya está inscrito en el torneo", row.name)));
+            continue;
+        }
+        if let Some(previous) = rows.iter().find(|r| name_key(&r.name) == key) {
+            errors.push(RowError::new(line, format!("'{}' está repetido (línea {})", row.name, previous.line)));
+            continue;
+        }
+
+        if let Some(federation_id) = &row.federation_id {
+            let same_id = |other: &Option<String>| other.as_ref().is_some_and(|id| id.eq_ignore_ascii_case(federation_id));
+            if let Some(player) = existing.iter().find(|p| same_id(&p.federation_id)) {
+                errors.push(RowError::new(line, format!("El ID federativo {} ya pertenece a '{}'", federation_id, player.name)));
+                continue;
+            }
+            if let Some(previous) = rows.iter().find(|r| same_id(&r.federation_id)) {
+                errors.push(RowError::new(line, format!("El ID federativo {} está repetido (línea {})", federation_id, previous.line)));
+                continue;
+            }
+        }
+
+        rows.push(row);
+    }
+
+    if errors.is_empty() { Ok(rows) } else { Err(errors) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_parse_header_quotes_and_duplicates() {
+        let csv = "\u{feff}Nombre;Categoría;Club;Licencia\r\n\
+                   Ana Pérez;Élite;\"Club \"\"Las Fichas\"\"; Madrid\";ESP-001\r\n\
+                   \r\n\
+                   Luis;;;\r\n";
+        let rows = parse(csv, &[]).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].club.as_deref(), Some("Club \"Las Fichas\"; Madrid"));
+        assert_eq!(rows[0].category.as_deref(), Some("Élite"));
+        assert_eq!(rows[0].federation_id.as_deref(), Some("ESP-001"));
+        assert_eq!((rows[1].line, rows[1].club.clone()), (4, None));
+
+        // Sin cabecera: columnas en orden nombre, club, categoría, ID
+        let existing = vec![Player {
+            id: Uuid::new_v4(),
+            name: "Marta".to_string(),
+            total_score: 0,
+            plays: Vec::new(),
+            category: None,
+            club: None,
+            federation_id: Some("ESP-009".to_string()),
+        }];
+        let csv = "Ana,Scrabble Sur,Sub-18,ESP-001\n,Club,,\nana  ,,,\nPedro,,,esp-001\nmarta,,,\nJuan,,,ESP-009\nEva,a,b,c,d\n";
+        let errors = parse(csv, &existing).unwrap_err();
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 3, 4, 5, 6, 7]);
+        assert!(errors[1].message.contains("línea 1"));
+    }
+}
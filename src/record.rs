@@ -0,0 +1,296 @@
+// Registro tabular de partidas duplicadas
+//
+// Es el formato con el que las federaciones intercambian partidas: una línea
+// por ronda con el residuo del atril anterior más las fichas nuevas
+// (AE+RSTLN), la palabra del master, su coordenada, sus puntos y el total
+// acumulado. Los atriles rechazados llevan "*" y el motivo al final de la
+// línea. Las líneas que empiezan con '#' son comentarios, salvo la del nombre
+// del torneo.
+//
+// Importado, el registro es una plantilla: un torneo nuevo reparte los mismos
+// atriles ronda a ronda para rejugar la partida.
+
+use serde::{Deserialize, Serialize};
+use wolges::alphabet;
+use crate::gcg::{self, split_tiles};
+use crate::models::{MasterPlay, Position, Tournament};
+use crate::tournament_manager::format_coordinate;
+
+const NAME_PREFIX: &str = "# Torneo:";
+const REJECTED_MARK: &str = "*";
+const RACK_SIZE: usize = 7;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateRound {
+    pub number: u32,
+    pub leftover: String,  // Residuo del atril anterior
+    pub drawn: String,  // Fichas sacadas de la bolsa en esta ronda
+    pub rack_rejected: bool,
+    pub rejection_reason: Option<String>,
+    pub master: MasterPlay,
+}
+
+impl TemplateRound {
+    pub fn rack(&self) -> String {
+        format!("{}{}", self.leftover, self.drawn)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameTemplate {
+    pub name: String,
+    pub rounds: Vec<TemplateRound>,
+}
+
+impl GameTemplate {
+    pub fn round(&self, number: u32) -> Option<&TemplateRound> {
+        self.rounds.iter().find(|r| r.number == number)
+    }
+}
+
+/// Ficha tal como vuelve al atril: un comodín jugado es de nuevo '?'
+fn rack_tile(tile: &str) -> String {
+    if tile.chars().any(|c| c.is_lowercase()) { "?".to_string() } else { tile.to_string() }
+}
+
+/// Quita de `tiles` una copia de cada ficha de `used`; devuelve las que sobran
+fn remove_tiles(tiles: &[String], used: &[String]) -> Vec<String> {
+    let mut remaining = tiles.to_vec();
+    for tile in used {
+        if let Some(pos) = remaining.iter().position(|t| t == tile) {
+            remaining.remove(pos);
+        }
+    }
+    remaining
+}
+
+/// Separa un atril en el residuo (lo que sale de `leave`) y las fichas nuevas
+fn split_rack(rack: &[String], leave: &[String]) -> (Vec<String>, Vec<String>) {
+    let drawn = remove_tiles(rack, leave);
+    let leftover = remove_tiles(rack, &drawn);
+    (leftover, drawn)
+}
+
+/// Registro de las rondas con master colocado
+pub fn export(tournament: &Tournament) -> String {
+    let mut record = format!(
+        "# Registro de partida duplicada\n{} {}\n# Ronda  Atril  Palabra  Coordenada  Puntos  Total\n",
+        NAME_PREFIX,
+        gcg::single_line(&tournament.name)
+    );
+
+    let mut leave: Vec<String> = Vec::new();
+    for turn in gcg::master_turns(tournament) {
+        let rack = split_tiles(&turn.round.rack);
+        // Un atril rechazado vuelve entero a la bolsa: no hay residuo
+        let (leftover, drawn) = if turn.round.rack_rejected {
+            (Vec::new(), rack.clone())
+        } else {
+            split_rack(&rack, &leave)
+        };
+        let placed: Vec<String> = turn.placed_tiles().iter().map(|t| rack_tile(t)).collect();
+        leave = remove_tiles(&rack, &placed);
+
+        let mut line = format!(
+            "{:<6} {:<18} {:<16} {:<5} {:>4} {:>5}",
+            turn.master.round_number,
+            format!("{}+{}", leftover.concat(), drawn.concat()),
+            turn.master.word,
+            format_coordinate(&turn.master.position),
+            turn.master.score,
+            turn.master.cumulative_score,
+        );
+        if turn.round.rack_rejected {
+            line.push_str(&format!(" {}", REJECTED_MARK));
+            if let Some(reason) = &turn.round.rejection_reason {
+                line.push_str(&format!(" {}", gcg::single_line(reason)));
+            }
+        }
+        record.push_str(line.trim_end());
+        record.push('\n');
+    }
+    record
+}
+
+/// Inversa de format_coordinate: "H8" horizontal (fila + columna), "8H" vertical
+fn parse_coordinate(coord: &str) -> Option<Position> {
+    let letters = "ABCDEFGHIJKLMNO";
+    let digits = coord.chars().take_while(|c| c.is_ascii_digit()).count();
+    let (down, row_letter, number) = if digits == 0 {
+        let (letter, number) = coord.split_at(coord.chars().next()?.len_utf8());
+        (false, letter, number)
+    } else {
+        let (number, letter) = coord.split_at(digits);
+        (true, letter, number)
+    };
+    let row = letters.find(row_letter.to_ascii_uppercase().as_str()).filter(|_| row_letter.len() == 1)?;
+    let col = number.parse::<usize>().ok()?.checked_sub(1).filter(|c| *c < 15)?;
+    Some(Position { row: row as u8, col: col as u8, down })
+}
+
+/// Lee un registro exportado con `export` (o escrito a mano con el mismo formato)
+pub fn parse(text: &str) -> Result<GameTemplate, String> {
+    let alphabet = alphabet::make_spanish_alphabet();
+    let racks = alphabet::AlphabetReader::new_for_racks(&alphabet);
+    let plays = alphabet::AlphabetReader::new_for_plays(&alphabet);
+    let mut tiles = Vec::new();
+
+    let mut name = None;
+    let mut rounds: Vec<TemplateRound> = Vec::new();
+    let mut board = vec![String::new(); 225];
+    let mut leave: Vec<String> = Vec::new();
+    let mut total = 0;
+
+    for (line_number, line) in (1usize..).zip(text.lines()) {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix(NAME_PREFIX) {
+            name = Some(rest.trim().to_string());
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let err = |msg: String| format!("Línea {}: {}", line_number, msg);
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.len() < 6 {
+            return Err(err("se esperan ronda, atril, palabra, coordenada, puntos y total".to_string()));
+        }
+
+        let number: u32 = tokens[0].parse().map_err(|_| err(format!("ronda inválida '{}'", tokens[0])))?;
+        if number as usize != rounds.len() + 1 {
+            return Err(err(format!("se esperaba la ronda {}", rounds.len() + 1)));
+        }
+
+        let (leftover, drawn) = tokens[1].split_once('+')
+            .ok_or_else(|| err(format!("el atril '{}' no separa residuo y fichas nuevas con '+'", tokens[1])))?;
+        for part in [leftover, drawn] {
+            racks.set_word(part, &mut tiles).map_err(|_| err(format!("ficha inválida en el atril '{}'", tokens[1])))?;
+        }
+        let rack = split_tiles(&format!("{}{}", leftover, drawn));
+        if rack.is_empty() || rack.len() > RACK_SIZE {
+            return Err(err(format!("el atril debe tener entre 1 y {} fichas", RACK_SIZE)));
+        }
+
+        let rack_rejected = match tokens.get(6) {
+            None => false,
+            Some(&REJECTED_MARK) => true,
+            Some(other) => return Err(err(format!("texto inesperado '{}' tras el total", other))),
+        };
+        let rejection_reason = Some(tokens[7.min(tokens.len())..].join(" ")).filter(|r| !r.is_empty());
+        let leftover_tiles = split_tiles(leftover);
+        if rack_rejected && !leftover_tiles.is_empty() {
+            return Err(err("un atril rechazado no conserva residuo".to_string()));
+        }
+        if !remove_tiles(&leftover_tiles, &leave).is_empty() {
+            return Err(err(format!("el residuo '{}' no sale del atril anterior", leftover)));
+        }
+
+        let word = tokens[2];
+        plays.set_word(&word.replace(['(', ')'], ""), &mut tiles)
+            .map_err(|_| err(format!("ficha inválida en la palabra '{}'", word)))?;
+        let position = parse_coordinate(tokens[3])
+            .ok_or_else(|| err(format!("coordenada inválida '{}'", tokens[3])))?;
+        let score: i32 = tokens[4].parse().map_err(|_| err(format!("puntos inválidos '{}'", tokens[4])))?;
+        let cumulative_score: i32 = tokens[5].parse().map_err(|_| err(format!("total inválido '{}'", tokens[5])))?;
+        if cumulative_score != total + score {
+            return Err(err(format!("el total {} no cuadra con {} + {}", cumulative_score, total, score)));
+        }
+        total = cumulative_score;
+
+        // Las fichas que pone el master tienen que salir del atril
+        let placed: Vec<String> = gcg::gcg_word(&board, &position, word)
+            .map(|w| split_tiles(&w).into_iter().filter(|t| t != ".").map(|t| rack_tile(&t)).collect())
+            .ok_or_else(|| err(format!("la palabra '{}' se sale del tablero", word)))?;
+        leave = remove_tiles(&rack, &placed);
+        if leave.len() + placed.len() != rack.len() {
+            return Err(err(format!("'{}' usa fichas que no están en el atril", word)));
+        }
+        gcg::place_word(&mut board, &position, word);
+
+        rounds.push(TemplateRound {
+            number,
+            leftover: leftover.to_string(),
+            drawn: drawn.to_string(),
+            rack_rejected,
+            rejection_reason,
+            master: MasterPlay { round_number: number, word: word.to_string(), position, score, cumulative_score },
+        });
+    }
+
+    if rounds.is_empty() {
+        return Err("El registro no tiene rondas".to_string());
+    }
+    Ok(GameTemplate {
+        name: name.filter(|n| !n.is_empty()).unwrap_or_else(|| "Partida importada".to_string()),
+        rounds,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use crate::models::{Round, TournamentStatus};
+    use crate::models::test_support::{completed_round, master};
+
+    fn round(number: u32, rack: &str, rejection_reason: Option<&str>) -> Round {
+        Round {
+            rack_rejected: rejection_reason.is_some(),
+            rejection_reason: rejection_reason.map(str::to_string),
+            ..completed_round(number, rack)
+        }
+    }
+
+    fn tournament() -> Tournament {
+        Tournament {
+            id: Uuid::new_v4(),
+            name: "Abierto de prueba".to_string(),
+            created_at: Utc::now(),
+            status: TournamentStatus::InProgress,
+            rounds: vec![
+                round(1, "[CH]ARCOSE", None),
+                round(2, "SEA?T[RR]A", None),
+                round(3, "UUIIOAE", Some("Más de 5 vocales")),
+            ],
+            players: Vec::new(),
+            tiles_remaining: 79,
+            master_plays: vec![
+                master(1, "[CH]ARCO", 7, 7, false, 28, 28),
+                master(2, "[rr]AS(C)ATE", 4, 10, true, 25, 53),
+                master(3, "IO(A)U", 5, 8, true, 6, 59),
+            ],
+            training: false,
+        }
+    }
+
+    #[test]
+    fn test_record_marks_leftover_and_rejections() {
+        let record = export(&tournament());
+        let rounds: Vec<Vec<&str>> = record.lines()
+            .filter(|l| !l.starts_with('#'))
+            .map(|l| l.split_whitespace().collect())
+            .collect();
+
+        assert_eq!(rounds[0], vec!["1", "+[CH]ARCOSE", "[CH]ARCO", "H8", "28", "28"]);
+        assert_eq!(rounds[1], vec!["2", "SE+A?T[RR]A", "[rr]AS(C)ATE", "11E", "25", "53"]);
+        assert_eq!(rounds[2], vec!["3", "+UUIIOAE", "IO(A)U", "9F", "6", "59", "*", "Más", "de", "5", "vocales"]);
+    }
+
+    #[test]
+    fn test_record_round_trips_as_template() {
+        let template = parse(&export(&tournament())).unwrap();
+
+        assert_eq!(template.name, "Abierto de prueba");
+        assert_eq!(template.rounds.len(), 3);
+        assert_eq!(template.round(2).unwrap().rack(), "SEA?T[RR]A");
+        let position = &template.round(2).unwrap().master.position;
+        assert_eq!((position.row, position.col, position.down), (4, 10, true));
+        assert!(template.round(3).unwrap().rack_rejected);
+        assert_eq!(template.round(3).unwrap().rejection_reason.as_deref(), Some("Más de 5 vocales"));
+
+        let err = parse("1 AE+RSTLN ERA H8 3 3\n").unwrap_err();
+        assert!(err.starts_with("Línea 1"), "{}", err);
+    }
+}
@@ -0,0 +1,175 @@
+// Recuperación automática de torneos en curso al iniciar el servidor
+//
+// Se buscan los snapshots con estado InProgress, se verifican (checksum y
+// diario), se restauran en el manager y se reconcilian los temporizadores
+// con la hora actual. El informe queda disponible para el árbitro.
+
+use serde::Serialize;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use crate::events;
+use crate::models::{RoundStatus, TournamentStatus};
+use crate::persistence::{LoadedTournament, PersistenceManager};
+use crate::tournament_manager::TournamentManager;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveredTournament {
+    pub tournament_id: Uuid,
+    pub name: String,
+    pub current_round: u32,
+    pub players: usize,
+    pub tiles_in_bag: u8,
+    pub journal_seq: u64,
+    pub timer: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryFailure {
+    pub tournament_id: String,
+    pub folder_path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryReport {
+    pub ran_at: DateTime<Utc>,
+    pub recovered: Vec<RecoveredTournament>,
+    pub failed: Vec<RecoveryFailure>,
+}
+
+pub fn recover_in_progress(manager: &mut TournamentManager) -> RecoveryReport {
+    let mut report = RecoveryReport {
+        ran_at: Utc::now(),
+        recovered: Vec::new(),
+        failed: Vec::new(),
+    };
+
+    let candidates = match PersistenceManager::list_tournaments() {
+        Ok(list) => list,
+        Err(e) => {
+            log::error!("No se pudo listar los torneos guardados: {}", e);
+            return report;
+        }
+    };
+
+    for item in candidates.into_iter().filter(|t| t.status == format!("{:?}", TournamentStatus::InProgress)) {
+        let loaded = match PersistenceManager::load_tournament(&item.id) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                log::error!("No se pudo recuperar el torneo '{}' ({}): {}", item.name, item.id, e);
+                report.failed.push(RecoveryFailure {
+                    tournament_id: item.id,
+                    folder_path: item.folder_path,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        // El diario puede haber terminado el torneo después del último snapshot
+        if loaded.tournament.status != TournamentStatus::InProgress {
+            continue;
+        }
+
+        if let Some(recovered) = restore_in_progress(manager, loaded) {
+            report.recovered.push(recovered);
+        }
+    }
+
+    report
+}
+
+/// Restaura un torneo en curso y reconcilia el temporizador de la ronda activa
+fn restore_in_progress(manager: &mut TournamentManager, loaded: LoadedTournament) -> Option<RecoveredTournament> {
+    let tournament_id = loaded.tournament.id;
+    manager.restore_tournament(loaded);
+
+    let tournament = manager.get_tournament(&tournament_id)?;
+    let timer = tournament.rounds.last()
+        .filter(|r| r.status == RoundStatus::Active && !r.optimal_revealed)
+        .and_then(|round| round.timer_started.map(|started| (round.number, started)))
+        .map(|(round_number, started)| reconcile_timer(manager, tournament_id, round_number, started));
+
+    log::info!("♻️ Torneo '{}' recuperado en la ronda {}", tournament.name, tournament.rounds.len());
+    Some(RecoveredTournament {
+        tournament_id,
+        name: tournament.name.clone(),
+        current_round: tournament.rounds.len() as u32,
+        players: tournament.players.len(),
+        tiles_in_bag: tournament.tiles_remaining,
+        journal_seq: manager.journal_seq(&tournament_id),
+        timer,
+    })
+}
+
+/// Reprograma los avisos del temporizador según el tiempo real transcurrido
+fn reconcile_timer(manager: &TournamentManager, tournament_id: Uuid, round_number: u32, started_at: DateTime<Utc>) -> String {
+    let elapsed = Utc::now().signed_duration_since(started_at).num_seconds();
+    let remaining = events::ROUND_TIMER_SECS - elapsed;
+    if remaining > 0 {
+        manager.events.spawn_timer_phases(tournament_id, round_number, started_at);
+        format!("Ronda {}: quedan {} segundos del temporizador", round_number, remaining)
+    } else {
+        format!("Ronda {}: el tiempo terminó hace {} segundos, mientras el servidor estaba detenido", round_number, -remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::models::{Round, Tournament};
+    use crate::models::test_support::active_round;
+    use crate::storage::memory::InMemoryStorage;
+
+    fn in_progress(timer_started: DateTime<Utc>) -> LoadedTournament {
+        let round = Round { timer_started: Some(timer_started), ..active_round(1, "CASEROS") };
+        LoadedTournament {
+            tournament: Tournament {
+                id: Uuid::new_v4(),
+                name: "Interrumpido".to_string(),
+                created_at: Utc::now(),
+                status: TournamentStatus::InProgress,
+                rounds: vec![round],
+                players: Vec::new(),
+                tiles_remaining: 100,
+                master_plays: Vec::new(),
+                training: false,
+            },
+            player_sessions: Vec::new(),
+            arbiter_accounts: Vec::new(),
+            device_policy: None,
+            device_alerts: Vec::new(),
+            round_devices: Vec::new(),
+            journal_seq: 3,
+        }
+    }
+
+    #[test]
+    fn test_restore_reconciles_bag_and_timer() {
+        let storage = Arc::new(InMemoryStorage::default());
+        let mut manager = TournamentManager::new(std::net::IpAddr::from([127, 0, 0, 1]));
+        manager.set_storage(storage.clone());
+        manager.load_dictionary("FISE2016_converted.kwg", None).unwrap();
+        let full_bag = wolges::bag::Bag::new(manager.engine.as_ref().unwrap().get_alphabet()).0.len();
+
+        let loaded = in_progress(Utc::now() - chrono::Duration::seconds(60));
+        let id = loaded.tournament.id;
+        let recovered = restore_in_progress(&mut manager, loaded).unwrap();
+
+        // El atril activo sale de la bolsa; la corrección queda en diario y snapshot
+        let expected = (full_bag - 7) as u8;
+        assert_eq!(recovered.tiles_in_bag, expected);
+        assert_eq!(storage.snapshot(&id).unwrap().tournament.tiles_remaining, expected);
+        let journal = storage.journal(&id);
+        assert_eq!(journal.len(), 1);
+        assert_eq!(journal[0].seq, 4);
+        assert!(matches!(&journal[0].event, crate::journal::JournalEvent::Checkpoint { tournament } if tournament.tiles_remaining == expected));
+        assert!(recovered.timer.unwrap().contains(&format!("quedan {}", events::ROUND_TIMER_SECS - 60)));
+
+        // Un temporizador vencido durante la caída no se reprograma
+        let expired = in_progress(Utc::now() - chrono::Duration::seconds(events::ROUND_TIMER_SECS + 30));
+        let recovered = restore_in_progress(&mut manager, expired).unwrap();
+        assert!(recovered.timer.unwrap().contains("el tiempo terminó hace 30 segundos"));
+    }
+}
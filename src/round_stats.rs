@@ -0,0 +1,220 @@
+// Estadísticas de cada ronda, para que el árbitro las anuncie tras revelar el
+// master y para el histórico de la partida
+//
+// Se calculan a partir de las PlayerPlay de la ronda. Las jugadas fuera de
+// tiempo o inválidas se cuentan aparte y no entran en la distribución ni en la
+// jugada más repetida; en el porcentaje medio cuentan como 0, igual que quien
+// no jugó.
+
+use serde::Serialize;
+use crate::models::{PlayerPlay, Position, Tournament};
+use crate::tournament_manager::format_coordinate;
+
+/// Ancho de cada tramo de la distribución, en porcentaje del master
+const BUCKET_WIDTH: u32 = 10;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ScoreBucket {
+    pub min_percentage: u32,
+    pub max_percentage: u32,  // El tramo superior es exactamente 100
+    pub players: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommonPlay {
+    pub word: String,
+    pub coordinate: String,
+    pub score: i32,
+    pub players: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundStats {
+    pub round_number: u32,
+    pub master_word: String,
+    pub master_score: i32,
+    pub players: usize,
+    pub submitted: usize,
+    pub missing: usize,
+    pub invalid: usize,
+    pub late: usize,
+    pub found_master: usize,  // Jugadores que igualaron la puntuación del master
+    pub average_percentage: f32,
+    pub average_score: f32,  // Media de las jugadas válidas
+    pub best_score: Option<i32>,
+    pub distribution: Vec<ScoreBucket>,  // De 100 % hacia abajo
+    pub most_common_play: Option<CommonPlay>,
+}
+
+fn same_position(a: &Position, b: &Position) -> bool {
+    (a.row, a.col, a.down) == (b.row, b.col, b.down)
+}
+
+fn distribution(percentages: &[f32]) -> Vec<ScoreBucket> {
+    let mut buckets = vec![ScoreBucket { min_percentage: 100, max_percentage: 100, players: 0 }];
+    buckets.extend((0..100 / BUCKET_WIDTH).rev().map(|i| ScoreBucket {
+        min_percentage: i * BUCKET_WIDTH,
+        max_percentage: (i + 1) * BUCKET_WIDTH - 1,
+        players: 0,
+    }));
+    for percentage in percentages {
+        let index = if *percentage >= 100.0 {
+            0
+        } else {
+            let tier = (percentage.max(0.0) as u32) / BUCKET_WIDTH;
+            buckets.len() - 1 - tier as usize
+        };
+        buckets[index].players += 1;
+    }
+    buckets
+}
+
+/// Estadísticas de una ronda; `None` si no existe o su master no se ha revelado
+pub fn compute(tournament: &Tournament, round_number: u32) -> Option<RoundStats> {
+    let round = tournament.rounds.iter().find(|r| r.number == round_number)?;
+    if !round.optimal_revealed {
+        return None;
+    }
+    let (master_word, master_score) = match tournament.master_plays.iter().find(|mp| mp.round_number == round_number) {
+        Some(master) => (master.word.clone(), master.score),
+        None => round.optimal_play.as_ref().map(|op| (op.word.clone(), op.score))?,
+    };
+
+    let plays: Vec<&PlayerPlay> = tournament.players.iter()
+        .filter_map(|p| p.plays.iter().find(|play| play.round_number == round_number))
+        .collect();
+    let valid: Vec<&PlayerPlay> = plays.iter().copied().filter(|p| p.is_valid()).collect();
+
+    let players = tournament.players.len();
+    let percentages: Vec<f32> = valid.iter().map(|p| p.percentage_of_optimal).collect();
+
+    // Jugada más repetida: misma palabra en la misma posición; a igualdad, la de más puntos
+    let mut groups: Vec<(&PlayerPlay, usize)> = Vec::new();
+    for play in &valid {
+        match groups.iter_mut().find(|(p, _)| p.word == play.word && same_position(&p.position, &play.position)) {
+            Some((_, count)) => *count += 1,
+            None => groups.push((play, 1)),
+        }
+    }
+    let most_common_play = groups.into_iter()
+        .max_by(|(a, ca), (b, cb)| ca.cmp(cb).then_with(|| a.score.cmp(&b.score)).then_with(|| b.word.cmp(&a.word)))
+        .map(|(play, players)| CommonPlay {
+            word: play.word.clone(),
+            coordinate: format_coordinate(&play.position),
+            score: play.score,
+            players,
+        });
+
+    Some(RoundStats {
+        round_number,
+        master_word,
+        master_score,
+        players,
+        submitted: plays.len(),
+        missing: players - plays.len(),
+        invalid: plays.iter().filter(|p| p.is_invalid()).count(),
+        late: plays.iter().filter(|p| p.is_late()).count(),
+        found_master: valid.iter().filter(|p| p.score >= master_score).count(),
+        average_percentage: if players > 0 { percentages.iter().sum::<f32>() / players as f32 } else { 0.0 },
+        average_score: if valid.is_empty() {
+            0.0
+        } else {
+            valid.iter().map(|p| p.score).sum::<i32>() as f32 / valid.len() as f32
+        },
+        best_score: valid.iter().map(|p| p.score).max(),
+        distribution: distribution(&percentages),
+        most_common_play,
+    })
+}
+
+/// Estadísticas de todas las rondas reveladas, en orden, para el histórico
+pub fn history(tournament: &Tournament) -> Vec<RoundStats> {
+    let mut numbers: Vec<u32> = tournament.rounds.iter().map(|r| r.number).collect();
+    numbers.sort_unstable();
+    numbers.into_iter().filter_map(|n| compute(tournament, n)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use crate::models::{MasterPlay, Player, Round, RoundStatus, TournamentStatus};
+    use crate::models::test_support::active_round;
+
+    fn play(word: &str, col: u8, score: i32, master: i32) -> PlayerPlay {
+        PlayerPlay {
+            round_number: 1,
+            word: word.to_string(),
+            position: Position { row: 7, col, down: false },
+            score,
+            percentage_of_optimal: score as f32 / master as f32 * 100.0,
+            submitted_at: Utc::now(),
+            cumulative_score: score,
+            difference_from_optimal: master - score,
+            cumulative_difference: master - score,
+            rank: None,
+        }
+    }
+
+    fn player(name: &str, plays: Vec<PlayerPlay>) -> Player {
+        Player {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            total_score: plays.iter().map(|p| p.score).sum(),
+            plays,
+            category: None,
+            club: None,
+            federation_id: None,
+        }
+    }
+
+    #[test]
+    fn test_round_stats_counts_and_distribution() {
+        let mut tournament = Tournament {
+            id: Uuid::new_v4(),
+            name: "Estadísticas".to_string(),
+            created_at: Utc::now(),
+            status: TournamentStatus::InProgress,
+            rounds: vec![Round { status: RoundStatus::Completed, ..active_round(1, "CASADOS") }],
+            players: vec![
+                player("Ana", vec![play("CASADOS", 7, 80, 80)]),
+                player("Luis", vec![play("CASA", 7, 20, 80)]),
+                player("Marta", vec![play("CASA", 7, 20, 80)]),
+                player("Pedro", vec![play("COSAS", 5, 30, 80)]),
+                player("Eva", vec![play("SACOD (INVÁLIDA)", 7, 0, 80)]),
+                player("Juan", vec![play("DOS (TIEMPO EXCEDIDO)", 7, 0, 80)]),
+                player("Sara", Vec::new()),
+            ],
+            tiles_remaining: 86,
+            master_plays: vec![MasterPlay {
+                round_number: 1,
+                word: "CASADOS".to_string(),
+                position: Position { row: 7, col: 7, down: false },
+                score: 80,
+                cumulative_score: 80,
+            }],
+            training: false,
+        };
+        assert!(compute(&tournament, 1).is_none());
+
+        tournament.rounds[0].optimal_revealed = true;
+        let stats = compute(&tournament, 1).unwrap();
+        assert_eq!((stats.submitted, stats.missing, stats.invalid, stats.late), (6, 1, 1, 1));
+        assert_eq!(stats.found_master, 1);
+        assert_eq!(stats.best_score, Some(80));
+        assert!((stats.average_score - 37.5).abs() < 0.01);
+        // (100 + 25 + 25 + 37.5) / 7 jugadores
+        assert!((stats.average_percentage - 26.79).abs() < 0.01);
+
+        let common = stats.most_common_play.unwrap();
+        assert_eq!((common.word.as_str(), common.coordinate.as_str(), common.players), ("CASA", "H8", 2));
+
+        let counts: Vec<(u32, usize)> = stats.distribution.iter()
+            .filter(|b| b.players > 0)
+            .map(|b| (b.min_percentage, b.players))
+            .collect();
+        assert_eq!(counts, vec![(100, 1), (30, 1), (20, 2)]);
+        assert_eq!(history(&tournament).len(), 1);
+    }
+}
@@ -1,4 +1,4 @@
-use actix_web::{get, post, put, web, HttpResponse, HttpRequest};
+use actix_web::{delete, get, post, put, web, HttpResponse, HttpRequest};
 use actix_ws;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -8,8 +8,51 @@ use chrono::Utc;
 use crate::models::*;
 use crate::tournament_manager::TournamentManager;
 use crate::database::Database;
+use crate::sqlite_store::SqliteStore;
+use crate::arbiter::ArbiterAction;
 
 type TournamentManagerData = web::Data<Arc<RwLock<TournamentManager>>>;
+type PersistenceConfigData = web::Data<Arc<crate::persistence_mode::PersistenceConfig>>;
+type SqliteStoreData = Option<web::Data<Arc<SqliteStore>>>;
+
+fn player_token(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get(crate::auth::PLAYER_TOKEN_HEADER)
+        .and_then(|h| h.to_str().ok())
+}
+
+fn arbiter_token(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get(crate::arbiter::ARBITER_TOKEN_HEADER)
+        .and_then(|h| h.to_str().ok())
+}
+
+/// Devuelve la respuesta de rechazo si la sesión de árbitro no permite la acción;
+/// las denegaciones se registran
+fn arbiter_denial(
+    manager: &TournamentManager,
+    req: &HttpRequest,
+    tournament_id: Option<&Uuid>,
+    action: ArbiterAction,
+) -> Option<HttpResponse> {
+    manager.authorize_arbiter(tournament_id, arbiter_token(req), action).err().map(|e| {
+        let peer = req.peer_addr()
+            .map(|addr| addr.ip().to_string())
+            .unwrap_or_else(|| "Unknown".to_string());
+        log::warn!("Acción de árbitro denegada ({:?}) desde {}: {}", action, peer, e);
+        
+        if let Some(id) = tournament_id {
+            if let Err(log_err) = crate::persistence::PersistenceManager::log_event(
+                &id.to_string(),
+                &format!("Acción denegada {:?} desde {}: {}", action, peer, e)
+            ) {
+                eprintln!("Error logging denied action: {}", log_err);
+            }
+        }
+        
+        HttpResponse::Forbidden().json(ApiResponse::<()>::error(e))
+    })
+}
 
 #[get("/health")]
 pub async fn health_check() -> HttpResponse {
@@ -147,9 +190,14 @@ pub async fn test_validate_word(
 pub async fn load_dictionary(
     manager: TournamentManagerData,
     req: web::Json<LoadDictionaryRequest>,
+    http_req: HttpRequest,
 ) -> HttpResponse {
     let mut manager = manager.write().await;
-    
+
+    if let Some(resp) = arbiter_denial(&manager, &http_req, None, ArbiterAction::ConfigureServer) {
+        return resp;
+    }
+        
     eprintln!("Loading dictionary from: {}", req.kwg_path);
     
     match manager.load_dictionary(&req.kwg_path, req.klv_path.as_deref()) {
@@ -173,26 +221,15 @@ pub async fn create_tournament(
     
     eprintln!("Creating tournament: {} with players: {:?}", req.name, req.player_names);
     
-    match manager.create_tournament(req.name.clone(), req.player_names.clone()) {
-        Ok(tournament) => {
+    match manager.create_tournament(req.name.clone(), req.player_names.clone(), req.arbiter_passphrase.clone()) {
+        Ok((tournament, player_tokens)) => {
             eprintln!("Tournament created successfully with ID: {}", tournament.id);
             
-            // También guardar en Supabase
-            if let Ok(db) = Database::new().await {
-                if let Err(e) = db.create_tournament_with_id(
-                    tournament.id,
-                    tournament.name.clone(), 
-                    tournament.players.iter().map(|p| p.name.clone()).collect(),
-                    tournament.created_at
-                ).await {
-                    eprintln!("Warning: Failed to save tournament to Supabase: {}", e);
-                }
-            }
-            
             let player_url = manager.get_tournament_url(&tournament.id);
             let response = CreateTournamentResponse {
                 tournament: tournament.clone(),
                 player_url: player_url.clone(),
+                player_tokens,
             };
             eprintln!("Creating response with player_url: {}", player_url);
             eprintln!("Response structure: tournament.id={}, player_url={}", response.tournament.id, response.player_url);
@@ -205,6 +242,27 @@ pub async fn create_tournament(
     }
 }
 
+#[post("/tournament/import_record")]
+pub async fn import_record(
+    manager: TournamentManagerData,
+    req: web::Json<ImportRecordRequest>,
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    let req = req.into_inner();
+    
+    match manager.create_tournament_from_record(&req.record, req.name, req.player_names, req.arbiter_passphrase) {
+        Ok((tournament, player_tokens)) => {
+            let player_url = manager.get_tournament_url(&tournament.id);
+            HttpResponse::Ok().json(ApiResponse::success(CreateTournamentResponse {
+                tournament,
+                player_url,
+                player_tokens,
+            }))
+        },
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<CreateTournamentResponse>::error(e)),
+    }
+}
+
 #[get("/tournament/{id}")]
 pub async fn get_tournament(
     manager: TournamentManagerData,
@@ -242,11 +300,16 @@ pub async fn get_tournament(
 pub async fn start_round(
     manager: TournamentManagerData,
     path: web::Path<Uuid>,
+    req: HttpRequest,
 ) -> HttpResponse {
     eprintln!("=== START_ROUND ENDPOINT CALLED ===");
     let mut manager = manager.write().await;
     let tournament_id = path.into_inner();
-    
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::StartRound) {
+        return resp;
+    }
+        
     eprintln!("start_round called for tournament {}", tournament_id);
     eprintln!("Engine loaded: {}", manager.engine.is_some());
     eprintln!("Tournament exists: {}", manager.get_tournament(&tournament_id).is_some());
@@ -259,19 +322,6 @@ pub async fn start_round(
     
     match manager.start_new_round(&tournament_id) {
         Ok(round) => {
-            // También guardar en Supabase
-            if let Ok(db) = Database::new().await {
-                let board_json = serde_json::to_string(&round.board_state).unwrap_or_else(|_| "{}".to_string());
-                if let Err(e) = db.create_round(
-                    tournament_id,
-                    round.number as i32,
-                    round.rack.clone(),
-                    board_json
-                ).await {
-                    eprintln!("Warning: Failed to save round to Supabase: {}", e);
-                }
-            }
-            
             HttpResponse::Ok().json(ApiResponse::success(round))
         },
         Err(e) => {
@@ -286,40 +336,76 @@ pub async fn start_manual_round(
     manager: TournamentManagerData,
     path: web::Path<Uuid>,
     req: web::Json<StartManualRoundRequest>,
+    http_req: HttpRequest,
 ) -> HttpResponse {
     let mut manager = manager.write().await;
     let tournament_id = path.into_inner();
-    
+
+    if let Some(resp) = arbiter_denial(&manager, &http_req, Some(&tournament_id), ArbiterAction::StartRound) {
+        return resp;
+    }
+        
     match manager.start_new_round_manual(&tournament_id, &req.rack) {
         Ok(round) => {
-            // También guardar en Supabase
-            if let Ok(db) = Database::new().await {
-                let board_json = serde_json::to_string(&round.board_state).unwrap_or_else(|_| "{}".to_string());
-                if let Err(e) = db.create_round(
-                    tournament_id,
-                    round.number as i32,
-                    round.rack.clone(),
-                    board_json
-                ).await {
-                    eprintln!("Warning: Failed to save manual round to Supabase: {}", e);
-                }
-            }
-            
             HttpResponse::Ok().json(ApiResponse::success(round))
         },
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<Round>::error(e)),
     }
 }
 
+#[post("/tournament/{id}/round/start_template")]
+pub async fn start_template_round(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    let tournament_id = path.into_inner();
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::StartRound) {
+        return resp;
+    }
+    
+    match manager.start_template_round(&tournament_id) {
+        Ok(round) => HttpResponse::Ok().json(ApiResponse::success(round)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<Round>::error(e)),
+    }
+}
+
+#[get("/tournament/{id}/template")]
+pub async fn get_template(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let manager = manager.read().await;
+    let tournament_id = path.into_inner();
+
+    // La plantilla adelanta atriles y jugadas master
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ViewMaster) {
+        return resp;
+    }
+    
+    match manager.template_for(&tournament_id) {
+        Some(template) => HttpResponse::Ok().json(ApiResponse::success(template)),
+        None => HttpResponse::NotFound().json(ApiResponse::<()>::error("El torneo no se creó desde un registro".to_string())),
+    }
+}
+
 #[put("/tournament/{id}/round/{round}/update_rack")]
 pub async fn update_current_round_rack(
     manager: TournamentManagerData,
     path: web::Path<(Uuid, u32)>,
     req: web::Json<StartManualRoundRequest>,
+    http_req: HttpRequest,
 ) -> HttpResponse {
     let mut manager = manager.write().await;
     let (tournament_id, round_number) = path.into_inner();
-    
+
+    if let Some(resp) = arbiter_denial(&manager, &http_req, Some(&tournament_id), ArbiterAction::UpdateRack) {
+        return resp;
+    }
+        
     match manager.update_round_rack(&tournament_id, round_number, &req.rack) {
         Ok(round) => HttpResponse::Ok().json(ApiResponse::success(round)),
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<Round>::error(e)),
@@ -329,12 +415,38 @@ pub async fn update_current_round_rack(
 #[post("/tournament/play/submit")]
 pub async fn submit_play(
     manager: TournamentManagerData,
-    async_queue: web::Data<Arc<crate::async_queue::AsyncQueue>>,
-    local_cache: web::Data<Arc<crate::local_cache::LocalCache>>,
     req: web::Json<SubmitPlayRequest>,
+    http_req: HttpRequest,
 ) -> HttpResponse {
     let mut manager = manager.write().await;
     
+    // El jugador se autentica con su token; un árbitro puede capturar la planilla en su nombre
+    let slip_entry = matches!(
+        manager.authorize_arbiter(Some(&req.tournament_id), arbiter_token(&http_req), ArbiterAction::EnterSlip),
+        Ok(Some(_))
+    );
+    let mut device = None;
+    if !slip_entry {
+        if let Err(e) = manager.verify_player_token(&req.tournament_id, &req.player_id, player_token(&http_req)) {
+            return HttpResponse::Unauthorized().json(ApiResponse::<PlaySubmissionResponse>::error(e));
+        }
+        
+        // Vincular el dispositivo a la jugada (las planillas del árbitro no cuentan)
+        let ip_address = http_req.peer_addr()
+            .map(|addr| addr.ip().to_string())
+            .unwrap_or_else(|| "Unknown".to_string());
+        let user_agent = http_req.headers()
+            .get("User-Agent")
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or("Unknown");
+        device = crate::device_guard::device_key(req.hardware_id.as_deref(), &ip_address, user_agent);
+        if let Some(ref device) = device {
+            if let Err(e) = manager.check_submission_device(&req.tournament_id, &req.player_id, req.round_number, device) {
+                return HttpResponse::Forbidden().json(ApiResponse::<PlaySubmissionResponse>::error(e));
+            }
+        }
+    }
+    
     match manager.submit_player_play(
         &req.tournament_id,
         &req.player_id,
@@ -343,44 +455,12 @@ pub async fn submit_play(
         req.position.clone(),
     ) {
         Ok(response) => {
-            // Guardar en cache local primero (respuesta inmediata)
-            if let Some(tournament) = manager.tournaments.get(&req.tournament_id) {
-                if let Some(player) = tournament.players.iter().find(|p| p.id == req.player_id) {
-                    if let Some(play) = player.plays.iter().find(|p| p.round_number == req.round_number) {
-                        let play_data = crate::async_queue::PlayData {
-                            tournament_id: req.tournament_id,
-                            player_id: req.player_id,
-                            round_number: req.round_number as i32,
-                            word: play.word.clone(),
-                            position_row: req.position.row as i32,
-                            position_col: req.position.col as i32,
-                            position_down: req.position.down,
-                            score: play.score,
-                            percentage_of_optimal: play.percentage_of_optimal,
-                            cumulative_score: play.cumulative_score,
-                            difference_from_optimal: play.difference_from_optimal,
-                            cumulative_difference: play.cumulative_difference,
-                        };
-                        
-                        // Guardar en cache local
-                        if let Err(e) = local_cache.store_play(play_data.clone()).await {
-                            log::warn!("Failed to cache play locally: {}", e);
-                        }
-                        
-                        // Enviar a cola asíncrona para Supabase (no bloquea)
-                        if let Err(e) = async_queue.submit_play(play_data).await {
-                            log::error!("Failed to queue play for Supabase: {}", e);
-                            // El juego continúa aunque falle el envío a Supabase
-                        } else {
-                            log::info!("Play queued for async Supabase sync: player {} round {}", 
-                                     req.player_id, req.round_number);
-                        }
-                    }
-                }
+            // El dispositivo solo se registra cuando la jugada fue aceptada
+            if let Some(device) = device {
+                manager.record_submission_device(&req.tournament_id, &req.player_id, req.round_number, &device);
             }
-            
             HttpResponse::Ok().json(ApiResponse::success(response))
-        },
+        }
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<PlaySubmissionResponse>::error(e)),
     }
 }
@@ -389,10 +469,15 @@ pub async fn submit_play(
 pub async fn get_optimal_play(
     manager: TournamentManagerData,
     path: web::Path<(Uuid, u32)>,
+    req: HttpRequest,
 ) -> HttpResponse {
     let mut manager = manager.write().await;
     let (tournament_id, round_number) = path.into_inner();
-    
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ViewMaster) {
+        return resp;
+    }
+        
     match manager.calculate_optimal_play(&tournament_id, round_number) {
         Ok(optimal) => HttpResponse::Ok().json(ApiResponse::success(optimal)),
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<OptimalPlay>::error(e)),
@@ -416,179 +501,679 @@ pub async fn get_leaderboard(
 pub async fn get_player_log(
     manager: TournamentManagerData,
     path: web::Path<(Uuid, Uuid)>,
+    req: HttpRequest,
 ) -> HttpResponse {
     let manager = manager.read().await;
     let (tournament_id, player_id) = path.into_inner();
     
+    let arbiter_view = matches!(
+        manager.authorize_arbiter(Some(&tournament_id), arbiter_token(&req), ArbiterAction::ViewPlayerLogs),
+        Ok(Some(_))
+    );
+    if !arbiter_view {
+        if let Err(e) = manager.verify_player_token(&tournament_id, &player_id, player_token(&req)) {
+            return HttpResponse::Unauthorized().json(ApiResponse::<PlayerLog>::error(e));
+        }
+    }
+    
     match manager.get_player_log(&tournament_id, &player_id) {
         Ok(log) => HttpResponse::Ok().json(ApiResponse::success(log)),
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<PlayerLog>::error(e)),
     }
 }
 
-#[put("/tournament/{id}/round/{round}/reject_rack")]
-pub async fn reject_rack(
+// Análisis para entrenamiento: mismo acceso que el log del jugador
+#[get("/tournament/{id}/player/{player_id}/analytics")]
+pub async fn get_player_analytics(
     manager: TournamentManagerData,
-    path: web::Path<(Uuid, u32)>,
+    path: web::Path<(Uuid, Uuid)>,
+    req: HttpRequest,
 ) -> HttpResponse {
-    let mut manager = manager.write().await;
-    let (tournament_id, round_number) = path.into_inner();
+    let manager = manager.read().await;
+    let (tournament_id, player_id) = path.into_inner();
     
-    match manager.reject_rack_and_regenerate(&tournament_id, round_number) {
-        Ok(round) => HttpResponse::Ok().json(ApiResponse::success(round)),
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<Round>::error(e)),
+    let arbiter_view = matches!(
+        manager.authorize_arbiter(Some(&tournament_id), arbiter_token(&req), ArbiterAction::ViewPlayerLogs),
+        Ok(Some(_))
+    );
+    if !arbiter_view {
+        if let Err(e) = manager.verify_player_token(&tournament_id, &player_id, player_token(&req)) {
+            return HttpResponse::Unauthorized().json(ApiResponse::<()>::error(e));
+        }
     }
-}
-
-#[put("/tournament/{id}/round/{round}/start_timer")]
-pub async fn start_round_timer(
-    manager: TournamentManagerData,
-    path: web::Path<(Uuid, u32)>,
-) -> HttpResponse {
-    let mut manager = manager.write().await;
-    let (tournament_id, round_number) = path.into_inner();
     
-    match manager.start_round_timer(&tournament_id, round_number) {
-        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Timer started")),
+    match manager.player_analytics(&tournament_id, &player_id) {
+        Ok(analytics) => HttpResponse::Ok().json(ApiResponse::success(analytics)),
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     }
 }
 
-#[put("/tournament/{id}/round/{round}/reveal_optimal")]
-pub async fn reveal_optimal_play(
-    manager: TournamentManagerData,
-    path: web::Path<(Uuid, u32)>,
-) -> HttpResponse {
-    let mut manager = manager.write().await;
-    let (tournament_id, round_number) = path.into_inner();
-    
-    match manager.reveal_optimal_play(&tournament_id, round_number) {
-        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Optimal play revealed")),
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+fn gcg_attachment(filename: String, gcg: String) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/x-gcg; charset=utf-8")
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
+        .body(gcg)
+}
+
+/// Las exportaciones con las jugadas master son públicas solo con el torneo
+/// terminado; antes exigen el mismo permiso que la plantilla
+fn master_export_denial(manager: &TournamentManager, req: &HttpRequest, tournament_id: &Uuid) -> Option<HttpResponse> {
+    let finished = manager.get_tournament(tournament_id)
+        .is_some_and(|t| t.status == TournamentStatus::Finished);
+    if finished {
+        return None;
     }
+    arbiter_denial(manager, req, Some(tournament_id), ArbiterAction::ViewMaster)
 }
 
-#[get("/tournament/{id}/round/{round}/player/{player_id}/feedback")]
-pub async fn get_round_feedback(
+#[get("/tournament/{id}/export/gcg")]
+pub async fn export_master_gcg(
     manager: TournamentManagerData,
-    path: web::Path<(Uuid, u32, Uuid)>,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
 ) -> HttpResponse {
     let manager = manager.read().await;
-    let (tournament_id, round_number, player_id) = path.into_inner();
-    
-    match manager.get_round_feedback(&tournament_id, round_number, &player_id) {
-        Ok(feedback) => HttpResponse::Ok().json(ApiResponse::success(feedback)),
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<RoundFeedback>::error(e)),
+    let tournament_id = path.into_inner();
+
+    if let Some(resp) = master_export_denial(&manager, &req, &tournament_id) {
+        return resp;
+    }
+
+    match manager.get_tournament(&tournament_id) {
+        Some(tournament) => gcg_attachment(
+            format!("{}_master.gcg", tournament_id),
+            crate::gcg::master_game(tournament),
+        ),
+        None => HttpResponse::NotFound().json(ApiResponse::<()>::error("Tournament not found".to_string())),
     }
 }
 
-#[put("/tournament/{id}/round/{round}/place_optimal")]
-pub async fn place_optimal_play(
+#[get("/tournament/{id}/player/{player_id}/export/gcg")]
+pub async fn export_player_gcg(
     manager: TournamentManagerData,
-    path: web::Path<(Uuid, u32)>,
+    path: web::Path<(Uuid, Uuid)>,
+    req: HttpRequest,
 ) -> HttpResponse {
-    let mut manager = manager.write().await;
-    let (tournament_id, round_number) = path.into_inner();
-    
-    match manager.place_optimal_play(&tournament_id, round_number) {
-        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Optimal play placed")),
+    let manager = manager.read().await;
+    let (tournament_id, player_id) = path.into_inner();
+
+    let arbiter_view = matches!(
+        manager.authorize_arbiter(Some(&tournament_id), arbiter_token(&req), ArbiterAction::ViewPlayerLogs),
+        Ok(Some(_))
+    );
+    if !arbiter_view {
+        if let Err(e) = manager.verify_player_token(&tournament_id, &player_id, player_token(&req)) {
+            return HttpResponse::Unauthorized().json(ApiResponse::<()>::error(e));
+        }
+    }
+
+    let Some(tournament) = manager.get_tournament(&tournament_id) else {
+        return HttpResponse::NotFound().json(ApiResponse::<()>::error("Tournament not found".to_string()));
+    };
+    match crate::gcg::player_game(tournament, &player_id) {
+        Ok(gcg) => gcg_attachment(format!("{}_{}.gcg", tournament_id, player_id), gcg),
         Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
     }
 }
 
-#[put("/tournament/{id}/finish")]
-pub async fn finish_tournament_manually(
+#[get("/tournament/{id}/export/record")]
+pub async fn export_record(
     manager: TournamentManagerData,
     path: web::Path<Uuid>,
+    req: HttpRequest,
 ) -> HttpResponse {
-    let mut manager = manager.write().await;
+    let manager = manager.read().await;
     let tournament_id = path.into_inner();
-    
-    match manager.finish_tournament_manually(&tournament_id) {
-        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Tournament finished manually")),
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+
+    // El registro incluye los atriles y masters de todas las rondas
+    if let Some(resp) = master_export_denial(&manager, &req, &tournament_id) {
+        return resp;
+    }
+
+    match manager.get_tournament(&tournament_id) {
+        Some(tournament) => HttpResponse::Ok()
+            .content_type("text/plain; charset=utf-8")
+            .insert_header(("Content-Disposition", format!("attachment; filename=\"{}_registro.txt\"", tournament_id)))
+            .body(crate::record::export(tournament)),
+        None => HttpResponse::NotFound().json(ApiResponse::<()>::error("Tournament not found".to_string())),
     }
 }
 
-#[get("/tournament/{id}/bag_tiles")]
-pub async fn get_bag_tiles(
+fn standings_for(manager: &TournamentManager, tournament_id: &Uuid) -> Option<crate::standings::Standings> {
+    manager.get_tournament(tournament_id).map(crate::standings::compute)
+}
+
+fn tournament_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(ApiResponse::<()>::error("Tournament not found".to_string()))
+}
+
+fn csv_attachment(filename: String, csv: String) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
+        // BOM para que las hojas de cálculo detecten UTF-8
+        .body(format!("\u{feff}{}", csv))
+}
+
+#[get("/tournament/{id}/export/standings.csv")]
+pub async fn export_standings_csv(
     manager: TournamentManagerData,
     path: web::Path<Uuid>,
 ) -> HttpResponse {
-    let manager = manager.read().await;
-    
-    match manager.get_bag_tiles(&path.into_inner()) {
-        Ok(tiles) => HttpResponse::Ok().json(ApiResponse::success(tiles)),
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<Vec<(String, bool)>>::error(e)),
+    let tournament_id = path.into_inner();
+    match standings_for(&*manager.read().await, &tournament_id) {
+        Some(standings) => csv_attachment(
+            format!("{}_clasificacion.csv", tournament_id),
+            crate::standings::standings_csv(&standings),
+        ),
+        None => tournament_not_found(),
     }
 }
 
-#[get("/tournament/{id}/check_end")]
-pub async fn check_game_end(
+#[get("/tournament/{id}/export/rounds.csv")]
+pub async fn export_rounds_csv(
     manager: TournamentManagerData,
     path: web::Path<Uuid>,
 ) -> HttpResponse {
-    let manager = manager.read().await;
-    
-    match manager.check_game_end_condition(&path.into_inner()) {
-        Ok((is_ended, reason)) => HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
-            "game_ended": is_ended,
-            "reason": reason
-        }))),
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    let tournament_id = path.into_inner();
+    match standings_for(&*manager.read().await, &tournament_id) {
+        Some(standings) => csv_attachment(
+            format!("{}_rondas.csv", tournament_id),
+            crate::standings::rounds_csv(&standings),
+        ),
+        None => tournament_not_found(),
     }
 }
 
-#[put("/tournament/{id}/undo")]
-pub async fn undo_last_round(
+#[get("/tournament/{id}/export/report.html")]
+pub async fn export_standings_report(
     manager: TournamentManagerData,
     path: web::Path<Uuid>,
 ) -> HttpResponse {
-    let mut manager = manager.write().await;
     let tournament_id = path.into_inner();
-    
-    match manager.undo_last_round(&tournament_id) {
-        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Last round undone")),
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    match standings_for(&*manager.read().await, &tournament_id) {
+        Some(standings) => {
+            let generated_at = chrono::Local::now().format("%d/%m/%Y %H:%M").to_string();
+            HttpResponse::Ok()
+                .content_type("text/html; charset=utf-8")
+                .body(crate::standings::report_html(&standings, &generated_at))
+        }
+        None => tournament_not_found(),
     }
 }
 
-#[get("/ws/tournament/{id}")]
-pub async fn ws_tournament_updates(
+#[put("/tournament/{id}/round/{round}/reject_rack")]
+pub async fn reject_rack(
+    manager: TournamentManagerData,
+    path: web::Path<(Uuid, u32)>,
     req: HttpRequest,
-    stream: web::Payload,
-    path: web::Path<Uuid>,
-) -> Result<HttpResponse, actix_web::Error> {
-    let (res, session, _msg_stream) = actix_ws::handle(&req, stream)?;
-    
-    // TODO: Implement WebSocket updates for real-time tournament status
-    
-    Ok(res)
-}
-
-// Nuevos endpoints para persistencia
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    let (tournament_id, round_number) = path.into_inner();
 
-#[get("/tournaments")]
-pub async fn list_tournaments() -> HttpResponse {
-    use crate::persistence::PersistenceManager;
-    
-    match PersistenceManager::list_tournaments() {
-        Ok(tournaments) => HttpResponse::Ok().json(ApiResponse::success(tournaments)),
-        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!("Error listing tournaments: {}", e))),
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::RejectRack) {
+        return resp;
+    }
+        
+    match manager.reject_rack_and_regenerate(&tournament_id, round_number) {
+        Ok(round) => HttpResponse::Ok().json(ApiResponse::success(round)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<Round>::error(e)),
     }
 }
 
-#[post("/tournament/{id}/load")]
-pub async fn load_tournament(
+#[put("/tournament/{id}/round/{round}/start_timer")]
+pub async fn start_round_timer(
     manager: TournamentManagerData,
-    path: web::Path<String>,
+    path: web::Path<(Uuid, u32)>,
+    req: HttpRequest,
 ) -> HttpResponse {
-    use crate::persistence::PersistenceManager;
-    
-    let tournament_id = path.into_inner();
+    let mut manager = manager.write().await;
+    let (tournament_id, round_number) = path.into_inner();
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::StartTimer) {
+        return resp;
+    }
+        
+    match manager.start_round_timer(&tournament_id, round_number) {
+        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Timer started")),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+#[get("/tournament/{id}/round/{round}/progress")]
+pub async fn get_round_progress(
+    manager: TournamentManagerData,
+    path: web::Path<(Uuid, u32)>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let manager = manager.read().await;
+    let (tournament_id, round_number) = path.into_inner();
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ViewRoundProgress) {
+        return resp;
+    }
+
+    match manager.round_progress(&tournament_id, round_number) {
+        Ok(progress) => HttpResponse::Ok().json(ApiResponse::success(progress)),
+        Err(e) => HttpResponse::NotFound().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// Estadísticas de la ronda; solo existen una vez revelado el master
+#[get("/tournament/{id}/round/{round}/stats")]
+pub async fn get_round_stats(
+    manager: TournamentManagerData,
+    path: web::Path<(Uuid, u32)>,
+) -> HttpResponse {
+    let manager = manager.read().await;
+    let (tournament_id, round_number) = path.into_inner();
+
+    match manager.round_stats(&tournament_id, round_number) {
+        Ok(stats) => HttpResponse::Ok().json(ApiResponse::success(stats)),
+        Err(e) => HttpResponse::NotFound().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// Análisis por equity (torneos de entrenamiento). Sin jugador es público una
+// vez revelado el master; con jugador, mismo acceso que su log.
+#[get("/tournament/{id}/round/{round}/coaching")]
+pub async fn get_round_coaching(
+    manager: TournamentManagerData,
+    path: web::Path<(Uuid, u32)>,
+    query: web::Query<CoachingQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let (tournament_id, round_number) = path.into_inner();
+    
+    // Se prepara con el lock de lectura; la generación de jugadas corre aparte
+    let job = {
+        let manager = manager.read().await;
+        match &query.player_id {
+            Some(player_id) => {
+                let arbiter_view = matches!(
+                    manager.authorize_arbiter(Some(&tournament_id), arbiter_token(&req), ArbiterAction::ViewPlayerLogs),
+                    Ok(Some(_))
+                );
+                if !arbiter_view {
+                    if let Err(e) = manager.verify_player_token(&tournament_id, player_id, player_token(&req)) {
+                        return HttpResponse::Unauthorized().json(ApiResponse::<()>::error(e));
+                    }
+                }
+            }
+            None => {
+                if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ViewPlayerLogs) {
+                    return resp;
+                }
+            }
+        }
+        match manager.coaching_job(&tournament_id, round_number, query.player_id.as_ref()) {
+            Ok(job) => job,
+            Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+        }
+    };
+    
+    match web::block(move || job.run()).await {
+        Ok(Ok(coaching)) => HttpResponse::Ok().json(ApiResponse::success(coaching)),
+        Ok(Err(e)) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(e.to_string())),
+    }
+}
+
+#[get("/tournament/{id}/stats")]
+pub async fn get_round_stats_history(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let manager = manager.read().await;
+
+    match manager.round_stats_history(&path.into_inner()) {
+        Ok(history) => HttpResponse::Ok().json(ApiResponse::success(history)),
+        Err(e) => HttpResponse::NotFound().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+#[post("/tournament/{id}/round/{round}/nudge")]
+pub async fn nudge_pending_players(
+    manager: TournamentManagerData,
+    path: web::Path<(Uuid, u32)>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let manager = manager.read().await;
+    let (tournament_id, round_number) = path.into_inner();
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::NudgePlayers) {
+        return resp;
+    }
+
+    match manager.nudge_pending_players(&tournament_id, round_number) {
+        Ok(player_ids) => HttpResponse::Ok().json(ApiResponse::success(player_ids)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+#[put("/tournament/{id}/round/{round}/reveal_optimal")]
+pub async fn reveal_optimal_play(
+    manager: TournamentManagerData,
+    path: web::Path<(Uuid, u32)>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    let (tournament_id, round_number) = path.into_inner();
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::RevealMaster) {
+        return resp;
+    }
+        
+    match manager.reveal_optimal_play(&tournament_id, round_number) {
+        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Optimal play revealed")),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+#[get("/tournament/{id}/round/{round}/player/{player_id}/feedback")]
+pub async fn get_round_feedback(
+    manager: TournamentManagerData,
+    path: web::Path<(Uuid, u32, Uuid)>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let manager = manager.read().await;
+    let (tournament_id, round_number, player_id) = path.into_inner();
+    
+    let arbiter_view = matches!(
+        manager.authorize_arbiter(Some(&tournament_id), arbiter_token(&req), ArbiterAction::ViewPlayerLogs),
+        Ok(Some(_))
+    );
+    if !arbiter_view {
+        if let Err(e) = manager.verify_player_token(&tournament_id, &player_id, player_token(&req)) {
+            return HttpResponse::Unauthorized().json(ApiResponse::<RoundFeedback>::error(e));
+        }
+    }
+    
+    match manager.get_round_feedback(&tournament_id, round_number, &player_id) {
+        Ok(feedback) => HttpResponse::Ok().json(ApiResponse::success(feedback)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<RoundFeedback>::error(e)),
+    }
+}
+
+#[put("/tournament/{id}/round/{round}/place_optimal")]
+pub async fn place_optimal_play(
+    manager: TournamentManagerData,
+    path: web::Path<(Uuid, u32)>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    let (tournament_id, round_number) = path.into_inner();
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::PlaceMaster) {
+        return resp;
+    }
+        
+    match manager.place_optimal_play(&tournament_id, round_number) {
+        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Optimal play placed")),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+#[put("/tournament/{id}/finish")]
+pub async fn finish_tournament_manually(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    let tournament_id = path.into_inner();
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::Finish) {
+        return resp;
+    }
+        
+    match manager.finish_tournament_manually(&tournament_id) {
+        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Tournament finished manually")),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+#[get("/tournament/{id}/bag_tiles")]
+pub async fn get_bag_tiles(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let manager = manager.read().await;
+    
+    match manager.get_bag_tiles(&path.into_inner()) {
+        Ok(tiles) => HttpResponse::Ok().json(ApiResponse::success(tiles)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<Vec<(String, bool)>>::error(e)),
+    }
+}
+
+#[get("/tournament/{id}/check_end")]
+pub async fn check_game_end(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let manager = manager.read().await;
+    
+    match manager.check_game_end_condition(&path.into_inner()) {
+        Ok((is_ended, reason)) => HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+            "game_ended": is_ended,
+            "reason": reason
+        }))),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+#[put("/tournament/{id}/undo")]
+pub async fn undo_last_round(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    let tournament_id = path.into_inner();
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::Undo) {
+        return resp;
+    }
+        
+    match manager.undo_last_round(&tournament_id) {
+        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Last round undone")),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+#[put("/tournament/{id}/player/{player_id}/round/{round}/score")]
+pub async fn adjust_player_score(
+    manager: TournamentManagerData,
+    path: web::Path<(Uuid, Uuid, u32)>,
+    body: web::Json<AdjustScoreRequest>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    let (tournament_id, player_id, round_number) = path.into_inner();
+
+    let session = match manager.authorize_arbiter(Some(&tournament_id), arbiter_token(&req), ArbiterAction::AdjustScore) {
+        Ok(session) => session,
+        Err(e) => return HttpResponse::Forbidden().json(ApiResponse::<()>::error(e)),
+    };
+
+    let body = body.into_inner();
+    let reason = match session {
+        Some(session) => format!("{} ({})", body.reason, session.name),
+        None => body.reason,
+    };
+
+    match manager.adjust_player_score(&tournament_id, &player_id, round_number, body.score, reason) {
+        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Score adjusted")),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+#[get("/tournament/{id}/journal")]
+pub async fn get_journal(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let manager = manager.read().await;
+    let tournament_id = path.into_inner();
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ViewJournal) {
+        return resp;
+    }
+
+    use crate::persistence::PersistenceManager;
+    match PersistenceManager::read_journal(&tournament_id.to_string()) {
+        Ok(entries) => HttpResponse::Ok().json(ApiResponse::success(entries)),
+        Err(e) => HttpResponse::NotFound().json(ApiResponse::<()>::error(e.to_string())),
+    }
+}
+
+#[get("/tournament/{id}/journal/replay")]
+pub async fn replay_journal(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+    query: web::Query<JournalReplayQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let manager = manager.read().await;
+    let tournament_id = path.into_inner();
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ViewJournal) {
+        return resp;
+    }
+
+    use crate::persistence::PersistenceManager;
+    let entries = match PersistenceManager::read_journal(&tournament_id.to_string()) {
+        Ok(entries) => entries,
+        Err(e) => return HttpResponse::NotFound().json(ApiResponse::<()>::error(e.to_string())),
+    };
+
+    match crate::journal::replay(&entries, query.up_to) {
+        Ok(tournament) => HttpResponse::Ok().json(ApiResponse::success(tournament)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+#[get("/ws/tournament/{id}")]
+pub async fn ws_tournament_updates(
+    manager: TournamentManagerData,
+    req: HttpRequest,
+    stream: web::Payload,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, actix_web::Error> {
+    use crate::events::TournamentEvent;
+    use tokio::sync::broadcast::error::RecvError;
+    
+    let tournament_id = path.into_inner();
+    
+    // Suscribirse antes de tomar el estado para no perder eventos intermedios
+    let (snapshot, mut events) = {
+        let manager = manager.read().await;
+        let events = manager.events.subscribe(&tournament_id);
+        match manager.state_snapshot(&tournament_id) {
+            Ok(snapshot) => (snapshot, events),
+            Err(e) => return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error(e))),
+        }
+    };
+    
+    let (res, mut session, mut msg_stream) = actix_ws::handle(&req, stream)?;
+    
+    actix_web::rt::spawn(async move {
+        let mut heartbeat = tokio::time::interval(std::time::Duration::from_secs(15));
+        
+        let mut pending = Some(TournamentEvent::State(snapshot));
+        loop {
+            if let Some(event) = pending.take() {
+                let json = match serde_json::to_string(&event) {
+                    Ok(json) => json,
+                    Err(e) => {
+                        log::error!("No se pudo serializar evento del torneo {}: {}", tournament_id, e);
+                        continue;
+                    }
+                };
+                if session.text(json).await.is_err() {
+                    break;
+                }
+            }
+            
+            tokio::select! {
+                _ = heartbeat.tick() => {
+                    pending = Some(TournamentEvent::Heartbeat { timestamp: Utc::now() });
+                }
+                received = events.recv() => match received {
+                    Ok(event) => pending = Some(event),
+                    Err(RecvError::Lagged(skipped)) => {
+                        // El cliente se quedó atrás: reenviar el estado completo
+                        log::warn!("Cliente WebSocket del torneo {} perdió {} eventos", tournament_id, skipped);
+                        let manager = manager.read().await;
+                        match manager.state_snapshot(&tournament_id) {
+                            Ok(snapshot) => pending = Some(TournamentEvent::State(snapshot)),
+                            Err(_) => break,
+                        }
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                msg = msg_stream.recv() => match msg {
+                    Some(Ok(actix_ws::Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(actix_ws::Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+        
+        let _ = session.close(None).await;
+    });
+    
+    Ok(res)
+}
+
+// Nuevos endpoints para persistencia
+
+#[get("/tournaments")]
+pub async fn list_tournaments() -> HttpResponse {
+    use crate::persistence::PersistenceManager;
+    
+    match PersistenceManager::list_tournaments() {
+        Ok(tournaments) => HttpResponse::Ok().json(ApiResponse::success(tournaments)),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!("Error listing tournaments: {}", e))),
+    }
+}
+
+#[get("/recovery_report")]
+pub async fn get_recovery_report(
+    manager: TournamentManagerData,
+    req: HttpRequest,
+) -> HttpResponse {
+    let manager = manager.read().await;
+
+    if let Some(resp) = arbiter_denial(&manager, &req, None, ArbiterAction::ViewJournal) {
+        return resp;
+    }
+
+    HttpResponse::Ok().json(ApiResponse::success(manager.recovery_report.clone()))
+}
+
+#[post("/tournament/{id}/load")]
+pub async fn load_tournament(
+    manager: TournamentManagerData,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> HttpResponse {
+    use crate::persistence::PersistenceManager;
+    
+    let tournament_id = path.into_inner();
+    
+    // Autorizar antes de leer y verificar el snapshot del disco
+    if let Some(resp) = arbiter_denial(&*manager.read().await, &req, None, ArbiterAction::ConfigureServer) {
+        return resp;
+    }
     
     match PersistenceManager::load_tournament(&tournament_id) {
-        Ok((tournament, _player_sessions)) => {
+        Ok(loaded) => {
             let mut manager = manager.write().await;
             
             // Cargar el diccionario si no está cargado
@@ -602,7 +1187,8 @@ pub async fn load_tournament(
             }
             
             // Restaurar el torneo en el manager
-            manager.restore_tournament(tournament.clone());
+            let tournament = loaded.tournament.clone();
+            manager.restore_tournament(loaded);
             
             HttpResponse::Ok().json(ApiResponse::success(tournament))
         }
@@ -649,36 +1235,26 @@ pub async fn enroll_player(
         hardware_id: body.hardware_id.clone(),
         enrolled_at: chrono::Utc::now(),
         last_seen: chrono::Utc::now(),
+        token_hash: None,
+        token_issued_at: None,
     };
+    let ip_address = player_session.ip_address.clone();
     
     // Agregar jugador al torneo
-    match manager.add_player(&body.tournament_id, &body.name, player_id) {
-        Ok(tournament) => {
+    match manager.add_player(&body.tournament_id, &body.name, player_id, player_session) {
+        Ok((tournament, player_token)) => {
             // Guardar sesión del jugador (JSON)
             if let Err(e) = PersistenceManager::log_player_action(
                 &body.tournament_id.to_string(),
                 &player_id.to_string(),
-                &format!("Player enrolled: {} from IP: {}", body.name, player_session.ip_address)
+                &format!("Player enrolled: {} from IP: {}", body.name, ip_address)
             ) {
                 eprintln!("Error logging player action: {}", e);
             }
             
-            // También guardar en Supabase
-            if let Ok(db) = Database::new().await {
-                if let Err(e) = db.enroll_player(
-                    body.tournament_id, 
-                    player_id, 
-                    body.name.clone(), 
-                    player_session.ip_address.clone(), 
-                    player_session.user_agent.clone(), 
-                    player_session.hardware_id.unwrap_or_else(|| "unknown".to_string())
-                ).await {
-                    eprintln!("Warning: Failed to save player to Supabase: {}", e);
-                }
-            }
-            
             HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
                 "player_id": player_id,
+                "player_token": player_token,
                 "tournament": tournament
             })))
         }
@@ -686,6 +1262,294 @@ pub async fn enroll_player(
     }
 }
 
+// Inscripción masiva desde CSV; devuelve el token de cada jugador para imprimirlo
+#[post("/tournament/{id}/players/import")]
+pub async fn import_players(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+    body: web::Json<ImportPlayersRequest>,
+    req: HttpRequest,
+) -> HttpResponse {
+    use crate::persistence::PersistenceManager;
+    
+    let mut manager = manager.write().await;
+    let tournament_id = path.into_inner();
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ManagePlayers) {
+        return resp;
+    }
+    
+    match manager.import_players(&tournament_id, &body.csv) {
+        Ok(players) => {
+            for player in &players {
+                if let Err(e) = PersistenceManager::log_player_action(
+                    &tournament_id.to_string(),
+                    &player.player_id.to_string(),
+                    &format!("Player imported from CSV: {}", player.name)
+                ) {
+                    eprintln!("Error logging player action: {}", e);
+                }
+            }
+            HttpResponse::Ok().json(ApiResponse::success(players))
+        }
+        // Los errores por fila van en `data` para que el árbitro corrija el fichero
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse {
+            success: false,
+            data: Some(e.rows),
+            error: Some(e.message),
+        }),
+    }
+}
+
+// Reemitir el token de un jugador (por ejemplo, si cambió de dispositivo)
+#[post("/tournament/{id}/player/{player_id}/token")]
+pub async fn reissue_player_token(
+    manager: TournamentManagerData,
+    path: web::Path<(Uuid, Uuid)>,
+    req: HttpRequest,
+) -> HttpResponse {
+    use crate::persistence::PersistenceManager;
+    
+    let mut manager = manager.write().await;
+    let (tournament_id, player_id) = path.into_inner();
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ManagePlayers) {
+        return resp;
+    }
+        
+    match manager.reissue_player_token(&tournament_id, &player_id) {
+        Ok(token) => {
+            if let Err(e) = PersistenceManager::log_player_action(
+                &tournament_id.to_string(),
+                &player_id.to_string(),
+                "Player token reissued by arbiter"
+            ) {
+                eprintln!("Error logging player action: {}", e);
+            }
+            HttpResponse::Ok().json(ApiResponse::success(token))
+        }
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<PlayerToken>::error(e)),
+    }
+}
+
+// Emitir token a los jugadores que nunca tuvieron uno (torneos anteriores a 1.1.0)
+#[post("/tournament/{id}/player_tokens")]
+pub async fn issue_missing_player_tokens(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    let tournament_id = path.into_inner();
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ManagePlayers) {
+        return resp;
+    }
+        
+    match manager.issue_missing_player_tokens(&tournament_id) {
+        Ok(tokens) => {
+            log::info!("Torneo {}: {} token(s) de jugador emitidos por el árbitro", tournament_id, tokens.len());
+            HttpResponse::Ok().json(ApiResponse::success(tokens))
+        }
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<Vec<PlayerToken>>::error(e)),
+    }
+}
+
+#[delete("/tournament/{id}/player/{player_id}/token")]
+pub async fn revoke_player_token(
+    manager: TournamentManagerData,
+    path: web::Path<(Uuid, Uuid)>,
+    req: HttpRequest,
+) -> HttpResponse {
+    use crate::persistence::PersistenceManager;
+    
+    let mut manager = manager.write().await;
+    let (tournament_id, player_id) = path.into_inner();
+
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ManagePlayers) {
+        return resp;
+    }
+        
+    match manager.revoke_player_token(&tournament_id, &player_id) {
+        Ok(_) => {
+            if let Err(e) = PersistenceManager::log_player_action(
+                &tournament_id.to_string(),
+                &player_id.to_string(),
+                "Player token revoked by arbiter"
+            ) {
+                eprintln!("Error logging player action: {}", e);
+            }
+            HttpResponse::Ok().json(ApiResponse::success("Player token revoked"))
+        }
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// ==================== DEVICE ROUTES ====================
+
+#[get("/tournament/{id}/device_alerts")]
+pub async fn get_device_alerts(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let manager = manager.read().await;
+    let tournament_id = path.into_inner();
+    
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ReviewAlerts) {
+        return resp;
+    }
+    
+    HttpResponse::Ok().json(ApiResponse::success(manager.device_alerts_for(&tournament_id)))
+}
+
+#[put("/tournament/{id}/device_alerts/{alert_id}/ack")]
+pub async fn acknowledge_device_alert(
+    manager: TournamentManagerData,
+    path: web::Path<(Uuid, Uuid)>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    let (tournament_id, alert_id) = path.into_inner();
+    
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ReviewAlerts) {
+        return resp;
+    }
+    
+    match manager.acknowledge_device_alert(&tournament_id, &alert_id) {
+        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Alert acknowledged")),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+#[get("/tournament/{id}/device_policy")]
+pub async fn get_device_policy(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let manager = manager.read().await;
+    let tournament_id = path.into_inner();
+    
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ReviewAlerts) {
+        return resp;
+    }
+    
+    HttpResponse::Ok().json(ApiResponse::success(manager.device_policy_for(&tournament_id)))
+}
+
+#[put("/tournament/{id}/device_policy")]
+pub async fn set_device_policy(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+    body: web::Json<crate::device_guard::DevicePolicy>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    let tournament_id = path.into_inner();
+    
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ConfigureTournament) {
+        return resp;
+    }
+    
+    match manager.set_device_policy(&tournament_id, body.into_inner()) {
+        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Device policy updated")),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+#[put("/tournament/{id}/training")]
+pub async fn set_training_mode(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+    body: web::Json<TrainingModeRequest>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    let tournament_id = path.into_inner();
+    
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ConfigureTournament) {
+        return resp;
+    }
+    
+    match manager.set_training(&tournament_id, body.enabled) {
+        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Training mode updated")),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+// ==================== ARBITER ROUTES ====================
+
+#[post("/tournament/{id}/arbiter/login")]
+pub async fn arbiter_login(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+    body: web::Json<crate::arbiter::ArbiterLoginRequest>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    let tournament_id = path.into_inner();
+    
+    match manager.arbiter_login(&tournament_id, body.name.as_deref(), &body.passphrase) {
+        Ok(login) => {
+            log::info!("Arbiter '{}' ({:?}) logged in to tournament {}", login.name, login.role, tournament_id);
+            HttpResponse::Ok().json(ApiResponse::success(login))
+        }
+        Err(e) => {
+            let peer = req.peer_addr()
+                .map(|addr| addr.ip().to_string())
+                .unwrap_or_else(|| "Unknown".to_string());
+            log::warn!("Failed arbiter login for tournament {} from {}", tournament_id, peer);
+            if let Err(log_err) = crate::persistence::PersistenceManager::log_event(
+                &tournament_id.to_string(),
+                &format!("Intento fallido de acceso de árbitro desde {}", peer)
+            ) {
+                eprintln!("Error logging failed login: {}", log_err);
+            }
+            HttpResponse::Unauthorized().json(ApiResponse::<()>::error(e))
+        }
+    }
+}
+
+#[post("/arbiter/logout")]
+pub async fn arbiter_logout(
+    manager: TournamentManagerData,
+    req: HttpRequest,
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    
+    if let Some(token) = arbiter_token(&req) {
+        manager.arbiter_logout(token);
+    }
+    
+    HttpResponse::Ok().json(ApiResponse::success("Arbiter session closed"))
+}
+
+#[post("/tournament/{id}/arbiters")]
+pub async fn create_arbiter_account(
+    manager: TournamentManagerData,
+    path: web::Path<Uuid>,
+    body: web::Json<crate::arbiter::CreateArbiterRequest>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let mut manager = manager.write().await;
+    let tournament_id = path.into_inner();
+    
+    if let Some(resp) = arbiter_denial(&manager, &req, Some(&tournament_id), ArbiterAction::ManageArbiters) {
+        return resp;
+    }
+    
+    if body.passphrase.is_empty() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error("La frase de acceso no puede estar vacía".to_string()));
+    }
+    
+    let account = crate::arbiter::ArbiterAccount::new(body.name.clone(), body.role, &body.passphrase);
+    match manager.add_arbiter_account(&tournament_id, account) {
+        Ok(_) => HttpResponse::Ok().json(ApiResponse::success(format!("Arbiter '{}' created as {:?}", body.name, body.role))),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(e)),
+    }
+}
+
 #[get("/api/metrics")]
 pub async fn get_queue_metrics(
     async_queue: web::Data<Arc<crate::async_queue::AsyncQueue>>,
@@ -701,12 +1565,64 @@ pub async fn get_queue_metrics(
             "max_latency_ms": metrics.max_latency_ms,
             "queue_size": metrics.queue_size,
             "last_error": metrics.last_error,
+            "dead_letter_size": metrics.dead_letter_size,
+            "total_recovered": metrics.total_recovered,
         }
     }))
 }
 
+// ==================== DEAD LETTER ROUTES ====================
+
+#[get("/api/dead-letters")]
+pub async fn list_dead_letters(
+    manager: TournamentManagerData,
+    async_queue: web::Data<Arc<crate::async_queue::AsyncQueue>>,
+    req: HttpRequest,
+) -> HttpResponse {
+    if let Some(resp) = arbiter_denial(&*manager.read().await, &req, None, ArbiterAction::ConfigureServer) {
+        return resp;
+    }
+    
+    HttpResponse::Ok().json(ApiResponse::success(async_queue.dead_letters().await))
+}
+
+#[post("/api/dead-letters/{id}/retry")]
+pub async fn retry_dead_letter(
+    manager: TournamentManagerData,
+    async_queue: web::Data<Arc<crate::async_queue::AsyncQueue>>,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    if let Some(resp) = arbiter_denial(&*manager.read().await, &req, None, ArbiterAction::ConfigureServer) {
+        return resp;
+    }
+    
+    match async_queue.retry_dead_letter(&path.into_inner()).await {
+        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Play delivered to database")),
+        Err(e) => HttpResponse::BadGateway().json(ApiResponse::<()>::error(e)),
+    }
+}
+
+#[delete("/api/dead-letters/{id}")]
+pub async fn discard_dead_letter(
+    manager: TournamentManagerData,
+    async_queue: web::Data<Arc<crate::async_queue::AsyncQueue>>,
+    path: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    if let Some(resp) = arbiter_denial(&*manager.read().await, &req, None, ArbiterAction::ConfigureServer) {
+        return resp;
+    }
+    
+    match async_queue.discard_dead_letter(&path.into_inner()).await {
+        Ok(entry) => HttpResponse::Ok().json(ApiResponse::success(entry)),
+        Err(e) => HttpResponse::NotFound().json(ApiResponse::<()>::error(e)),
+    }
+}
+
 #[get("/api/health")]
 pub async fn system_health_check(
+    manager: TournamentManagerData,
     async_queue: web::Data<Arc<crate::async_queue::AsyncQueue>>,
     local_cache: web::Data<Arc<crate::local_cache::LocalCache>>,
 ) -> HttpResponse {
@@ -725,6 +1641,7 @@ pub async fn system_health_check(
                 "capacity_used": format!("{:.1}%", (cache_total as f64 / 10000.0) * 100.0),
             }
         },
+        "arbiter_open_mode": manager.read().await.open_mode(),
         "timestamp": Utc::now(),
     }))
 }
@@ -747,6 +1664,7 @@ pub async fn get_cache_stats(
                 "round": p.play_data.round_number,
                 "score": p.play_data.score,
                 "timestamp": p.timestamp,
+                "last_error": p.last_error,
             })
         }).collect::<Vec<_>>(),
     }))
@@ -754,33 +1672,39 @@ pub async fn get_cache_stats(
 
 #[post("/api/cache/sync")]
 pub async fn sync_cache_to_database(
+    manager: TournamentManagerData,
     local_cache: web::Data<Arc<crate::local_cache::LocalCache>>,
-    async_queue: web::Data<Arc<crate::async_queue::AsyncQueue>>,
+    database: web::Data<Arc<Database>>,
+    req: HttpRequest,
 ) -> HttpResponse {
-    let unsynced = local_cache.get_unsynced_plays().await;
-    let mut synced_count = 0;
-    
-    for play in unsynced {
-        if let Ok(_) = async_queue.submit_play(play.play_data.clone()).await {
-            local_cache.mark_as_synced(
-                play.play_data.tournament_id,
-                play.play_data.player_id,
-                play.play_data.round_number,
-            ).await;
-            synced_count += 1;
-        }
+    if let Some(resp) = arbiter_denial(&*manager.read().await, &req, None, ArbiterAction::ConfigureServer) {
+        return resp;
     }
     
-    HttpResponse::Ok().json(serde_json::json!({
-        "synced": synced_count,
-        "message": format!("Synced {} plays to database", synced_count),
-    }))
+    let report = local_cache.sync_to_database(&database, 0).await;
+    let body = serde_json::json!({
+        "synced": report.synced,
+        "failed": report.failed,
+        "last_error": report.last_error,
+        "message": format!("Synced {} plays to database, {} failed", report.synced, report.failed),
+    });
+    if report.failed > 0 {
+        HttpResponse::BadGateway().json(body)
+    } else {
+        HttpResponse::Ok().json(body)
+    }
 }
 
 #[post("/api/cache/clear")]
 pub async fn clear_synced_cache(
+    manager: TournamentManagerData,
     local_cache: web::Data<Arc<crate::local_cache::LocalCache>>,
+    req: HttpRequest,
 ) -> HttpResponse {
+    if let Some(resp) = arbiter_denial(&*manager.read().await, &req, None, ArbiterAction::ConfigureServer) {
+        return resp;
+    }
+    
     local_cache.clear_synced().await;
     
     HttpResponse::Ok().json(serde_json::json!({
@@ -791,7 +1715,7 @@ pub async fn clear_synced_cache(
 
 #[get("/api/persistence/mode")]
 pub async fn get_persistence_mode(
-    config: web::Data<Arc<crate::persistence_mode::PersistenceConfig>>,
+    config: PersistenceConfigData,
 ) -> HttpResponse {
     let current_mode = config.get_mode().await;
     let cloud_available = config.is_cloud_available().await;
@@ -821,6 +1745,12 @@ pub async fn get_persistence_mode(
             "description": "Guarda en la nube primero, local como respaldo",
             "recommended_when": "Prioridad en sincronización inmediata",
         }),
+        serde_json::json!({
+            "value": "LocalSqlite",
+            "name": "Local + SQLite",
+            "description": "Guarda en JSON y en una base SQLite local consultable",
+            "recommended_when": "Sin internet, con archivo histórico de torneos y jugadores",
+        }),
     ];
     
     HttpResponse::Ok().json(serde_json::json!({
@@ -832,15 +1762,22 @@ pub async fn get_persistence_mode(
 
 #[post("/api/persistence/mode")]
 pub async fn set_persistence_mode(
-    config: web::Data<Arc<crate::persistence_mode::PersistenceConfig>>,
+    manager: TournamentManagerData,
+    config: PersistenceConfigData,
     body: web::Json<serde_json::Value>,
+    req: HttpRequest,
 ) -> HttpResponse {
+    if let Some(resp) = arbiter_denial(&*manager.read().await, &req, None, ArbiterAction::ConfigureServer) {
+        return resp;
+    }
+    
     if let Some(mode_str) = body.get("mode").and_then(|m| m.as_str()) {
         let mode = match mode_str {
             "LocalOnly" => crate::persistence_mode::PersistenceMode::LocalOnly,
             "CloudOnly" => crate::persistence_mode::PersistenceMode::CloudOnly,
             "DualLocalFirst" => crate::persistence_mode::PersistenceMode::DualLocalFirst,
             "DualCloudFirst" => crate::persistence_mode::PersistenceMode::DualCloudFirst,
+            "LocalSqlite" => crate::persistence_mode::PersistenceMode::LocalSqlite,
             _ => {
                 return HttpResponse::BadRequest().json(serde_json::json!({
                     "error": "Invalid persistence mode"
@@ -861,3 +1798,30 @@ pub async fn set_persistence_mode(
         }))
     }
 }
+
+// ==================== ARCHIVO SQLITE ====================
+
+#[get("/archive/tournaments")]
+pub async fn list_archived_tournaments(sqlite: SqliteStoreData) -> HttpResponse {
+    let Some(store) = sqlite else {
+        return HttpResponse::ServiceUnavailable().json(ApiResponse::<()>::error("La base SQLite no está disponible".to_string()));
+    };
+    match store.list_tournaments().await {
+        Ok(tournaments) => HttpResponse::Ok().json(ApiResponse::success(tournaments)),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!("Error consultando SQLite: {}", e))),
+    }
+}
+
+#[get("/archive/player/{name}/history")]
+pub async fn get_player_history(
+    sqlite: SqliteStoreData,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let Some(store) = sqlite else {
+        return HttpResponse::ServiceUnavailable().json(ApiResponse::<()>::error("La base SQLite no está disponible".to_string()));
+    };
+    match store.player_history(&path.into_inner()).await {
+        Ok(history) => HttpResponse::Ok().json(ApiResponse::success(history)),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!("Error consultando SQLite: {}", e))),
+    }
+}
@@ -0,0 +1,490 @@
+// Backend SQLite local
+//
+// Mismo esquema que migrations/postgres traducido a SQLite (UUID y fechas
+// como TEXT, JSONB como TEXT). Sirve de archivo consultable: listar torneos o
+// el historial de un jugador sin abrir cada tournament.json. Se activa con
+// PersistenceMode::LocalSqlite; la ruta se configura con SQLITE_PATH.
+
+use std::str::FromStr;
+use serde::Serialize;
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::{Row, Sqlite, Transaction};
+use uuid::Uuid;
+use crate::models::{MasterPlay, Player, PlayerPlay, Round, RoundStatus, Tournament};
+
+pub const DEFAULT_SQLITE_PATH: &str = "tournaments/archive.db";
+
+type StoreResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS tournaments (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    last_modified TEXT DEFAULT CURRENT_TIMESTAMP,
+    status TEXT DEFAULT 'Created' CHECK (status IN ('Created', 'InProgress', 'Finished')),
+    tiles_remaining INTEGER DEFAULT 100,
+    dictionary_hash TEXT,
+    current_round INTEGER DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS players (
+    id TEXT PRIMARY KEY,
+    tournament_id TEXT REFERENCES tournaments(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    total_score INTEGER DEFAULT 0,
+    enrolled_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    ip_address TEXT,
+    user_agent TEXT,
+    hardware_id TEXT
+);
+
+CREATE TABLE IF NOT EXISTS rounds (
+    id TEXT PRIMARY KEY,
+    tournament_id TEXT REFERENCES tournaments(id) ON DELETE CASCADE,
+    number INTEGER NOT NULL,
+    rack TEXT NOT NULL,
+    status TEXT DEFAULT 'Generated' CHECK (status IN ('Generated', 'InProgress', 'Completed')),
+    optimal_revealed INTEGER DEFAULT 0,
+    rack_rejected INTEGER DEFAULT 0,
+    start_time TEXT,
+    timer_duration INTEGER DEFAULT 180,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(tournament_id, number)
+);
+
+CREATE TABLE IF NOT EXISTS master_plays (
+    id TEXT PRIMARY KEY,
+    tournament_id TEXT REFERENCES tournaments(id) ON DELETE CASCADE,
+    round_number INTEGER NOT NULL,
+    word TEXT NOT NULL,
+    coord TEXT NOT NULL,
+    score INTEGER NOT NULL,
+    direction TEXT CHECK (direction IN ('across', 'down')),
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(tournament_id, round_number)
+);
+
+CREATE TABLE IF NOT EXISTS player_plays (
+    id TEXT PRIMARY KEY,
+    tournament_id TEXT REFERENCES tournaments(id) ON DELETE CASCADE,
+    player_id TEXT REFERENCES players(id) ON DELETE CASCADE,
+    round_number INTEGER NOT NULL,
+    word TEXT,
+    position_row INTEGER,
+    position_col INTEGER,
+    position_down INTEGER,
+    score INTEGER DEFAULT 0,
+    percentage_of_optimal REAL DEFAULT 0,
+    submitted_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    cumulative_score INTEGER DEFAULT 0,
+    difference_from_optimal INTEGER DEFAULT 0,
+    cumulative_difference INTEGER DEFAULT 0,
+    UNIQUE(tournament_id, player_id, round_number)
+);
+
+CREATE TABLE IF NOT EXISTS board_states (
+    id TEXT PRIMARY KEY,
+    tournament_id TEXT REFERENCES tournaments(id) ON DELETE CASCADE,
+    round_number INTEGER NOT NULL,
+    board_data TEXT NOT NULL,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(tournament_id, round_number)
+);
+
+CREATE INDEX IF NOT EXISTS idx_tournaments_status ON tournaments(status);
+CREATE INDEX IF NOT EXISTS idx_players_tournament ON players(tournament_id);
+CREATE INDEX IF NOT EXISTS idx_players_name ON players(name COLLATE NOCASE);
+CREATE INDEX IF NOT EXISTS idx_rounds_tournament ON rounds(tournament_id, number);
+CREATE INDEX IF NOT EXISTS idx_master_plays_tournament ON master_plays(tournament_id, round_number);
+CREATE INDEX IF NOT EXISTS idx_player_plays_tournament ON player_plays(tournament_id, round_number);
+CREATE INDEX IF NOT EXISTS idx_player_plays_player ON player_plays(player_id, round_number);
+"#;
+
+/// Tablas cuyo cambio actualiza tournaments.last_modified
+const MODIFIED_TRIGGER_TABLES: &[&str] = &["players", "rounds", "master_plays", "player_plays"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchivedTournament {
+    pub id: String,
+    pub name: String,
+    pub created_at: String,
+    pub last_modified: String,
+    pub status: String,
+    pub current_round: i64,
+    pub player_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerHistoryEntry {
+    pub tournament_id: String,
+    pub tournament_name: String,
+    pub tournament_created_at: String,
+    pub player_id: String,
+    pub player_name: String,
+    pub total_score: i64,
+    pub rounds_played: i64,
+    pub average_percentage: f64,
+}
+
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    /// Abre (o crea) la base en SQLITE_PATH o en tournaments/archive.db
+    pub async fn open_default() -> StoreResult<Self> {
+        dotenv::dotenv().ok();
+        let path = std::env::var("SQLITE_PATH").unwrap_or_else(|_| DEFAULT_SQLITE_PATH.to_string());
+        if let Some(parent) = std::path::Path::new(&path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Self::connect(&format!("sqlite://{}", path)).await
+    }
+
+    pub async fn connect(url: &str) -> StoreResult<Self> {
+        let options = SqliteConnectOptions::from_str(url)?
+            .create_if_missing(true)
+            .foreign_keys(true);
+        // Una sola conexión: SQLite serializa las escrituras de todos modos
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+
+        let store = SqliteStore { pool };
+        store.init_schema().await?;
+        Ok(store)
+    }
+
+    async fn init_schema(&self) -> StoreResult<()> {
+        sqlx::raw_sql(SCHEMA).execute(&self.pool).await?;
+
+        for table in MODIFIED_TRIGGER_TABLES {
+            for (event, row) in [("INSERT", "NEW"), ("UPDATE", "NEW"), ("DELETE", "OLD")] {
+                let trigger = format!(
+                    "CREATE TRIGGER IF NOT EXISTS update_tournament_modified_{table}_{event_lower} \
+                     AFTER {event} ON {table} FOR EACH ROW BEGIN \
+                     UPDATE tournaments SET last_modified = CURRENT_TIMESTAMP WHERE id = {row}.tournament_id; \
+                     END",
+                    table = table,
+                    event = event,
+                    event_lower = event.to_lowercase(),
+                    row = row,
+                );
+                sqlx::raw_sql(&trigger).execute(&self.pool).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Escribe el torneo completo en una sola transacción (creación, inscripciones,
+    /// carga desde JSON, fin de torneo). Las rondas deshechas se eliminan.
+    pub async fn save_tournament(&self, tournament: &Tournament) -> StoreResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let tournament_id = tournament.id.to_string();
+
+        upsert_tournament_row(&mut tx, tournament).await?;
+        for player in &tournament.players {
+            upsert_player(&mut tx, &tournament_id, player).await?;
+            for play in &player.plays {
+                upsert_play(&mut tx, &tournament_id, &player.id, play).await?;
+            }
+        }
+        for round in &tournament.rounds {
+            upsert_round(&mut tx, &tournament_id, round).await?;
+        }
+        for master in &tournament.master_plays {
+            upsert_master_play(&mut tx, &tournament_id, master).await?;
+        }
+
+        let last_round = tournament.rounds.len() as i64;
+        for table in ["player_plays", "master_plays", "board_states"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE tournament_id = ? AND round_number > ?", table))
+                .bind(&tournament_id)
+                .bind(last_round)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("DELETE FROM rounds WHERE tournament_id = ? AND number > ?")
+            .bind(&tournament_id)
+            .bind(last_round)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Ronda, tablero y jugada maestra (si la ronda terminó) en una transacción
+    pub async fn save_round(&self, tournament: &Tournament, round_number: u32) -> StoreResult<()> {
+        let round = tournament.rounds.iter()
+            .find(|r| r.number == round_number)
+            .ok_or_else(|| format!("Ronda {} no encontrada", round_number))?;
+        let tournament_id = tournament.id.to_string();
+
+        let mut tx = self.pool.begin().await?;
+        upsert_tournament_row(&mut tx, tournament).await?;
+        upsert_round(&mut tx, &tournament_id, round).await?;
+        if let Some(master) = tournament.master_plays.iter().find(|m| m.round_number == round_number) {
+            upsert_master_play(&mut tx, &tournament_id, master).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Jugada del jugador y su total acumulado en una transacción
+    pub async fn save_play(&self, tournament_id: &Uuid, player: &Player, round_number: u32) -> StoreResult<()> {
+        let play = player.plays.iter()
+            .find(|p| p.round_number == round_number)
+            .ok_or_else(|| format!("El jugador no tiene jugada en la ronda {}", round_number))?;
+        let tournament_id = tournament_id.to_string();
+
+        let mut tx = self.pool.begin().await?;
+        upsert_player(&mut tx, &tournament_id, player).await?;
+        upsert_play(&mut tx, &tournament_id, &player.id, play).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn list_tournaments(&self) -> StoreResult<Vec<ArchivedTournament>> {
+        let rows = sqlx::query(
+            "SELECT t.id, t.name, t.created_at, t.last_modified, t.status, t.current_round, \
+                    (SELECT COUNT(*) FROM players p WHERE p.tournament_id = t.id) AS player_count \
+             FROM tournaments t ORDER BY t.created_at DESC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| ArchivedTournament {
+            id: row.get("id"),
+            name: row.get("name"),
+            created_at: row.get("created_at"),
+            last_modified: row.get("last_modified"),
+            status: row.get("status"),
+            current_round: row.get("current_round"),
+            player_count: row.get("player_count"),
+        }).collect())
+    }
+
+    /// Participaciones de un jugador (por nombre, sin distinguir mayúsculas) en todos los torneos
+    pub async fn player_history(&self, player_name: &str) -> StoreResult<Vec<PlayerHistoryEntry>> {
+        let rows = sqlx::query(
+            "SELECT t.id AS tournament_id, t.name AS tournament_name, t.created_at AS tournament_created_at, \
+                    p.id AS player_id, p.name AS player_name, p.total_score, \
+                    COUNT(pp.id) AS rounds_played, COALESCE(AVG(pp.percentage_of_optimal), 0.0) AS average_percentage \
+             FROM players p \
+             JOIN tournaments t ON t.id = p.tournament_id \
+             LEFT JOIN player_plays pp ON pp.player_id = p.id \
+             WHERE p.name = ? COLLATE NOCASE \
+             GROUP BY p.id \
+             ORDER BY t.created_at DESC",
+        )
+        .bind(player_name.trim())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| PlayerHistoryEntry {
+            tournament_id: row.get("tournament_id"),
+            tournament_name: row.get("tournament_name"),
+            tournament_created_at: row.get("tournament_created_at"),
+            player_id: row.get("player_id"),
+            player_name: row.get("player_name"),
+            total_score: row.get("total_score"),
+            rounds_played: row.get("rounds_played"),
+            average_percentage: row.get("average_percentage"),
+        }).collect())
+    }
+}
+
+fn timestamp(at: &DateTime<Utc>) -> String {
+    at.to_rfc3339()
+}
+
+/// Estados de ronda del servidor a los del esquema SQL
+fn round_status(status: &RoundStatus) -> &'static str {
+    match status {
+        RoundStatus::Pending => "Generated",
+        RoundStatus::Active => "InProgress",
+        RoundStatus::Completed => "Completed",
+    }
+}
+
+async fn upsert_tournament_row(tx: &mut Transaction<'_, Sqlite>, tournament: &Tournament) -> StoreResult<()> {
+    sqlx::query(
+        "INSERT INTO tournaments (id, name, created_at, status, tiles_remaining, current_round) \
+         VALUES (?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, status = excluded.status, \
+             tiles_remaining = excluded.tiles_remaining, current_round = excluded.current_round, \
+             last_modified = CURRENT_TIMESTAMP",
+    )
+    .bind(tournament.id.to_string())
+    .bind(&tournament.name)
+    .bind(timestamp(&tournament.created_at))
+    .bind(format!("{:?}", tournament.status))
+    .bind(tournament.tiles_remaining as i64)
+    .bind(tournament.rounds.len() as i64)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+async fn upsert_player(tx: &mut Transaction<'_, Sqlite>, tournament_id: &str, player: &Player) -> StoreResult<()> {
+    sqlx::query(
+        "INSERT INTO players (id, tournament_id, name, total_score) VALUES (?, ?, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, total_score = excluded.total_score",
+    )
+    .bind(player.id.to_string())
+    .bind(tournament_id)
+    .bind(&player.name)
+    .bind(player.total_score as i64)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+async fn upsert_play(tx: &mut Transaction<'_, Sqlite>, tournament_id: &str, player_id: &Uuid, play: &PlayerPlay) -> StoreResult<()> {
+    sqlx::query(
+        "INSERT INTO player_plays (id, tournament_id, player_id, round_number, word, position_row, position_col, \
+             position_down, score, percentage_of_optimal, submitted_at, cumulative_score, difference_from_optimal, \
+             cumulative_difference) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(tournament_id, player_id, round_number) DO UPDATE SET word = excluded.word, \
+             position_row = excluded.position_row, position_col = excluded.position_col, \
+             position_down = excluded.position_down, score = excluded.score, \
+             percentage_of_optimal = excluded.percentage_of_optimal, submitted_at = excluded.submitted_at, \
+             cumulative_score = excluded.cumulative_score, \
+             difference_from_optimal = excluded.difference_from_optimal, \
+             cumulative_difference = excluded.cumulative_difference",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(tournament_id)
+    .bind(player_id.to_string())
+    .bind(play.round_number as i64)
+    .bind(&play.word)
+    .bind(play.position.row as i64)
+    .bind(play.position.col as i64)
+    .bind(play.position.down)
+    .bind(play.score as i64)
+    .bind(play.percentage_of_optimal as f64)
+    .bind(timestamp(&play.submitted_at))
+    .bind(play.cumulative_score as i64)
+    .bind(play.difference_from_optimal as i64)
+    .bind(play.cumulative_difference as i64)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+async fn upsert_round(tx: &mut Transaction<'_, Sqlite>, tournament_id: &str, round: &Round) -> StoreResult<()> {
+    sqlx::query(
+        "INSERT INTO rounds (id, tournament_id, number, rack, status, optimal_revealed, rack_rejected, start_time) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(tournament_id, number) DO UPDATE SET rack = excluded.rack, status = excluded.status, \
+             optimal_revealed = excluded.optimal_revealed, rack_rejected = excluded.rack_rejected, \
+             start_time = excluded.start_time",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(tournament_id)
+    .bind(round.number as i64)
+    .bind(&round.rack)
+    .bind(round_status(&round.status))
+    .bind(round.optimal_revealed)
+    .bind(round.rack_rejected)
+    .bind(round.timer_started.as_ref().map(timestamp))
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO board_states (id, tournament_id, round_number, board_data) VALUES (?, ?, ?, ?) \
+         ON CONFLICT(tournament_id, round_number) DO UPDATE SET board_data = excluded.board_data",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(tournament_id)
+    .bind(round.number as i64)
+    .bind(serde_json::to_string(&round.board_state)?)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+async fn upsert_master_play(tx: &mut Transaction<'_, Sqlite>, tournament_id: &str, master: &MasterPlay) -> StoreResult<()> {
+    sqlx::query(
+        "INSERT INTO master_plays (id, tournament_id, round_number, word, coord, score, direction) \
+         VALUES (?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(tournament_id, round_number) DO UPDATE SET word = excluded.word, coord = excluded.coord, \
+             score = excluded.score, direction = excluded.direction",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(tournament_id)
+    .bind(master.round_number as i64)
+    .bind(&master.word)
+    .bind(crate::tournament_manager::format_coordinate(&master.position))
+    .bind(master.score as i64)
+    .bind(if master.position.down { "down" } else { "across" })
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Position, TournamentStatus};
+    use crate::models::test_support::active_round;
+
+    fn tournament() -> Tournament {
+        let play = PlayerPlay {
+            round_number: 1,
+            word: "CASERO".to_string(),
+            position: Position { row: 7, col: 7, down: false },
+            score: 24,
+            percentage_of_optimal: 100.0,
+            submitted_at: Utc::now(),
+            cumulative_score: 24,
+            difference_from_optimal: 0,
+            cumulative_difference: 0,
+            rank: None,
+        };
+        Tournament {
+            id: Uuid::new_v4(),
+            name: "Archivo".to_string(),
+            created_at: Utc::now(),
+            status: TournamentStatus::InProgress,
+            rounds: vec![active_round(1, "ACEORS?")],
+            players: vec![Player { id: Uuid::new_v4(), name: "Ana".to_string(), total_score: 24, plays: vec![play], category: None, club: None, federation_id: None }],
+            tiles_remaining: 93,
+            master_plays: Vec::new(),
+            training: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_archive_queries() {
+        let store = SqliteStore::connect("sqlite::memory:").await.unwrap();
+        let mut t = tournament();
+        store.save_tournament(&t).await.unwrap();
+
+        let list = store.list_tournaments().await.unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].status, "InProgress");
+        assert_eq!(list[0].player_count, 1);
+
+        // Reenviar la jugada actualiza la fila en lugar de duplicarla
+        t.players[0].plays[0].score = 30;
+        t.players[0].total_score = 30;
+        store.save_play(&t.id, &t.players[0], 1).await.unwrap();
+
+        let history = store.player_history("ana").await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].total_score, 30);
+        assert_eq!(history[0].rounds_played, 1);
+
+        // Deshacer la ronda la elimina del archivo
+        t.rounds.clear();
+        t.players[0].plays.clear();
+        store.save_tournament(&t).await.unwrap();
+        assert_eq!(store.player_history("Ana").await.unwrap()[0].rounds_played, 0);
+    }
+}
@@ -0,0 +1,287 @@
+// Clasificación y resultados para imprimir o pasar a una hoja de cálculo
+//
+// Mismas cuentas que la tabla final del panel del árbitro: el porcentaje y la
+// diferencia se miden contra el total del master. Se exporta como CSV
+// (clasificación y matriz de puntos por ronda por separado) o como un informe
+// HTML autocontenido listo para imprimir.
+
+use serde::Serialize;
+use crate::models::Tournament;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StandingRow {
+    pub rank: usize,
+    pub name: String,
+    pub category: Option<String>,
+    pub total: i32,
+    pub percentage: f32,
+    pub difference: i32,
+    pub round_scores: Vec<Option<i32>>,  // Puntos por ronda, en el orden de `Standings::rounds`
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Standings {
+    pub tournament_name: String,
+    pub rounds: Vec<u32>,
+    pub master_total: i32,
+    pub master_scores: Vec<Option<i32>>,
+    pub rows: Vec<StandingRow>,
+}
+
+pub fn compute(tournament: &Tournament) -> Standings {
+    let rounds: Vec<u32> = tournament.rounds.iter().map(|r| r.number).collect();
+    let master_total: i32 = tournament.master_plays.iter().map(|mp| mp.score).sum();
+    let master_scores = rounds.iter()
+        .map(|n| tournament.master_plays.iter().find(|mp| mp.round_number == *n).map(|mp| mp.score))
+        .collect();
+
+    let mut players: Vec<_> = tournament.players.iter().collect();
+    players.sort_by(|a, b| b.total_score.cmp(&a.total_score).then_with(|| a.name.cmp(&b.name)));
+
+    let mut rows: Vec<StandingRow> = Vec::with_capacity(players.len());
+    for (index, player) in players.into_iter().enumerate() {
+        // Empatados comparten puesto (1, 1, 3)
+        let rank = match rows.last() {
+            Some(prev) if prev.total == player.total_score => prev.rank,
+            _ => index + 1,
+        };
+        rows.push(StandingRow {
+            rank,
+            name: player.name.clone(),
+            category: player.category.clone(),
+            total: player.total_score,
+            percentage: if master_total > 0 {
+                player.total_score as f32 / master_total as f32 * 100.0
+            } else {
+                0.0
+            },
+            difference: player.total_score - master_total,
+            round_scores: rounds.iter()
+                .map(|n| player.plays.iter().find(|p| p.round_number == *n).map(|p| p.score))
+                .collect(),
+        });
+    }
+
+    Standings {
+        tournament_name: tournament.name.clone(),
+        rounds,
+        master_total,
+        master_scores,
+        rows,
+    }
+}
+
+/// Escapa un campo CSV. El texto que empieza como una fórmula (`=`, `+`, `-`,
+/// `@`, tabulador o retorno de carro) lleva un apóstrofo delante para que la hoja de cálculo no lo ejecute;
+/// los números negativos se dejan tal cual.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) && value.parse::<f64>().is_err() {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn csv_line(fields: &[String]) -> String {
+    let mut line = fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(",");
+    line.push_str("\r\n");
+    line
+}
+
+fn score_cell(score: Option<i32>) -> String {
+    score.map(|s| s.to_string()).unwrap_or_default()
+}
+
+/// Clasificación final: puesto, nombre, categoría, total, porcentaje y diferencia
+pub fn standings_csv(standings: &Standings) -> String {
+    let mut csv = csv_line(&["Puesto", "Nombre", "Categoría", "Total", "Porcentaje", "Diferencia"].map(String::from));
+    for row in &standings.rows {
+        csv.push_str(&csv_line(&[
+            row.rank.to_string(),
+            row.name.clone(),
+            row.category.clone().unwrap_or_default(),
+            row.total.to_string(),
+            format!("{:.1}", row.percentage),
+            row.difference.to_string(),
+        ]));
+    }
+    csv
+}
+
+/// Matriz de puntos por ronda; la primera fila es la del master
+pub fn rounds_csv(standings: &Standings) -> String {
+    let mut header = vec!["Puesto".to_string(), "Nombre".to_string()];
+    header.extend(standings.rounds.iter().map(|n| format!("R{}", n)));
+    header.push("Total".to_string());
+    let mut csv = csv_line(&header);
+
+    let mut master = vec![String::new(), "Master".to_string()];
+    master.extend(standings.master_scores.iter().map(|s| score_cell(*s)));
+    master.push(standings.master_total.to_string());
+    csv.push_str(&csv_line(&master));
+
+    for row in &standings.rows {
+        let mut fields = vec![row.rank.to_string(), row.name.clone()];
+        fields.extend(row.round_scores.iter().map(|s| score_cell(*s)));
+        fields.push(row.total.to_string());
+        csv.push_str(&csv_line(&fields));
+    }
+    csv
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const REPORT_STYLE: &str = "\
+body { font-family: Arial, Helvetica, sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.6em; margin-bottom: 0.2em; }
+h2 { font-size: 1.2em; margin-top: 2em; }
+p.meta { color: #555; margin-top: 0; }
+table { border-collapse: collapse; width: 100%; font-size: 0.9em; }
+th, td { border: 1px solid #999; padding: 4px 6px; }
+th { background: #eee; }
+td.num { text-align: right; }
+tr.master td { font-style: italic; background: #f7f7f7; }
+.matrix { page-break-before: always; }
+@media print {
+  body { margin: 0; }
+  th { background: #ddd !important; -webkit-print-color-adjust: exact; print-color-adjust: exact; }
+}";
+
+/// Informe HTML con la clasificación y la matriz por rondas, sin recursos externos
+pub fn report_html(standings: &Standings, generated_at: &str) -> String {
+    let name = escape_html(&standings.tournament_name);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"es\">\n<head>\n<meta charset=\"utf-8\">\n<title>{name} - Clasificación</title>\n<style>\n{}\n</style>\n</head>\n<body>\n\
+         <h1>{name}</h1>\n<p class=\"meta\">Clasificación tras {} rondas · Master: {} puntos · Generado el {}</p>\n",
+        REPORT_STYLE,
+        standings.rounds.len(),
+        standings.master_total,
+        escape_html(generated_at),
+    );
+
+    html.push_str("<h2>Clasificación</h2>\n<table>\n<thead><tr><th>Puesto</th><th>Nombre</th><th>Categoría</th><th>Total</th><th>%</th><th>Diferencia</th></tr></thead>\n<tbody>\n");
+    for row in &standings.rows {
+        html.push_str(&format!(
+            "<tr><td class=\"num\">{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{:.1}</td><td class=\"num\">{:+}</td></tr>\n",
+            row.rank,
+            escape_html(&row.name),
+            escape_html(row.category.as_deref().unwrap_or("")),
+            row.total,
+            row.percentage,
+            row.difference,
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    html.push_str("<div class=\"matrix\">\n<h2>Puntos por ronda</h2>\n<table>\n<thead><tr><th>Puesto</th><th>Nombre</th>");
+    for number in &standings.rounds {
+        html.push_str(&format!("<th>R{}</th>", number));
+    }
+    html.push_str("<th>Total</th></tr></thead>\n<tbody>\n<tr class=\"master\"><td></td><td>Master</td>");
+    for score in &standings.master_scores {
+        html.push_str(&format!("<td class=\"num\">{}</td>", score_cell(*score)));
+    }
+    html.push_str(&format!("<td class=\"num\">{}</td></tr>\n", standings.master_total));
+    for row in &standings.rows {
+        html.push_str(&format!("<tr><td class=\"num\">{}</td><td>{}</td>", row.rank, escape_html(&row.name)));
+        for score in &row.round_scores {
+            html.push_str(&format!("<td class=\"num\">{}</td>", score_cell(*score)));
+        }
+        html.push_str(&format!("<td class=\"num\">{}</td></tr>\n", row.total));
+    }
+    html.push_str("</tbody>\n</table>\n</div>\n</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use crate::models::{Player, PlayerPlay, Position, TournamentStatus};
+    use crate::models::test_support::{completed_round, master};
+
+    fn play(round_number: u32, score: i32) -> PlayerPlay {
+        PlayerPlay {
+            round_number,
+            word: "CASA".to_string(),
+            position: Position { row: 7, col: 7, down: false },
+            score,
+            percentage_of_optimal: 0.0,
+            submitted_at: Utc::now(),
+            cumulative_score: 0,
+            difference_from_optimal: 0,
+            cumulative_difference: 0,
+            rank: None,
+        }
+    }
+
+    fn player(name: &str, category: Option<&str>, plays: Vec<PlayerPlay>) -> Player {
+        Player {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            total_score: plays.iter().map(|p| p.score).sum(),
+            plays,
+            category: category.map(str::to_string),
+            club: None,
+            federation_id: None,
+        }
+    }
+
+    #[test]
+    fn test_standings_rank_ties_and_escape_csv() {
+        let master = |round_number, score| master(round_number, "CASA", 7, 7, false, score, 0);
+        let round = |number| completed_round(number, "ACAS???");
+        let tournament = Tournament {
+            id: Uuid::new_v4(),
+            name: "Abierto <2026>".to_string(),
+            created_at: Utc::now(),
+            status: TournamentStatus::Finished,
+            rounds: vec![round(1), round(2)],
+            players: vec![
+                player("Pérez, Ana", Some("Élite"), vec![play(1, 30), play(2, 10)]),
+                player("Luis", None, vec![play(1, 40)]),
+                player("Marta", Some("Sub-18"), vec![play(2, 20)]),
+            ],
+            tiles_remaining: 0,
+            master_plays: vec![master(1, 40), master(2, 40)],
+            training: false,
+        };
+
+        let standings = compute(&tournament);
+        let ranks: Vec<(usize, &str)> = standings.rows.iter().map(|r| (r.rank, r.name.as_str())).collect();
+        assert_eq!(ranks, vec![(1, "Luis"), (1, "Pérez, Ana"), (3, "Marta")]);
+        assert_eq!(standings.rows[0].round_scores, vec![Some(40), None]);
+
+        let csv = standings_csv(&standings);
+        assert!(csv.contains("1,\"Pérez, Ana\",Élite,40,50.0,-40\r\n"), "{}", csv);
+        assert!(rounds_csv(&standings).contains(",Master,40,40,80\r\n"));
+
+        let html = report_html(&standings, "2026-10-18");
+        assert!(html.contains("Abierto &lt;2026&gt;"));
+        assert!(!html.contains("<script"));
+    }
+
+    #[test]
+    fn test_csv_neutralizes_formulas() {
+        assert_eq!(csv_field("=HYPERLINK(\"http://x\")"), "\"'=HYPERLINK(\"\"http://x\"\")\"");
+        assert_eq!(csv_field("+34 600"), "'+34 600");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("-cmd|' /C calc'!A0"), "'-cmd|' /C calc'!A0");
+        assert_eq!(csv_field("-40"), "-40");
+        assert_eq!(csv_field("-12.5"), "-12.5");
+        assert_eq!(csv_field("\t=1+1"), "'\t=1+1");
+        assert_eq!(csv_field("\r=1+1"), "\"'\r=1+1\"");
+        assert_eq!(csv_field("Ana"), "Ana");
+    }
+}
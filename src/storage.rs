@@ -0,0 +1,533 @@
+// Almacenamiento de torneos
+//
+// Cada backend (archivos JSON, SQLite, Supabase/Postgres, memoria) implementa
+// TournamentStorage. El manager solo conoce un almacenamiento: ModeStorage
+// reparte cada escritura entre los backends según el PersistenceMode activo.
+// Los backends asíncronos se envuelven en QueuedStorage, que aplica las
+// operaciones en orden desde un worker de tokio.
+
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use crate::async_queue::{AsyncQueue, PlayData};
+use crate::database::Database;
+use crate::journal::{JournalEntry, JournalEvent};
+use crate::local_cache::LocalCache;
+use crate::models::Tournament;
+use crate::persistence::{LoadedTournament, PersistenceManager, PlayerSession};
+use crate::persistence_mode::{PersistenceConfig, WritePriority};
+use crate::sqlite_store::SqliteStore;
+
+/// Estado que acompaña a cada evento, prestado del manager: registrar un
+/// evento no clona el torneo salvo en los backends que lo envían a otro hilo
+#[derive(Clone, Copy)]
+pub struct EventState<'a> {
+    pub tournament: &'a Tournament,
+    pub player_sessions: &'a [PlayerSession],
+}
+
+impl EventState<'_> {
+    fn into_owned(self) -> OwnedEventState {
+        OwnedEventState {
+            tournament: self.tournament.clone(),
+            player_sessions: self.player_sessions.to_vec(),
+        }
+    }
+}
+
+/// Copia de EventState para los backends asíncronos
+pub struct OwnedEventState {
+    pub tournament: Tournament,
+    pub player_sessions: Vec<PlayerSession>,
+}
+
+pub trait TournamentStorage: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Prepara el espacio del torneo (directorio, filas base)
+    fn create_tournament(&self, state: &LoadedTournament) -> Result<(), String>;
+
+    /// Registra un cambio de estado; `state` ya incluye el cambio
+    fn record_event(&self, state: EventState<'_>, entry: &JournalEntry) -> Result<(), String>;
+
+    /// Guarda el estado completo (punto de control)
+    fn save_snapshot(&self, state: &LoadedTournament) -> Result<(), String>;
+
+    /// Vuelve a subir el estado completo tras un corte; solo lo usan los backends remotos
+    fn resync(&self, _state: &LoadedTournament) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Archivos en tournaments/<dir>: snapshot firmado, backups y diario
+pub struct JsonStorage;
+
+impl TournamentStorage for JsonStorage {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn create_tournament(&self, state: &LoadedTournament) -> Result<(), String> {
+        let tournament = &state.tournament;
+        PersistenceManager::create_tournament_directory(&tournament.id.to_string(), &tournament.name)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn record_event(&self, state: EventState<'_>, entry: &JournalEntry) -> Result<(), String> {
+        PersistenceManager::append_journal(&state.tournament.id.to_string(), entry)
+            .map_err(|e| e.to_string())
+    }
+
+    fn save_snapshot(&self, state: &LoadedTournament) -> Result<(), String> {
+        PersistenceManager::save_tournament(state).map_err(|e| e.to_string())
+    }
+}
+
+/// Almacenamiento en memoria para pruebas
+#[cfg(test)]
+pub mod memory {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+    use super::{EventState, TournamentStorage};
+    use crate::journal::JournalEntry;
+    use crate::persistence::LoadedTournament;
+
+    #[derive(Default)]
+    pub struct InMemoryStorage {
+        snapshots: Mutex<HashMap<Uuid, LoadedTournament>>,
+        journals: Mutex<HashMap<Uuid, Vec<JournalEntry>>>,
+        resynced: Mutex<Vec<Uuid>>,
+    }
+
+    impl InMemoryStorage {
+        pub fn snapshot(&self, tournament_id: &Uuid) -> Option<LoadedTournament> {
+            self.snapshots.lock().unwrap_or_else(|e| e.into_inner()).get(tournament_id).cloned()
+        }
+
+        pub fn journal(&self, tournament_id: &Uuid) -> Vec<JournalEntry> {
+            self.journals.lock().unwrap_or_else(|e| e.into_inner()).get(tournament_id).cloned().unwrap_or_default()
+        }
+
+        pub fn resynced(&self) -> Vec<Uuid> {
+            self.resynced.lock().unwrap_or_else(|e| e.into_inner()).clone()
+        }
+    }
+
+    impl TournamentStorage for InMemoryStorage {
+        fn name(&self) -> &'static str {
+            "memory"
+        }
+
+        fn create_tournament(&self, state: &LoadedTournament) -> Result<(), String> {
+            self.journals.lock().unwrap_or_else(|e| e.into_inner()).entry(state.tournament.id).or_default();
+            Ok(())
+        }
+
+        fn record_event(&self, state: EventState<'_>, entry: &JournalEntry) -> Result<(), String> {
+            self.journals.lock().unwrap_or_else(|e| e.into_inner())
+                .entry(state.tournament.id)
+                .or_default()
+                .push(entry.clone());
+            Ok(())
+        }
+
+        fn save_snapshot(&self, state: &LoadedTournament) -> Result<(), String> {
+            self.snapshots.lock().unwrap_or_else(|e| e.into_inner()).insert(state.tournament.id, state.clone());
+            Ok(())
+        }
+
+        fn resync(&self, state: &LoadedTournament) -> Result<(), String> {
+            self.resynced.lock().unwrap_or_else(|e| e.into_inner()).push(state.tournament.id);
+            Ok(())
+        }
+    }
+}
+
+/// Operación pendiente para un backend asíncrono
+pub enum StorageOp {
+    Create(LoadedTournament),
+    Event(Box<OwnedEventState>, Box<JournalEntry>),
+    Snapshot(LoadedTournament),
+    Resync(LoadedTournament),
+}
+
+/// Adapta un backend asíncrono: las escrituras se encolan y un worker las aplica en orden
+pub struct QueuedStorage {
+    name: &'static str,
+    sender: mpsc::UnboundedSender<StorageOp>,
+}
+
+impl QueuedStorage {
+    /// Requiere un runtime de tokio activo
+    pub fn spawn<F, Fut>(name: &'static str, handler: F) -> Self
+    where
+        F: Fn(StorageOp) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<StorageOp>();
+        tokio::spawn(async move {
+            while let Some(op) = receiver.recv().await {
+                if let Err(e) = handler(op).await {
+                    log::warn!("Escritura en {} fallida: {}", name, e);
+                }
+            }
+        });
+        Self { name, sender }
+    }
+
+    fn enqueue(&self, op: StorageOp) -> Result<(), String> {
+        self.sender.send(op).map_err(|_| format!("El worker de {} no está activo", self.name))
+    }
+}
+
+impl TournamentStorage for QueuedStorage {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn create_tournament(&self, state: &LoadedTournament) -> Result<(), String> {
+        self.enqueue(StorageOp::Create(state.clone()))
+    }
+
+    fn record_event(&self, state: EventState<'_>, entry: &JournalEntry) -> Result<(), String> {
+        self.enqueue(StorageOp::Event(Box::new(state.into_owned()), Box::new(entry.clone())))
+    }
+
+    fn save_snapshot(&self, state: &LoadedTournament) -> Result<(), String> {
+        self.enqueue(StorageOp::Snapshot(state.clone()))
+    }
+
+    fn resync(&self, state: &LoadedTournament) -> Result<(), String> {
+        self.enqueue(StorageOp::Resync(state.clone()))
+    }
+}
+
+/// Base SQLite local: snapshot completo en una transacción, jugadas una a una
+pub fn sqlite_storage(store: Arc<SqliteStore>) -> QueuedStorage {
+    QueuedStorage::spawn("sqlite", move |op| {
+        let store = store.clone();
+        async move {
+            match op {
+                StorageOp::Create(state) | StorageOp::Snapshot(state) | StorageOp::Resync(state) => store.save_tournament(&state.tournament).await,
+                StorageOp::Event(state, entry) => match entry.event {
+                    // Las jugadas no generan snapshot en cada envío
+                    JournalEvent::PlaySubmitted { player_id, play } => {
+                        match state.tournament.players.iter().find(|p| p.id == player_id) {
+                            Some(player) => store.save_play(&state.tournament.id, player, play.round_number).await,
+                            None => Ok(()),
+                        }
+                    }
+                    JournalEvent::RoundStarted { round, .. } => store.save_round(&state.tournament, round.number).await,
+                    JournalEvent::RackUpdated { round_number, .. }
+                    | JournalEvent::RackRejected { round_number, .. }
+                    | JournalEvent::TimerStarted { round_number, .. }
+                    | JournalEvent::MasterRevealed { round_number }
+                    | JournalEvent::MasterPlaced { round_number, .. } => store.save_round(&state.tournament, round_number).await,
+                    _ => Ok(()),
+                },
+            }
+            .map_err(|e| e.to_string())
+        }
+    })
+}
+
+/// Caché local de jugadas pendientes de subir. ModeStorage la escribe aunque la
+/// nube esté caída: al volver, el sincronizador sube lo enviado durante el corte.
+pub struct CacheStorage {
+    sender: mpsc::UnboundedSender<PlayData>,
+}
+
+impl CacheStorage {
+    /// Requiere un runtime de tokio activo
+    pub fn spawn(cache: Arc<LocalCache>) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<PlayData>();
+        tokio::spawn(async move {
+            while let Some(play) = receiver.recv().await {
+                if let Err(e) = cache.store_play(play).await {
+                    log::warn!("Failed to cache play locally: {}", e);
+                }
+            }
+        });
+        Self { sender }
+    }
+}
+
+impl TournamentStorage for CacheStorage {
+    fn name(&self) -> &'static str {
+        "cache"
+    }
+
+    fn create_tournament(&self, _state: &LoadedTournament) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn record_event(&self, state: EventState<'_>, entry: &JournalEntry) -> Result<(), String> {
+        let JournalEvent::PlaySubmitted { player_id, play } = &entry.event else {
+            return Ok(());
+        };
+        self.sender.send(PlayData::from_play(state.tournament.id, *player_id, play))
+            .map_err(|_| "El worker del cache no está activo".to_string())
+    }
+
+    fn save_snapshot(&self, _state: &LoadedTournament) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Supabase/Postgres: el esquema relacional sigue todo el ciclo del torneo
+/// (rondas, tableros, jugadas maestras, totales); las jugadas de los jugadores
+/// pasan por la cola asíncrona (y por CacheStorage, aparte)
+pub fn cloud_storage(database: Arc<Database>, queue: Option<Arc<AsyncQueue>>) -> QueuedStorage {
+    QueuedStorage::spawn("cloud", move |op| {
+        let database = database.clone();
+        let queue = queue.clone();
+        async move {
+            match op {
+                // Al volver la nube se sube todo lo que cambió durante el corte
+                StorageOp::Create(state) | StorageOp::Resync(state) => {
+                    database.sync_tournament(&state.tournament).await.map_err(|e| e.to_string())
+                }
+                StorageOp::Event(state, entry) => match entry.event {
+                    JournalEvent::PlayerAdded { player } => {
+                        let player_id = player.id.to_string();
+                        let session = state.player_sessions.iter().find(|s| s.player_id == player_id);
+                        database.enroll_player(
+                            state.tournament.id,
+                            player.id,
+                            player.name.clone(),
+                            session.map(|s| s.ip_address.clone()).unwrap_or_else(|| "Unknown".to_string()),
+                            session.map(|s| s.user_agent.clone()).unwrap_or_else(|| "Unknown".to_string()),
+                            session.and_then(|s| s.hardware_id.clone()).unwrap_or_else(|| "unknown".to_string()),
+                        ).await.map(|_| ()).map_err(|e| e.to_string())
+                    }
+                    JournalEvent::RoundStarted { round, .. } => {
+                        database.sync_round(&state.tournament, round.number).await.map_err(|e| e.to_string())
+                    }
+                    JournalEvent::RackUpdated { round_number, .. }
+                    | JournalEvent::RackRejected { round_number, .. }
+                    | JournalEvent::TimerStarted { round_number, .. }
+                    | JournalEvent::MasterRevealed { round_number }
+                    | JournalEvent::MasterPlaced { round_number, .. } => {
+                        database.sync_round(&state.tournament, round_number).await.map_err(|e| e.to_string())
+                    }
+                    JournalEvent::TournamentFinished => {
+                        database.sync_standings(&state.tournament).await.map_err(|e| e.to_string())
+                    }
+                    // Cambian rondas o totales ya subidos: se resincroniza todo el torneo
+                    JournalEvent::RoundUndone { .. } | JournalEvent::ScoreAdjusted { .. } | JournalEvent::Checkpoint { .. } => {
+                        database.sync_tournament(&state.tournament).await.map_err(|e| e.to_string())
+                    }
+                    JournalEvent::PlaySubmitted { player_id, play } => {
+                        let play_data = PlayData::from_play(state.tournament.id, player_id, &play);
+                        match queue {
+                            Some(queue) => queue.submit_play(play_data).await,
+                            None => Err("Cola asíncrona no disponible".to_string()),
+                        }
+                    }
+                },
+                // Los eventos ya mantienen el esquema relacional al día
+                StorageOp::Snapshot(_) => Ok(()),
+            }
+        }
+    })
+}
+
+/// Reparte las escrituras entre backends según el PersistenceMode activo
+pub struct ModeStorage {
+    config: Arc<PersistenceConfig>,
+    local: Arc<dyn TournamentStorage>,
+    cloud: Option<Arc<dyn TournamentStorage>>,
+    sqlite: Option<Arc<dyn TournamentStorage>>,
+    cache: Option<Arc<dyn TournamentStorage>>,
+}
+
+impl ModeStorage {
+    pub fn new(config: Arc<PersistenceConfig>, local: Arc<dyn TournamentStorage>) -> Self {
+        Self { config, local, cloud: None, sqlite: None, cache: None }
+    }
+
+    pub fn with_cloud(mut self, cloud: Arc<dyn TournamentStorage>) -> Self {
+        self.cloud = Some(cloud);
+        self
+    }
+
+    pub fn with_sqlite(mut self, sqlite: Arc<dyn TournamentStorage>) -> Self {
+        self.sqlite = Some(sqlite);
+        self
+    }
+
+    pub fn with_cache(mut self, cache: Arc<dyn TournamentStorage>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Backends a escribir, en orden de prioridad; el primero es el principal.
+    /// El cache va siempre al final si el modo sube a la nube, aunque esté caída.
+    fn targets(&self) -> Vec<&dyn TournamentStorage> {
+        let local = self.config.should_write_local().then_some(self.local.as_ref());
+        let cloud = self.cloud.as_deref().filter(|_| self.config.should_write_cloud());
+        let sqlite = self.sqlite.as_deref().filter(|_| self.config.should_write_sqlite());
+
+        let ordered = match self.config.get_write_priority() {
+            WritePriority::LocalOnly => vec![local, sqlite],
+            WritePriority::CloudOnly => vec![cloud],
+            WritePriority::LocalThenCloud => vec![local, cloud],
+            WritePriority::CloudThenLocal => vec![cloud, local],
+        };
+        let mut targets: Vec<_> = ordered.into_iter().flatten().collect();
+
+        if targets.is_empty() {
+            // Solo nube sin conexión: no perder el cambio
+            log::warn!("Ningún backend disponible para {:?}; se escribe en local", self.config.current_mode());
+            targets.push(self.local.as_ref());
+        }
+        if let Some(cache) = self.cache.as_deref().filter(|_| self.config.uses_cloud()) {
+            targets.push(cache);
+        }
+        targets
+    }
+
+    /// Aplica la escritura; el error del backend principal se devuelve, el resto solo se registra
+    fn write(&self, op: impl Fn(&dyn TournamentStorage) -> Result<(), String>) -> Result<(), String> {
+        let mut result = Ok(());
+        for (i, backend) in self.targets().into_iter().enumerate() {
+            if let Err(e) = op(backend) {
+                if i == 0 {
+                    result = Err(format!("{}: {}", backend.name(), e));
+                } else {
+                    log::warn!("Escritura secundaria en {} fallida: {}", backend.name(), e);
+                }
+            }
+        }
+        result
+    }
+}
+
+impl TournamentStorage for ModeStorage {
+    fn name(&self) -> &'static str {
+        "mode"
+    }
+
+    fn create_tournament(&self, state: &LoadedTournament) -> Result<(), String> {
+        self.write(|backend| backend.create_tournament(state))
+    }
+
+    fn record_event(&self, state: EventState<'_>, entry: &JournalEntry) -> Result<(), String> {
+        self.write(|backend| backend.record_event(state, entry))
+    }
+
+    fn save_snapshot(&self, state: &LoadedTournament) -> Result<(), String> {
+        self.write(|backend| backend.save_snapshot(state))
+    }
+
+    /// Solo la nube: los backends locales ya tienen el estado al día
+    fn resync(&self, state: &LoadedTournament) -> Result<(), String> {
+        match self.cloud.as_deref().filter(|_| self.config.should_write_cloud()) {
+            Some(cloud) => cloud.resync(state),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::memory::InMemoryStorage;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use crate::models::{Tournament, TournamentStatus};
+    use crate::persistence_mode::PersistenceMode;
+
+    fn state() -> LoadedTournament {
+        LoadedTournament {
+            tournament: Tournament {
+                id: Uuid::new_v4(),
+                name: "Modos".to_string(),
+                created_at: Utc::now(),
+                status: TournamentStatus::Created,
+                rounds: Vec::new(),
+                players: Vec::new(),
+                tiles_remaining: 100,
+                master_plays: Vec::new(),
+                training: false,
+            },
+            player_sessions: Vec::new(),
+            arbiter_accounts: Vec::new(),
+            device_policy: None,
+            device_alerts: Vec::new(),
+            round_devices: Vec::new(),
+            journal_seq: 1,
+        }
+    }
+
+    fn event_state(state: &LoadedTournament) -> EventState<'_> {
+        EventState { tournament: &state.tournament, player_sessions: &state.player_sessions }
+    }
+
+    #[tokio::test]
+    async fn test_mode_storage_honors_mode() {
+        let config = Arc::new(PersistenceConfig::new());
+        let local = Arc::new(InMemoryStorage::default());
+        let cloud = Arc::new(InMemoryStorage::default());
+        let storage = ModeStorage::new(config.clone(), local.clone()).with_cloud(cloud.clone());
+        let state = state();
+        let id = state.tournament.id;
+
+        // Sin nube disponible, el modo dual solo escribe en local
+        storage.save_snapshot(&state).unwrap();
+        assert!(local.snapshot(&id).is_some());
+        assert!(cloud.snapshot(&id).is_none());
+
+        config.set_cloud_status(true).await;
+        let entry = JournalEntry { seq: 1, recorded_at: Utc::now(), event: JournalEvent::TournamentFinished, mac: None };
+        storage.record_event(event_state(&state), &entry).unwrap();
+        assert_eq!(local.journal(&id).len(), 1);
+        assert_eq!(cloud.journal(&id).len(), 1);
+
+        config.set_mode(PersistenceMode::CloudOnly).await;
+        storage.record_event(event_state(&state), &entry).unwrap();
+        assert_eq!(local.journal(&id).len(), 1);
+        assert_eq!(cloud.journal(&id).len(), 2);
+
+        config.set_mode(PersistenceMode::LocalOnly).await;
+        storage.record_event(event_state(&state), &entry).unwrap();
+        assert_eq!(local.journal(&id).len(), 2);
+        assert_eq!(cloud.journal(&id).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cache_written_during_cloud_outage() {
+        let config = Arc::new(PersistenceConfig::new());
+        let local = Arc::new(InMemoryStorage::default());
+        let cloud = Arc::new(InMemoryStorage::default());
+        let cache = Arc::new(InMemoryStorage::default());
+        let storage = ModeStorage::new(config.clone(), local.clone())
+            .with_cloud(cloud.clone())
+            .with_cache(cache.clone());
+        let state = state();
+        let id = state.tournament.id;
+        let entry = JournalEntry { seq: 1, recorded_at: Utc::now(), event: JournalEvent::TournamentFinished, mac: None };
+
+        // Nube caída: el cambio queda en local y en el cache para subirlo al volver
+        storage.record_event(event_state(&state), &entry).unwrap();
+        assert_eq!(local.journal(&id).len(), 1);
+        assert_eq!(cache.journal(&id).len(), 1);
+        assert!(cloud.journal(&id).is_empty());
+
+        // La resincronización solo va a la nube, y solo si está disponible
+        storage.resync(&state).unwrap();
+        assert!(cloud.resynced().is_empty());
+        config.set_cloud_status(true).await;
+        storage.resync(&state).unwrap();
+        assert_eq!(cloud.resynced(), vec![id]);
+        assert!(local.resynced().is_empty());
+        assert_eq!(cache.journal(&id).len(), 1);
+
+        // Un modo sin nube no usa el cache
+        config.set_mode(PersistenceMode::LocalOnly).await;
+        storage.record_event(event_state(&state), &entry).unwrap();
+        assert_eq!(cache.journal(&id).len(), 1);
+    }
+}
@@ -1,16 +1,43 @@
-use std::sync::Arc;
+// Ingesta de jugadas enviadas directamente a Supabase
+//
+// Un trigger en player_plays (migración 0003_play_ingest_notify) asigna un
+// `ingest_seq` creciente (también al reenviar una jugada) y emite NOTIFY en
+// PLAYS_CHANNEL. El servidor escucha
+// el canal y lee las filas posteriores a su cursor, guardado en
+// play_ingest_cursors: el cursor solo avanza después de procesar cada fila,
+// así que tras un corte las jugadas se vuelven a entregar (al menos una vez).
+// Una jugada que este servidor no puede evaluar (torneo o ronda desconocidos) se
+// registra y se salta; solo los fallos transitorios detienen el cursor, y como
+// mucho MAX_TRANSIENT_RETRIES pasadas seguidas.
+// Un sondeo periódico cubre las notificaciones perdidas durante una reconexión.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
-use tokio::time::{sleep, Duration};
+use tokio::time::{interval, Duration, MissedTickBehavior};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use log::{info, warn, error};
 use serde::{Serialize, Deserialize};
+use sqlx::postgres::{PgListener, PgPool};
+use sqlx::Row;
 use crate::tournament_manager::TournamentManager;
 use crate::database::Database;
 
+pub const PLAYS_CHANNEL: &str = "player_plays_inserted";
+
+/// Nombre del consumidor en play_ingest_cursors
+const CURSOR_CONSUMER: &str = "tournament_server";
+const BATCH_SIZE: i64 = 100;
+/// Pasadas seguidas que una jugada con fallo transitorio puede detener el cursor
+const MAX_TRANSIENT_RETRIES: u32 = 10;
+
+type IngestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SupabasePlay {
     pub id: Uuid,
+    pub ingest_seq: i64,
     pub tournament_id: Uuid,
     pub player_id: Uuid,
     pub round_number: i32,
@@ -19,14 +46,148 @@ pub struct SupabasePlay {
     pub position_col: i32,
     pub position_down: bool,
     pub submitted_at: DateTime<Utc>,
-    pub processed: Option<bool>,
+}
+
+/// Por qué no se pudo procesar una jugada de la cola
+#[derive(Debug)]
+pub enum IngestError {
+    /// La jugada no se puede evaluar en este servidor: se registra y el cursor avanza
+    Unprocessable(String),
+    /// Puede resolverse sola (p. ej. diccionario sin cargar): el cursor se detiene
+    Transient(String),
+}
+
+/// Intentos fallidos de la jugada que detiene el cursor
+#[derive(Debug, Default)]
+struct TransientRetries {
+    seq: i64,
+    attempts: u32,
+}
+
+impl TransientRetries {
+    /// Cuenta un fallo transitorio; `false` si la jugada agotó sus pasadas y hay que saltarla
+    fn hold(&mut self, seq: i64) -> bool {
+        if self.seq != seq {
+            *self = Self { seq, attempts: 0 };
+        }
+        self.attempts += 1;
+        self.attempts < MAX_TRANSIENT_RETRIES
+    }
+}
+
+/// Lectura de player_plays por cursor persistido
+pub struct PlayFeed {
+    pool: PgPool,
+    consumer: String,
+    retries: Mutex<TransientRetries>,
+}
+
+impl PlayFeed {
+    pub fn new(pool: PgPool, consumer: &str) -> Self {
+        Self { pool, consumer: consumer.to_string(), retries: Mutex::new(TransientRetries::default()) }
+    }
+
+    /// Último `ingest_seq` procesado; un consumidor nuevo empieza en la jugada más reciente
+    pub async fn cursor(&self) -> IngestResult<i64> {
+        let row = sqlx::query("SELECT last_seq FROM play_ingest_cursors WHERE consumer = $1")
+            .persistent(false)
+            .bind(&self.consumer)
+            .fetch_optional(&self.pool)
+            .await?;
+        if let Some(row) = row {
+            return Ok(row.get("last_seq"));
+        }
+
+        let latest: i64 = sqlx::query("SELECT COALESCE(MAX(ingest_seq), 0) AS latest FROM player_plays")
+            .persistent(false)
+            .fetch_one(&self.pool)
+            .await?
+            .get("latest");
+        self.advance(latest).await?;
+        Ok(latest)
+    }
+
+    async fn advance(&self, seq: i64) -> IngestResult<()> {
+        sqlx::query(
+            "INSERT INTO play_ingest_cursors (consumer, last_seq, updated_at) VALUES ($1, $2, NOW()) \
+             ON CONFLICT (consumer) DO UPDATE SET last_seq = GREATEST(play_ingest_cursors.last_seq, EXCLUDED.last_seq), \
+                 updated_at = NOW()",
+        )
+        .persistent(false)
+        .bind(&self.consumer)
+        .bind(seq)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn fetch_after(&self, seq: i64) -> IngestResult<Vec<SupabasePlay>> {
+        let rows = sqlx::query(
+            "SELECT id, ingest_seq, tournament_id, player_id, round_number, word, \
+                    position_row, position_col, position_down, submitted_at \
+             FROM player_plays WHERE ingest_seq > $1 ORDER BY ingest_seq ASC LIMIT $2",
+        )
+        .persistent(false)
+        .bind(seq)
+        .bind(BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| SupabasePlay {
+            id: row.get("id"),
+            ingest_seq: row.get("ingest_seq"),
+            tournament_id: row.get("tournament_id"),
+            player_id: row.get("player_id"),
+            round_number: row.get("round_number"),
+            word: row.get::<Option<String>, _>("word").unwrap_or_default(),
+            position_row: row.get::<Option<i32>, _>("position_row").unwrap_or_default(),
+            position_col: row.get::<Option<i32>, _>("position_col").unwrap_or_default(),
+            position_down: row.get::<Option<bool>, _>("position_down").unwrap_or_default(),
+            submitted_at: row.get("submitted_at"),
+        }).collect())
+    }
+
+    /// Entrega las jugadas pendientes en orden. Una jugada que no se puede evaluar se
+    /// salta; si `handle` falla de forma transitoria, el cursor queda en la última
+    /// jugada procesada y la fallida se reintenta en la próxima pasada, hasta
+    /// MAX_TRANSIENT_RETRIES veces. Devuelve cuántas jugadas se procesaron.
+    pub async fn drain<F, Fut>(&self, mut handle: F) -> IngestResult<usize>
+    where
+        F: FnMut(SupabasePlay) -> Fut,
+        Fut: Future<Output = Result<(), IngestError>>,
+    {
+        let mut cursor = self.cursor().await?;
+        let mut processed = 0;
+        loop {
+            let plays = self.fetch_after(cursor).await?;
+            if plays.is_empty() {
+                return Ok(processed);
+            }
+            for play in plays {
+                let (id, seq) = (play.id, play.ingest_seq);
+                match handle(play).await {
+                    Ok(()) => processed += 1,
+                    Err(IngestError::Unprocessable(e)) => {
+                        warn!("⚠️ Jugada {} (seq {}) descartada: {}", id, seq, e);
+                    }
+                    Err(IngestError::Transient(e)) => {
+                        if self.retries.lock().unwrap().hold(seq) {
+                            return Err(format!("Jugada {} (seq {}): {}", id, seq, e).into());
+                        }
+                        error!("❌ Jugada {} (seq {}) descartada tras {} intentos: {}", id, seq, MAX_TRANSIENT_RETRIES, e);
+                    }
+                }
+                self.advance(seq).await?;
+                cursor = seq;
+            }
+        }
+    }
 }
 
 pub struct SupabasePoller {
     database: Arc<Database>,
     manager: Arc<RwLock<TournamentManager>>,
     poll_interval_ms: u64,
-    last_poll_time: Arc<RwLock<DateTime<Utc>>>,
 }
 
 impl SupabasePoller {
@@ -39,125 +200,106 @@ impl SupabasePoller {
             database,
             manager,
             poll_interval_ms,
-            last_poll_time: Arc::new(RwLock::new(Utc::now())),
         }
     }
-    
+
     pub async fn start_polling(self: Arc<Self>) {
-        info!("🔄 Iniciando polling de Supabase cada {}ms", self.poll_interval_ms);
-        
+        let feed = PlayFeed::new(self.database.pool().clone(), CURSOR_CONSUMER);
+
+        let mut listener = match self.connect_listener().await {
+            Ok(listener) => {
+                info!("🔔 Escuchando {} en Supabase (sondeo de respaldo cada {}ms)", PLAYS_CHANNEL, self.poll_interval_ms);
+                Some(listener)
+            }
+            Err(e) => {
+                warn!("⚠️ LISTEN no disponible ({}); solo sondeo cada {}ms", e, self.poll_interval_ms);
+                None
+            }
+        };
+
+        let mut fallback = interval(Duration::from_millis(self.poll_interval_ms));
+        fallback.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
         loop {
-            sleep(Duration::from_millis(self.poll_interval_ms)).await;
-            
-            match self.poll_and_process().await {
-                Ok(count) => {
-                    if count > 0 {
-                        info!("✅ Procesadas {} jugadas desde Supabase", count);
-                    }
-                }
-                Err(e) => {
-                    error!("❌ Error en polling: {}", e);
+            match listener.as_mut() {
+                Some(l) => tokio::select! {
+                    notification = l.try_recv() => match notification {
+                        Ok(Some(_)) => {}
+                        // Conexión perdida: el listener reconecta y se relee desde el cursor
+                        Ok(None) => warn!("⚠️ Conexión LISTEN reiniciada; releyendo desde el cursor"),
+                        Err(e) => {
+                            error!("❌ Error en LISTEN: {}", e);
+                            fallback.tick().await;
+                        }
+                    },
+                    _ = fallback.tick() => {}
+                },
+                None => {
+                    fallback.tick().await;
                 }
             }
-        }
-    }
-    
-    async fn poll_and_process(&self) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
-        let last_time = *self.last_poll_time.read().await;
-        
-        // Consultar jugadas nuevas desde Supabase
-        let new_plays = self.fetch_new_plays(last_time).await?;
-        
-        if new_plays.is_empty() {
-            return Ok(0);
-        }
-        
-        info!("📥 Encontradas {} jugadas nuevas para procesar", new_plays.len());
-        
-        let mut processed_count = 0;
-        let mut manager = self.manager.write().await;
-        
-        for play in new_plays {
-            // Procesar la jugada en el TournamentManager
-            match self.process_single_play(&mut manager, &play).await {
-                Ok(_) => {
-                    processed_count += 1;
-                    
-                    // Marcar como procesada en Supabase
-                    if let Err(e) = self.mark_as_processed(play.id).await {
-                        warn!("⚠️ No se pudo marcar jugada {} como procesada: {}", play.id, e);
-                    }
-                }
-                Err(e) => {
-                    error!("❌ Error procesando jugada {}: {}", play.id, e);
-                }
+
+            // El manager se bloquea solo mientras se procesa cada jugada
+            let drained = feed.drain(|play| {
+                let manager = self.manager.clone();
+                async move { Self::process_single_play(&mut *manager.write().await, &play) }
+            }).await;
+            match drained {
+                Ok(count) if count > 0 => info!("✅ Procesadas {} jugadas desde Supabase", count),
+                Ok(_) => {}
+                Err(e) => error!("❌ Error procesando jugadas de Supabase: {}", e),
             }
         }
-        
-        // Actualizar última vez de polling
-        *self.last_poll_time.write().await = Utc::now();
-        
-        Ok(processed_count)
     }
-    
-    async fn fetch_new_plays(&self, since: DateTime<Utc>) -> Result<Vec<SupabasePlay>, Box<dyn std::error::Error + Send + Sync>> {
-        use sqlx::{PgPool, Row};
-        use std::env;
-        
-        // Conectar directamente para la consulta
-        let database_url = env::var("DATABASE_URL")?;
-        let pool = PgPool::connect(&database_url).await?;
-        
-        let query = format!(
-            "SELECT id, tournament_id, player_id, round_number, word, 
-                    position_row, position_col, position_down, submitted_at, processed
-             FROM player_plays 
-             WHERE submitted_at > '{}'
-               AND (processed IS NULL OR processed = false)
-             ORDER BY submitted_at ASC
-             LIMIT 100",
-            since.format("%Y-%m-%d %H:%M:%S%.3f%:z")
-        );
-        
-        let rows = sqlx::query(&query)
-            .fetch_all(&pool)
-            .await?;
-        
-        let mut plays = Vec::new();
-        for row in rows {
-            plays.push(SupabasePlay {
-                id: row.get("id"),
-                tournament_id: row.get("tournament_id"),
-                player_id: row.get("player_id"),
-                round_number: row.get("round_number"),
-                word: row.get("word"),
-                position_row: row.get("position_row"),
-                position_col: row.get("position_col"),
-                position_down: row.get("position_down"),
-                submitted_at: row.get("submitted_at"),
-                processed: row.get("processed"),
-            });
-        }
-        
-        Ok(plays)
+
+    /// LISTEN requiere una conexión de sesión; DATABASE_LISTEN_URL permite evitar el pooler
+    async fn connect_listener(&self) -> IngestResult<PgListener> {
+        let mut listener = match std::env::var("DATABASE_LISTEN_URL") {
+            Ok(url) => PgListener::connect(&url).await?,
+            Err(_) => PgListener::connect_with(self.database.pool()).await?,
+        };
+        listener.listen(PLAYS_CHANNEL).await?;
+        Ok(listener)
     }
-    
-    async fn process_single_play(
-        &self,
+
+    fn process_single_play(
         manager: &mut TournamentManager,
         play: &SupabasePlay,
-    ) -> Result<(), String> {
-        info!("🎮 Procesando jugada: Torneo {} - Jugador {} - Palabra: {}", 
-              play.tournament_id, play.player_id, play.word);
-        
-        // Convertir a formato del TournamentManager
+    ) -> Result<(), IngestError> {
         let position = crate::models::Position {
             row: play.position_row as u8,
             col: play.position_col as u8,
             down: play.position_down,
         };
-        
-        // Procesar la jugada
+
+        // Los torneos en curso se restauran antes de arrancar el sondeo: un torneo o una
+        // ronda desconocidos no van a aparecer y la jugada se salta. Sin diccionario
+        // la jugada aún no se puede evaluar: el cursor se detiene hasta cargarlo
+        let tournament = manager.get_tournament(&play.tournament_id)
+            .ok_or_else(|| IngestError::Unprocessable(format!("Tournament {} not loaded", play.tournament_id)))?;
+        if !tournament.rounds.iter().any(|r| r.number == play.round_number as u32) {
+            return Err(IngestError::Unprocessable(format!(
+                "Round {} not found in tournament {}", play.round_number, play.tournament_id
+            )));
+        }
+        if manager.engine.is_none() {
+            return Err(IngestError::Transient("Engine not initialized".to_string()));
+        }
+
+        // Las jugadas que este servidor sincronizó vuelven por el mismo canal
+        let already_recorded = tournament.players.iter().find(|p| p.id == play.player_id)
+            .and_then(|p| p.plays.iter().find(|pp| pp.round_number == play.round_number as u32))
+            .is_some_and(|pp| pp.word == play.word
+                && pp.position.row == position.row
+                && pp.position.col == position.col
+                && pp.position.down == position.down);
+        if already_recorded {
+            return Ok(());
+        }
+
+        info!("🎮 Procesando jugada: Torneo {} - Jugador {} - Palabra: {}",
+              play.tournament_id, play.player_id, play.word);
+
         match manager.submit_player_play(
             &play.tournament_id,
             &play.player_id,
@@ -165,53 +307,153 @@ impl SupabasePoller {
             play.word.clone(),
             position,
         ) {
-            Ok(response) => {
-                info!("✅ Jugada procesada: {}", response.message);
-                Ok(())
-            }
-            Err(e) => {
-                warn!("⚠️ Jugada inválida o error: {}", e);
-                // Aún así la marcamos como procesada para no reintentarla
-                Ok(())
-            }
+            Ok(response) => info!("✅ Jugada procesada: {}", response.message),
+            // Rechazo definitivo (p. ej. jugador inexistente): cuenta como procesada
+            Err(e) => warn!("⚠️ Jugada rechazada: {}", e),
         }
-    }
-    
-    async fn mark_as_processed(&self, play_id: Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        use sqlx::{PgPool};
-        use std::env;
-        
-        let database_url = env::var("DATABASE_URL")?;
-        let pool = PgPool::connect(&database_url).await?;
-        
-        let query = format!(
-            "UPDATE player_plays 
-             SET processed = true 
-             WHERE id = '{}'",
-            play_id
-        );
-        
-        sqlx::query(&query)
-            .execute(&pool)
-            .await?;
-        
         Ok(())
     }
-    
-    pub async fn get_pending_plays_count(&self) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
-        use sqlx::{PgPool, Row};
-        use std::env;
-        
-        let database_url = env::var("DATABASE_URL")?;
-        let pool = PgPool::connect(&database_url).await?;
-        
-        let query = "SELECT COUNT(*) as count FROM player_plays WHERE submitted_at > NOW() - INTERVAL '1 hour'";
-        
-        let row = sqlx::query(query)
-            .fetch_one(&pool)
-            .await?;
-        
-        let count: i64 = row.get("count");
-        Ok(count as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::testing::temp_schema_pool;
+
+    async fn insert_play(pool: &PgPool, tournament_id: Uuid, player_id: Uuid, round_number: i32, word: &str) {
+        sqlx::query(
+            "INSERT INTO player_plays (tournament_id, player_id, round_number, word, position_row, position_col, position_down) \
+             VALUES ($1, $2, $3, $4, 7, 7, false) \
+             ON CONFLICT (tournament_id, player_id, round_number) DO UPDATE SET word = EXCLUDED.word",
+        )
+        .bind(tournament_id)
+        .bind(player_id)
+        .bind(round_number)
+        .bind(word)
+        .execute(pool)
+        .await
+        .unwrap();
     }
-}
\ No newline at end of file
+
+    fn supabase_play(tournament_id: Uuid, player_id: Uuid) -> SupabasePlay {
+        SupabasePlay {
+            id: Uuid::new_v4(),
+            ingest_seq: 1,
+            tournament_id,
+            player_id,
+            round_number: 1,
+            word: "CASA".to_string(),
+            position_row: 7,
+            position_col: 7,
+            position_down: false,
+            submitted_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_play_not_processed_until_manager_ready() {
+        use crate::models::{Player, Tournament, TournamentStatus};
+        use crate::models::test_support::active_round;
+
+        let mut manager = TournamentManager::new(std::net::IpAddr::from([127, 0, 0, 1]));
+        let tournament_id = Uuid::new_v4();
+        let player_id = Uuid::new_v4();
+        let play = supabase_play(tournament_id, player_id);
+
+        // Torneo que este servidor no tiene: se salta
+        assert!(matches!(SupabasePoller::process_single_play(&mut manager, &play), Err(IngestError::Unprocessable(_))));
+
+        manager.tournaments.insert(tournament_id, Tournament {
+            id: tournament_id,
+            name: "Ingesta".to_string(),
+            created_at: Utc::now(),
+            status: TournamentStatus::InProgress,
+            rounds: vec![active_round(1, "CASADOS")],
+            players: vec![Player {
+                id: player_id,
+                name: "Ana".to_string(),
+                total_score: 0,
+                plays: Vec::new(),
+                category: None,
+                club: None,
+                federation_id: None,
+            }],
+            tiles_remaining: 93,
+            master_plays: Vec::new(),
+            training: false,
+        });
+
+        // Diccionario sin cargar: error para que el cursor no avance y la jugada siga intacta
+        let error = SupabasePoller::process_single_play(&mut manager, &play).unwrap_err();
+        assert!(matches!(error, IngestError::Transient(ref e) if e.contains("Engine not initialized")));
+        assert!(manager.get_tournament(&tournament_id).unwrap().players[0].plays.is_empty());
+
+        // Ronda inexistente
+        let mut later = supabase_play(tournament_id, player_id);
+        later.round_number = 2;
+        assert!(matches!(SupabasePoller::process_single_play(&mut manager, &later), Err(IngestError::Unprocessable(_))));
+    }
+
+    #[test]
+    fn test_transient_retries_capped() {
+        let mut retries = TransientRetries::default();
+        for _ in 1..MAX_TRANSIENT_RETRIES {
+            assert!(retries.hold(7));
+        }
+        assert!(!retries.hold(7));
+        // Otra jugada empieza de cero
+        assert!(retries.hold(8));
+    }
+
+    #[tokio::test]
+    #[ignore = "requiere TEST_DATABASE_URL apuntando a un Postgres local"]
+    async fn test_feed_delivers_at_least_once() {
+        let (pool, schema) = temp_schema_pool("ingest_test").await;
+        let feed = PlayFeed::new(pool.clone(), "test");
+
+        let tournament_id = Uuid::new_v4();
+        let player_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO tournaments (id, name) VALUES ($1, 'Ingesta')").bind(tournament_id).execute(&pool).await.unwrap();
+        sqlx::query("INSERT INTO players (id, tournament_id, name) VALUES ($1, $2, 'Ana')").bind(player_id).bind(tournament_id).execute(&pool).await.unwrap();
+
+        // El cursor de un consumidor nuevo ignora el historial
+        insert_play(&pool, tournament_id, player_id, 1, "VIEJA").await;
+        assert_eq!(feed.drain(|_| async { Ok::<_, IngestError>(()) }).await.unwrap(), 0);
+
+        let mut listener = PgListener::connect_with(&pool).await.unwrap();
+        listener.listen(PLAYS_CHANNEL).await.unwrap();
+        insert_play(&pool, tournament_id, player_id, 2, "CASA").await;
+        assert!(listener.recv().await.is_ok());
+
+        // Un fallo transitorio deja el cursor antes de la jugada y se vuelve a entregar
+        assert!(feed.drain(|_| async { Err(IngestError::Transient("caído".to_string())) }).await.is_err());
+        let mut seen = Vec::new();
+        assert_eq!(feed.drain(|p| { seen.push(p.word); async { Ok::<_, IngestError>(()) } }).await.unwrap(), 1);
+        assert_eq!(seen, vec!["CASA"]);
+
+        // Reenviar la jugada la pone de nuevo al final de la secuencia
+        insert_play(&pool, tournament_id, player_id, 2, "CASAS").await;
+        let mut seen = Vec::new();
+        assert_eq!(feed.drain(|p| { seen.push(p.word); async { Ok::<_, IngestError>(()) } }).await.unwrap(), 1);
+        assert_eq!(seen, vec!["CASAS"]);
+
+        // Una jugada que no se puede evaluar no bloquea a la siguiente
+        insert_play(&pool, tournament_id, player_id, 3, "OTRO").await;
+        insert_play(&pool, tournament_id, player_id, 4, "VALIDA").await;
+        let mut seen = Vec::new();
+        let processed = feed.drain(|p| {
+            seen.push(p.word.clone());
+            async move {
+                match p.round_number {
+                    3 => Err(IngestError::Unprocessable("ronda desconocida".to_string())),
+                    _ => Ok(()),
+                }
+            }
+        }).await.unwrap();
+        assert_eq!(processed, 1);
+        assert_eq!(seen, vec!["OTRO", "VALIDA"]);
+        assert_eq!(feed.drain(|_| async { Ok::<_, IngestError>(()) }).await.unwrap(), 0);
+
+        sqlx::raw_sql(&format!("DROP SCHEMA {} CASCADE", schema)).execute(&pool).await.unwrap();
+    }
+}
@@ -1,16 +1,56 @@
 use crate::models::*;
 use crate::wolges_engine::WolgesEngine;
+use crate::persistence::{LoadedTournament, PlayerSession};
+use crate::device_guard::{self, DeviceAlert, DeviceAlertKind, DevicePolicy, RoundDevices};
+use crate::arbiter::{ArbiterAccount, ArbiterAction, ArbiterLoginResponse, ArbiterRole, ArbiterSession};
+use crate::journal::{self, JournalEntry, JournalEvent};
+use crate::storage::{EventState, JsonStorage, TournamentStorage};
+use crate::persistence_mode::PersistenceMode;
+use crate::events::{self, EventHub, LeaderboardEntry, RoundView, StateSnapshot, TimerPhase, TournamentEvent};
+use crate::record::GameTemplate;
+use crate::gcg;
+use crate::player_import::{self, PlayerImportError};
+use crate::round_stats::{self, RoundStats};
+use crate::player_analytics::{self, PlayerAnalytics};
+use crate::coaching::{CoachingCache, CoachingJob};
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 use chrono::Utc;
 use wolges::{alphabet, bag};
 use std::fs::{File, OpenOptions};
 use std::io::{Write, BufWriter};
 
+/// Todas las puntuaciones posibles de una ronda, para el puesto de cada jugada.
+/// Se generan al empezar la ronda o cambiar el atril y sirven mientras no
+/// cambien atril ni tablero.
+struct RoundPlayScores {
+    round_number: u32,
+    rack: String,
+    board: Vec<String>,
+    scores: Vec<i32>,
+}
+
 pub struct TournamentManager {
     pub tournaments: HashMap<Uuid, Tournament>,
     pub engine: Option<WolgesEngine>,
+    coaching_engine: Option<Arc<Mutex<WolgesEngine>>>,  // Motor propio del análisis, que corre fuera del lock del manager
+    coaching_cache: CoachingCache,  // Master y mejor jugada por equity de cada ronda analizada
     bags: HashMap<Uuid, bag::Bag>,  // Bolsa por torneo
+    player_sessions: HashMap<Uuid, Vec<PlayerSession>>,  // Sesiones por torneo
+    arbiter_accounts: HashMap<Uuid, Vec<ArbiterAccount>>,  // Cuentas de árbitro por torneo
+    arbiter_sessions: HashMap<String, ArbiterSession>,  // Sesiones activas por hash de token
+    server_credential: Option<String>,  // Hash de la credencial del servidor (acciones globales)
+    open_mode: bool,  // Torneos sin cuentas de árbitro quedan abiertos
+    device_policies: HashMap<Uuid, DevicePolicy>,
+    device_alerts: HashMap<Uuid, Vec<DeviceAlert>>,
+    round_devices: HashMap<Uuid, HashMap<(Uuid, u32), Vec<String>>>,  // Dispositivos por (jugador, ronda)
+    journal_seqs: HashMap<Uuid, u64>,  // Última entrada del diario por torneo
+    templates: HashMap<Uuid, GameTemplate>,  // Registro importado que reparte los atriles
+    play_scores: HashMap<Uuid, RoundPlayScores>,  // Puntuaciones posibles de la ronda en curso
+    storage: Arc<dyn TournamentStorage>,  // Destino de snapshots y diario según el modo de persistencia
+    pub events: EventHub,
+    pub recovery_report: Option<crate::recovery::RecoveryReport>,  // Resultado de la recuperación al iniciar
     server_ip: std::net::IpAddr,
 }
 
@@ -19,13 +59,48 @@ impl TournamentManager {
         Self {
             tournaments: HashMap::new(),
             engine: None,
+            coaching_engine: None,
+            coaching_cache: CoachingCache::default(),
             bags: HashMap::new(),
+            player_sessions: HashMap::new(),
+            arbiter_accounts: HashMap::new(),
+            arbiter_sessions: HashMap::new(),
+            server_credential: None,
+            open_mode: false,
+            device_policies: HashMap::new(),
+            device_alerts: HashMap::new(),
+            round_devices: HashMap::new(),
+            journal_seqs: HashMap::new(),
+            templates: HashMap::new(),
+            play_scores: HashMap::new(),
+            storage: Arc::new(JsonStorage),
+            events: EventHub::default(),
+            recovery_report: None,
             server_ip,
         }
     }
     
+    pub fn set_storage(&mut self, storage: Arc<dyn TournamentStorage>) {
+        self.storage = storage;
+    }
+    
+    /// Credencial del servidor para acciones globales y modo abierto para
+    /// torneos sin cuentas de árbitro
+    pub fn configure_access(&mut self, server_token: Option<&str>, open_mode: bool) {
+        self.server_credential = server_token
+            .filter(|t| !t.is_empty())
+            .map(crate::auth::hash_token);
+        self.open_mode = open_mode;
+    }
+    
+    pub fn open_mode(&self) -> bool {
+        self.open_mode
+    }
+    
     pub fn load_dictionary(&mut self, kwg_path: &str, klv_path: Option<&str>) -> Result<(), String> {
         self.engine = Some(WolgesEngine::new(kwg_path, klv_path)?);
+        self.coaching_engine = Some(Arc::new(Mutex::new(WolgesEngine::new(kwg_path, klv_path)?)));
+        self.coaching_cache.lock().unwrap().clear();
         Ok(())
     }
     
@@ -47,7 +122,12 @@ impl TournamentManager {
         }
     }
     
-    pub fn create_tournament(&mut self, name: String, player_names: Vec<String>) -> Result<Tournament, String> {
+    pub fn create_tournament(&mut self, name: String, player_names: Vec<String>, arbiter_passphrase: Option<String>) -> Result<(Tournament, Vec<PlayerToken>), String> {
+        let arbiter_passphrase = arbiter_passphrase.filter(|p| !p.is_empty());
+        if arbiter_passphrase.is_none() && !self.open_mode {
+            return Err("Se requiere la frase de acceso del árbitro principal".to_string());
+        }
+        
         let engine = self.engine.as_ref()
             .ok_or("Dictionary not loaded. Load a KWG file first.")?;
         
@@ -58,6 +138,9 @@ impl TournamentManager {
                 name,
                 total_score: 0,
                 plays: Vec::new(),
+                category: None,
+                club: None,
+                federation_id: None,
             }
         }).collect();
         
@@ -109,18 +192,56 @@ impl TournamentManager {
             players,
             tiles_remaining,
             master_plays: Vec::new(),
+            training: false,
         };
         
         self.tournaments.insert(id, tournament.clone());
         
-        // Create tournament directory for persistence
-        use crate::persistence::PersistenceManager;
-        if let Err(e) = PersistenceManager::create_tournament_directory(&id.to_string(), &tournament.name) {
-            eprintln!("Failed to create tournament directory: {}", e);
+        // Emitir tokens para los jugadores pre-inscritos
+        let mut player_tokens = Vec::new();
+        let mut sessions = Vec::new();
+        for player in &tournament.players {
+            let token = crate::auth::generate_token();
+            let now = Utc::now();
+            sessions.push(PlayerSession {
+                player_id: player.id.to_string(),
+                name: player.name.clone(),
+                ip_address: "Unknown".to_string(),
+                user_agent: "Unknown".to_string(),
+                hardware_id: None,
+                enrolled_at: now,
+                last_seen: now,
+                token_hash: Some(crate::auth::hash_token(&token)),
+                token_issued_at: Some(now),
+            });
+            player_tokens.push(PlayerToken {
+                player_id: player.id,
+                name: player.name.clone(),
+                token,
+            });
+        }
+        self.player_sessions.insert(id, sessions);
+        
+        // La frase de acceso inicial crea la cuenta del árbitro principal
+        match arbiter_passphrase {
+            Some(passphrase) => {
+                self.arbiter_accounts.insert(id, vec![
+                    ArbiterAccount::new("Principal".to_string(), ArbiterRole::HeadArbiter, &passphrase)
+                ]);
+            }
+            None => log::warn!("Tournament {} created without arbiter passphrase (open mode) - control endpoints are open", id),
+        }
+        
+        // Preparar el almacenamiento del torneo (directorio, filas base)
+        if let Some(state) = self.tournament_state(&id) {
+            if let Err(e) = self.storage.create_tournament(&state) {
+                eprintln!("Failed to create tournament storage: {}", e);
+            }
         }
+        self.record(&id, JournalEvent::Checkpoint { tournament: tournament.clone() });
         
         // Save initial state (JSON persistence)
-        if let Err(e) = PersistenceManager::save_tournament(&tournament, self, vec![]) {
+        if let Err(e) = self.persist(&id) {
             eprintln!("Failed to save tournament to JSON: {}", e);
         }
         
@@ -129,38 +250,509 @@ impl TournamentManager {
             eprintln!("Failed to log tournament start: {}", e);
         }
         
-        Ok(tournament)
+        Ok((tournament, player_tokens))
     }
     
     pub fn get_tournament(&self, id: &Uuid) -> Option<&Tournament> {
         self.tournaments.get(id)
     }
     
-    pub fn add_player(&mut self, tournament_id: &Uuid, name: &str, player_id: Uuid) -> Result<Tournament, String> {
-        let tournament = self.tournaments.get_mut(tournament_id)
+    pub fn add_player(&mut self, tournament_id: &Uuid, name: &str, player_id: Uuid, mut session: PlayerSession) -> Result<(Tournament, String), String> {
+        let status = self.tournaments.get(tournament_id)
+            .map(|t| t.status.clone())
             .ok_or("Tournament not found")?;
             
-        if tournament.status != TournamentStatus::Created {
+        if status != TournamentStatus::Created {
             return Err("Cannot add players after tournament has started".to_string());
         }
         
+        // Detectar si este dispositivo ya inscribió a otro jugador
+        let policy = self.device_policy_for(tournament_id);
+        let device = device_guard::device_key(session.hardware_id.as_deref(), &session.ip_address, &session.user_agent);
+        if let (true, Some(device)) = (policy.detect_shared_device, device) {
+            let mut player_ids: Vec<Uuid> = self.sessions_for(tournament_id).iter()
+                .filter(|s| device_guard::device_key(s.hardware_id.as_deref(), &s.ip_address, &s.user_agent).as_ref() == Some(&device))
+                .filter_map(|s| s.player_id.parse().ok())
+                .collect();
+            
+            if !player_ids.is_empty() {
+                player_ids.push(player_id);
+                let message = format!("El dispositivo que inscribe a '{}' ya inscribió a {} jugador(es)", name, player_ids.len() - 1);
+                self.raise_device_alert(tournament_id, DeviceAlert::new(
+                    DeviceAlertKind::SharedEnrollment, player_ids, None, &device, message, policy.block
+                ));
+                if policy.block {
+                    return Err("Este dispositivo ya inscribió a otro jugador en el torneo".to_string());
+                }
+            }
+        }
+        
+        let tournament = self.tournaments.get_mut(tournament_id)
+            .ok_or("Tournament not found")?;
+        
         let player = Player {
             id: player_id,
             name: name.to_string(),
             total_score: 0,
             plays: Vec::new(),
+            category: None,
+            club: None,
+            federation_id: None,
         };
         
-        tournament.players.push(player);
+        tournament.players.push(player.clone());
         let tournament_clone = tournament.clone();
         
+        // Emitir token de acceso; solo se guarda su hash
+        let token = crate::auth::generate_token();
+        session.token_hash = Some(crate::auth::hash_token(&token));
+        session.token_issued_at = Some(Utc::now());
+        self.player_sessions.entry(*tournament_id).or_default().push(session);
+        self.record(tournament_id, JournalEvent::PlayerAdded { player });
+        
         // Save updated tournament
-        use crate::persistence::PersistenceManager;
-        if let Err(e) = PersistenceManager::save_tournament(&tournament_clone, self, vec![]) {
+        if let Err(e) = self.persist(tournament_id) {
             eprintln!("Failed to save tournament after adding player: {}", e);
         }
         
-        Ok(tournament_clone)
+        Ok((tournament_clone, token))
+    }
+    
+    /// Inscribe de una vez a todos los jugadores de un CSV. Si alguna fila no
+    /// es válida no se inscribe a nadie; el alta queda en un único checkpoint
+    /// (una sola transacción en la base relacional).
+    pub fn import_players(&mut self, tournament_id: &Uuid, csv: &str) -> Result<Vec<ImportedPlayer>, PlayerImportError> {
+        let tournament = self.tournaments.get(tournament_id)
+            .ok_or_else(|| PlayerImportError::from("Tournament not found"))?;
+        if tournament.status != TournamentStatus::Created {
+            return Err("Cannot add players after tournament has started".into());
+        }
+
+        let rows = player_import::parse(csv, &tournament.players)
+            .map_err(|rows| PlayerImportError {
+                message: format!("{} fila(s) con errores; no se inscribió a ningún jugador", rows.len()),
+                rows,
+            })?;
+
+        let player_url = self.get_tournament_url(tournament_id);
+        let now = Utc::now();
+        let mut players = Vec::with_capacity(rows.len());
+        let mut imported = Vec::with_capacity(rows.len());
+        let mut sessions = Vec::with_capacity(rows.len());
+        for row in rows {
+            let player = Player {
+                id: Uuid::new_v4(),
+                name: row.name,
+                total_score: 0,
+                plays: Vec::new(),
+                category: row.category,
+                club: row.club,
+                federation_id: row.federation_id,
+            };
+            let token = crate::auth::generate_token();
+            sessions.push(PlayerSession {
+                player_id: player.id.to_string(),
+                name: player.name.clone(),
+                ip_address: "Unknown".to_string(),
+                user_agent: "Unknown".to_string(),
+                hardware_id: None,
+                enrolled_at: now,
+                last_seen: now,
+                token_hash: Some(crate::auth::hash_token(&token)),
+                token_issued_at: Some(now),
+            });
+            imported.push(ImportedPlayer {
+                player_id: player.id,
+                name: player.name.clone(),
+                club: player.club.clone(),
+                category: player.category.clone(),
+                federation_id: player.federation_id.clone(),
+                token,
+                player_url: player_url.clone(),
+            });
+            players.push(player);
+        }
+
+        let tournament = self.tournaments.get_mut(tournament_id)
+            .ok_or_else(|| PlayerImportError::from("Tournament not found"))?;
+        tournament.players.extend(players);
+        let tournament = tournament.clone();
+        self.player_sessions.entry(*tournament_id).or_default().extend(sessions);
+        self.record(tournament_id, JournalEvent::Checkpoint { tournament });
+
+        if let Err(e) = self.persist(tournament_id) {
+            eprintln!("Failed to save tournament after importing players: {}", e);
+        }
+
+        Ok(imported)
+    }
+
+    pub fn sessions_for(&self, tournament_id: &Uuid) -> Vec<PlayerSession> {
+        self.player_sessions.get(tournament_id).cloned().unwrap_or_default()
+    }
+    
+    /// Verifica el token de un jugador contra el hash de su sesión
+    pub fn verify_player_token(&self, tournament_id: &Uuid, player_id: &Uuid, token: Option<&str>) -> Result<(), String> {
+        let token = token.ok_or("Token de jugador requerido")?;
+        
+        let player_id_str = player_id.to_string();
+        let session = self.player_sessions.get(tournament_id)
+            .and_then(|sessions| sessions.iter().find(|s| s.player_id == player_id_str))
+            .ok_or("Jugador sin sesión activa")?;
+        
+        let token_hash = match (&session.token_hash, session.token_issued_at) {
+            (Some(hash), _) => hash,
+            (None, Some(_)) => return Err("El token del jugador fue revocado".to_string()),
+            (None, None) => return Err("El jugador aún no tiene token; el árbitro debe emitirlo".to_string()),
+        };
+        
+        if crate::auth::verify_token(token, token_hash) {
+            Ok(())
+        } else {
+            Err("Token de jugador inválido".to_string())
+        }
+    }
+    
+    /// Emite un token nuevo para el jugador, invalidando el anterior
+    pub fn reissue_player_token(&mut self, tournament_id: &Uuid, player_id: &Uuid) -> Result<PlayerToken, String> {
+        let tournament = self.tournaments.get(tournament_id)
+            .ok_or("Tournament not found")?;
+        
+        let player = tournament.players.iter()
+            .find(|p| &p.id == player_id)
+            .ok_or("Player not found")?;
+        let player_name = player.name.clone();
+        
+        let token = crate::auth::generate_token();
+        let now = Utc::now();
+        let player_id_str = player_id.to_string();
+        let sessions = self.player_sessions.entry(*tournament_id).or_default();
+        
+        match sessions.iter_mut().find(|s| s.player_id == player_id_str) {
+            Some(session) => {
+                session.token_hash = Some(crate::auth::hash_token(&token));
+                session.token_issued_at = Some(now);
+            }
+            None => sessions.push(PlayerSession {
+                player_id: player_id_str,
+                name: player_name.clone(),
+                ip_address: "Unknown".to_string(),
+                user_agent: "Unknown".to_string(),
+                hardware_id: None,
+                enrolled_at: now,
+                last_seen: now,
+                token_hash: Some(crate::auth::hash_token(&token)),
+                token_issued_at: Some(now),
+            }),
+        }
+        
+        self.save_sessions(tournament_id, "reissuing player token");
+        
+        Ok(PlayerToken {
+            player_id: *player_id,
+            name: player_name,
+            token,
+        })
+    }
+    
+    /// Emite token a los jugadores que nunca tuvieron uno (sesiones de
+    /// snapshots anteriores a 1.1.0). Los tokens revocados no se tocan.
+    pub fn issue_missing_player_tokens(&mut self, tournament_id: &Uuid) -> Result<Vec<PlayerToken>, String> {
+        let tournament = self.tournaments.get(tournament_id)
+            .ok_or("Tournament not found")?;
+        
+        let sessions = self.player_sessions.get(tournament_id);
+        let missing: Vec<Uuid> = tournament.players.iter()
+            .filter(|p| {
+                let player_id = p.id.to_string();
+                sessions.and_then(|sessions| sessions.iter().find(|s| s.player_id == player_id))
+                    .is_none_or(|s| s.token_hash.is_none() && s.token_issued_at.is_none())
+            })
+            .map(|p| p.id)
+            .collect();
+        
+        missing.iter()
+            .map(|player_id| self.reissue_player_token(tournament_id, player_id))
+            .collect()
+    }
+    
+    pub fn revoke_player_token(&mut self, tournament_id: &Uuid, player_id: &Uuid) -> Result<(), String> {
+        let player_id_str = player_id.to_string();
+        let session = self.player_sessions.get_mut(tournament_id)
+            .and_then(|sessions| sessions.iter_mut().find(|s| s.player_id == player_id_str))
+            .ok_or("Jugador sin sesión activa")?;
+        
+        // `token_issued_at` se conserva para distinguir un token revocado de
+        // una sesión que nunca tuvo token (snapshots anteriores a 1.1.0)
+        session.token_hash = None;
+        
+        self.save_sessions(tournament_id, "revoking player token");
+        
+        Ok(())
+    }
+    
+    pub fn arbiter_accounts_for(&self, tournament_id: &Uuid) -> Vec<ArbiterAccount> {
+        self.arbiter_accounts.get(tournament_id).cloned().unwrap_or_default()
+    }
+    
+    pub fn add_arbiter_account(&mut self, tournament_id: &Uuid, account: ArbiterAccount) -> Result<(), String> {
+        if !self.tournaments.contains_key(tournament_id) {
+            return Err("Tournament not found".to_string());
+        }
+        
+        let accounts = self.arbiter_accounts.entry(*tournament_id).or_default();
+        if accounts.iter().any(|a| a.name.eq_ignore_ascii_case(&account.name)) {
+            return Err(format!("Ya existe un árbitro llamado '{}'", account.name));
+        }
+        accounts.push(account);
+        
+        self.save_sessions(tournament_id, "adding arbiter account");
+        Ok(())
+    }
+    
+    pub fn arbiter_login(&mut self, tournament_id: &Uuid, name: Option<&str>, passphrase: &str) -> Result<ArbiterLoginResponse, String> {
+        let account = self.arbiter_accounts.get(tournament_id)
+            .and_then(|accounts| accounts.iter()
+                .filter(|a| name.is_none_or(|n| a.name.eq_ignore_ascii_case(n)))
+                .find(|a| a.check_passphrase(passphrase)))
+            .ok_or("Credenciales de árbitro inválidas")?;
+        
+        let token = crate::auth::generate_token();
+        let session = ArbiterSession {
+            tournament_id: *tournament_id,
+            name: account.name.clone(),
+            role: account.role,
+            created_at: Utc::now(),
+        };
+        let response = ArbiterLoginResponse {
+            token: token.clone(),
+            name: session.name.clone(),
+            role: session.role,
+        };
+        self.arbiter_sessions.insert(crate::auth::hash_token(&token), session);
+        
+        Ok(response)
+    }
+    
+    pub fn arbiter_logout(&mut self, token: &str) {
+        self.arbiter_sessions.remove(&crate::auth::hash_token(token));
+    }
+    
+    /// Autoriza una acción de árbitro. Devuelve `None` si la autoriza la
+    /// credencial del servidor o el modo abierto (torneo sin cuentas). Sin
+    /// torneo, la acción es global y solo la autoriza la credencial del servidor.
+    pub fn authorize_arbiter(&self, tournament_id: Option<&Uuid>, token: Option<&str>, action: ArbiterAction) -> Result<Option<ArbiterSession>, String> {
+        let is_server = token.zip(self.server_credential.as_deref())
+            .is_some_and(|(token, hash)| crate::auth::verify_token(token, hash));
+        if is_server {
+            return Ok(None);
+        }
+        
+        let Some(tournament_id) = tournament_id else {
+            return if self.open_mode && self.server_credential.is_none() {
+                Ok(None)
+            } else {
+                Err(format!("La acción {:?} requiere la credencial del servidor", action))
+            };
+        };
+        if action == ArbiterAction::ConfigureServer {
+            return Err("Se requiere la credencial del servidor".to_string());
+        }
+        
+        let protected = self.arbiter_accounts.get(tournament_id).is_some_and(|a| !a.is_empty());
+        if !protected {
+            return if self.open_mode {
+                Ok(None)
+            } else {
+                Err("Torneo sin árbitros configurados; se requiere la credencial del servidor".to_string())
+            };
+        }
+        
+        let token = token.ok_or("Se requiere sesión de árbitro")?;
+        let session = self.arbiter_sessions.get(&crate::auth::hash_token(token))
+            .ok_or("Sesión de árbitro inválida o expirada")?;
+        
+        if *tournament_id != session.tournament_id {
+            return Err("La sesión de árbitro pertenece a otro torneo".to_string());
+        }
+        if !session.role.allows(action) {
+            return Err(format!("El rol {:?} no permite la acción {:?}", session.role, action));
+        }
+        
+        Ok(Some(session.clone()))
+    }
+    
+    pub fn device_policy_for(&self, tournament_id: &Uuid) -> DevicePolicy {
+        self.device_policies.get(tournament_id).copied().unwrap_or_default()
+    }
+    
+    /// Política explícita del torneo (`None` si usa la predeterminada)
+    pub fn configured_device_policy(&self, tournament_id: &Uuid) -> Option<DevicePolicy> {
+        self.device_policies.get(tournament_id).copied()
+    }
+    
+    pub fn set_device_policy(&mut self, tournament_id: &Uuid, policy: DevicePolicy) -> Result<(), String> {
+        if !self.tournaments.contains_key(tournament_id) {
+            return Err("Tournament not found".to_string());
+        }
+        self.device_policies.insert(*tournament_id, policy);
+        self.save_sessions(tournament_id, "updating device policy");
+        Ok(())
+    }
+    
+    pub fn device_alerts_for(&self, tournament_id: &Uuid) -> Vec<DeviceAlert> {
+        self.device_alerts.get(tournament_id).cloned().unwrap_or_default()
+    }
+    
+    /// Dispositivos por jugador y ronda, ordenados para que el snapshot sea estable
+    fn round_devices_for(&self, tournament_id: &Uuid) -> Vec<RoundDevices> {
+        let mut entries: Vec<RoundDevices> = self.round_devices.get(tournament_id)
+            .map(|devices| devices.iter()
+                .map(|((player_id, round_number), devices)| RoundDevices {
+                    player_id: *player_id,
+                    round_number: *round_number,
+                    devices: devices.clone(),
+                })
+                .collect())
+            .unwrap_or_default();
+        entries.sort_by_key(|e| (e.round_number, e.player_id));
+        entries
+    }
+    
+    pub fn acknowledge_device_alert(&mut self, tournament_id: &Uuid, alert_id: &Uuid) -> Result<(), String> {
+        let alert = self.device_alerts.get_mut(tournament_id)
+            .and_then(|alerts| alerts.iter_mut().find(|a| &a.id == alert_id))
+            .ok_or("Alert not found")?;
+        alert.acknowledged = true;
+        self.save_sessions(tournament_id, "acknowledging device alert");
+        Ok(())
+    }
+    
+    fn raise_device_alert(&mut self, tournament_id: &Uuid, alert: DeviceAlert) {
+        use crate::persistence::PersistenceManager;
+        
+        let alerts = self.device_alerts.entry(*tournament_id).or_default();
+        // No repetir la misma alerta para el mismo dispositivo, ronda y jugadores
+        let mut ids = alert.player_ids.clone();
+        ids.sort();
+        if alerts.iter().any(|a| {
+            let mut existing = a.player_ids.clone();
+            existing.sort();
+            a.kind == alert.kind && a.round_number == alert.round_number && a.device == alert.device && existing == ids
+        }) {
+            return;
+        }
+        
+        log::warn!("Device alert ({:?}) in tournament {}: {}", alert.kind, tournament_id, alert.message);
+        if let Err(e) = PersistenceManager::log_event(&tournament_id.to_string(), &format!("ALERTA {:?}: {}", alert.kind, alert.message)) {
+            eprintln!("Failed to log device alert: {}", e);
+        }
+        alerts.push(alert);
+    }
+    
+    /// Avisa a los torneos no terminados de un cambio en la disponibilidad de la nube
+    pub fn announce_cloud_status(&self, available: bool, mode: PersistenceMode) {
+        use crate::persistence::PersistenceManager;
+        
+        let message = if available {
+            format!("NUBE disponible de nuevo; modo de persistencia {:?}", mode)
+        } else {
+            format!("NUBE no disponible; se escribe en local ({:?})", mode)
+        };
+        for tournament in self.tournaments.values().filter(|t| t.status != TournamentStatus::Finished) {
+            self.events.publish(&tournament.id, TournamentEvent::CloudStatus { available, mode, at: Utc::now() });
+            if let Err(e) = PersistenceManager::log_event(&tournament.id.to_string(), &message) {
+                eprintln!("Failed to log cloud status: {}", e);
+            }
+        }
+    }
+    
+    /// Alertas que levanta una jugada enviada desde `device`: jugadores compartiendo
+    /// o alternando dispositivos en la ronda
+    fn submission_device_alerts(&self, tournament_id: &Uuid, player_id: &Uuid, round_number: u32, device: &str) -> Vec<DeviceAlert> {
+        let policy = self.device_policy_for(tournament_id);
+        let Some(devices) = self.round_devices.get(tournament_id) else {
+            return Vec::new();
+        };
+        let mut alerts = Vec::new();
+        
+        if policy.detect_multi_device_player {
+            if let Some(seen) = devices.get(&(*player_id, round_number)) {
+                if !seen.is_empty() && !seen.iter().any(|d| d == device) {
+                    alerts.push(DeviceAlert::new(
+                        DeviceAlertKind::MultiDevicePlayer,
+                        vec![*player_id],
+                        Some(round_number),
+                        device,
+                        format!("El jugador envió desde {} dispositivos distintos en la ronda {}", seen.len() + 1, round_number),
+                        policy.block,
+                    ));
+                }
+            }
+        }
+        
+        if policy.detect_shared_device {
+            let mut player_ids: Vec<Uuid> = devices.iter()
+                .filter(|((p, r), seen)| *r == round_number && p != player_id && seen.iter().any(|d| d == device))
+                .map(|((p, _), _)| *p)
+                .collect();
+            if !player_ids.is_empty() {
+                player_ids.push(*player_id);
+                alerts.push(DeviceAlert::new(
+                    DeviceAlertKind::SharedSubmission,
+                    player_ids.clone(),
+                    Some(round_number),
+                    device,
+                    format!("Un mismo dispositivo envió jugadas de {} jugadores en la ronda {}", player_ids.len(), round_number),
+                    policy.block,
+                ));
+            }
+        }
+        alerts
+    }
+    
+    /// Comprueba el dispositivo antes de aceptar una jugada. Si la política bloquea,
+    /// registra las alertas y devuelve error; el dispositivo no se guarda.
+    pub fn check_submission_device(&mut self, tournament_id: &Uuid, player_id: &Uuid, round_number: u32, device: &str) -> Result<(), String> {
+        if !self.device_policy_for(tournament_id).block {
+            return Ok(());
+        }
+        let alerts = self.submission_device_alerts(tournament_id, player_id, round_number, device);
+        if alerts.is_empty() {
+            return Ok(());
+        }
+        for alert in alerts {
+            self.raise_device_alert(tournament_id, alert);
+        }
+        Err("Jugada bloqueada: dispositivo no autorizado para este jugador en la ronda".to_string())
+    }
+    
+    /// Registra el dispositivo de una jugada ya aceptada y las alertas que levante.
+    /// Solo cuentan las jugadas válidas: una rechazada no deja rastro del dispositivo.
+    pub fn record_submission_device(&mut self, tournament_id: &Uuid, player_id: &Uuid, round_number: u32, device: &str) {
+        let alerts = self.submission_device_alerts(tournament_id, player_id, round_number, device);
+        
+        let seen = self.round_devices.entry(*tournament_id).or_default()
+            .entry((*player_id, round_number)).or_default();
+        if !seen.iter().any(|d| d == device) {
+            seen.push(device.to_string());
+        }
+        
+        let player_id_str = player_id.to_string();
+        if let Some(session) = self.player_sessions.get_mut(tournament_id)
+            .and_then(|sessions| sessions.iter_mut().find(|s| s.player_id == player_id_str)) {
+            session.last_seen = Utc::now();
+        }
+        
+        for alert in alerts {
+            self.raise_device_alert(tournament_id, alert);
+        }
+    }
+    
+    fn save_sessions(&self, tournament_id: &Uuid, action: &str) {
+        if let Err(e) = self.persist(tournament_id) {
+            eprintln!("Failed to save tournament after {}: {}", action, e);
+        }
     }
     
     pub fn validate_word(&self, word: &str) -> Result<bool, String> {
@@ -245,9 +837,8 @@ impl TournamentManager {
         tournament.rounds.push(round.clone());
         
         // Save tournament state after round creation
-        use crate::persistence::PersistenceManager;
-        let tournament_clone = tournament.clone();
-        if let Err(e) = PersistenceManager::save_tournament(&tournament_clone, self, vec![]) {
+        self.record(tournament_id, JournalEvent::RoundStarted { round: round.clone(), tiles_remaining });
+        if let Err(e) = self.persist(tournament_id) {
             eprintln!("Failed to save tournament after round creation: {}", e);
         }
         
@@ -256,6 +847,9 @@ impl TournamentManager {
             eprintln!("Failed to log round: {}", e);
         }
         
+        self.events.publish(tournament_id, TournamentEvent::RoundStarted { round: RoundView::from_round(&round) });
+        self.refresh_play_scores(tournament_id);
+        
         Ok(round)
     }
     
@@ -376,10 +970,30 @@ impl TournamentManager {
         round.rack_rejected = false;
         round.rejection_reason = None;
         
-        Ok(round.clone())
+        let updated = round.clone();
+        let tiles_remaining = tournament.tiles_remaining;
+        self.record(tournament_id, JournalEvent::RackUpdated {
+            round_number: updated.number,
+            rack: updated.rack.clone(),
+            tiles_remaining,
+        });
+        self.events.publish(tournament_id, TournamentEvent::RackUpdated {
+            round_number: updated.number,
+            rack: updated.rack.clone(),
+        });
+        self.refresh_play_scores(tournament_id);
+        
+        Ok(updated)
     }
     
     pub fn start_new_round_manual(&mut self, tournament_id: &Uuid, manual_rack: &str) -> Result<Round, String> {
+        self.start_round_with_rack(tournament_id, manual_rack, true)
+    }
+    
+    /// Inicia una ronda con un atril dado y saca sus fichas de la bolsa.
+    /// Con `full_rack` el atril debe tener 7 fichas; si no, basta con 1 a 7
+    /// (final de partida en una plantilla).
+    fn start_round_with_rack(&mut self, tournament_id: &Uuid, manual_rack: &str, full_rack: bool) -> Result<Round, String> {
         let engine = self.engine.as_mut()
             .ok_or("Engine not initialized")?;
             
@@ -437,9 +1051,12 @@ impl TournamentManager {
             }
         }
         
-        if tile_count != 7 {
+        if full_rack && tile_count != 7 {
             return Err(format!("El atril debe tener exactamente 7 fichas, se proporcionaron {}", tile_count));
         }
+        if !(1..=7).contains(&tile_count) {
+            return Err(format!("El atril debe tener entre 1 y 7 fichas, se proporcionaron {}", tile_count));
+        }
         
         // Parse manual rack to remove tiles from bag (usar mismo rack convertido)
         let rack_bytes = internal_rack.as_bytes();
@@ -523,15 +1140,120 @@ impl TournamentManager {
         };
         
         tournament.rounds.push(round.clone());
+        let tiles_remaining = tournament.tiles_remaining;
+        self.record(tournament_id, JournalEvent::RoundStarted { round: round.clone(), tiles_remaining });
         
         // Log round
         if let Err(e) = self.log_round(tournament_id, &round) {
             eprintln!("Failed to log round: {}", e);
         }
         
+        self.events.publish(tournament_id, TournamentEvent::RoundStarted { round: RoundView::from_round(&round) });
+        self.refresh_play_scores(tournament_id);
+        
         Ok(round)
     }
     
+    /// Crea un torneo que reparte los atriles de un registro importado
+    pub fn create_tournament_from_record(
+        &mut self,
+        record: &str,
+        name: Option<String>,
+        player_names: Vec<String>,
+        arbiter_passphrase: Option<String>,
+    ) -> Result<(Tournament, Vec<PlayerToken>), String> {
+        let template = crate::record::parse(record)?;
+        let name = name.filter(|n| !n.trim().is_empty()).unwrap_or_else(|| template.name.clone());
+        let (tournament, player_tokens) = self.create_tournament(name, player_names, arbiter_passphrase)?;
+        
+        use crate::persistence::PersistenceManager;
+        if let Err(e) = PersistenceManager::save_template(&tournament.id.to_string(), record) {
+            eprintln!("Failed to save tournament template: {}", e);
+        }
+        self.templates.insert(tournament.id, template);
+        
+        Ok((tournament, player_tokens))
+    }
+    
+    pub fn template_for(&self, tournament_id: &Uuid) -> Option<&GameTemplate> {
+        self.templates.get(tournament_id)
+    }
+    
+    /// Inicia la siguiente ronda con el atril que marca la plantilla
+    pub fn start_template_round(&mut self, tournament_id: &Uuid) -> Result<Round, String> {
+        let engine = self.engine.as_ref()
+            .ok_or("Engine not initialized")?;
+        
+        let tournament = self.tournaments.get(tournament_id)
+            .ok_or("Tournament not found")?;
+        
+        let template = self.templates.get(tournament_id)
+            .ok_or("El torneo no se creó desde un registro")?;
+        
+        let round_number = tournament.rounds.len() as u32 + 1;
+        let rack = template.round(round_number)
+            .ok_or_else(|| format!("La plantilla no tiene ronda {}", round_number))?
+            .rack();
+        
+        // El atril de la plantilla se saca completo de la bolsa, así que el
+        // residuo de la ronda anterior vuelve antes a ella
+        let leave = match tournament.rounds.last() {
+            Some(last_round) if last_round.status == RoundStatus::Completed => match &last_round.optimal_play {
+                Some(optimal) => Self::get_remaining_rack_tiles(engine, &last_round.rack, &optimal.tiles_used, &optimal.blank_positions)?,
+                None => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+        self.bags.get_mut(tournament_id)
+            .ok_or("Bag not found for tournament")?
+            .0.extend(leave);
+        
+        self.start_round_with_rack(tournament_id, &rack, false)
+    }
+    
+    /// Puntuaciones posibles de la ronda; solo se generan si cambió el atril o el tablero
+    fn round_play_scores<'a>(
+        cache: &'a mut HashMap<Uuid, RoundPlayScores>,
+        engine: &mut WolgesEngine,
+        tournament_id: &Uuid,
+        round: &Round,
+    ) -> Option<&'a RoundPlayScores> {
+        let fresh = cache.get(tournament_id).is_some_and(|c| {
+            c.round_number == round.number && c.rack == round.rack && c.board == round.board_state.tiles
+        });
+        if !fresh {
+            match engine.all_play_scores(&round.board_state, &round.rack) {
+                Ok(scores) => {
+                    cache.insert(*tournament_id, RoundPlayScores {
+                        round_number: round.number,
+                        rack: round.rack.clone(),
+                        board: round.board_state.tiles.clone(),
+                        scores,
+                    });
+                }
+                Err(e) => {
+                    log::warn!("No se pudo calcular el puesto de las jugadas: {}", e);
+                    cache.remove(tournament_id);
+                    return None;
+                }
+            }
+        }
+        cache.get(tournament_id)
+    }
+    
+    /// Prepara las puntuaciones de la ronda activa para que los envíos no las generen
+    fn refresh_play_scores(&mut self, tournament_id: &Uuid) {
+        let (Some(engine), Some(round)) = (
+            self.engine.as_mut(),
+            self.tournaments.get(tournament_id)
+                .and_then(|t| t.rounds.last())
+                .filter(|r| r.status == RoundStatus::Active),
+        ) else {
+            return;
+        };
+        Self::round_play_scores(&mut self.play_scores, engine, tournament_id, round);
+    }
+    
     pub fn calculate_optimal_play(&mut self, tournament_id: &Uuid, round_number: u32) -> Result<OptimalPlay, String> {
         let engine = self.engine.as_mut()
             .ok_or("Engine not initialized")?;
@@ -578,9 +1300,9 @@ impl TournamentManager {
         
         if let Some(timer_started) = round.timer_started {
             let elapsed = now.signed_duration_since(timer_started);
-            if elapsed.num_seconds() > 180 { // 3 minutes = 180 seconds
+            if elapsed.num_seconds() > events::ROUND_TIMER_SECS { // 3 minutes = 180 seconds
                 is_late = true;
-                eprintln!("Jugada tardía: {} segundos después del límite", elapsed.num_seconds() - 180);
+                eprintln!("Jugada tardía: {} segundos después del límite", elapsed.num_seconds() - events::ROUND_TIMER_SECS);
             }
         }
         
@@ -594,6 +1316,14 @@ impl TournamentManager {
             ).unwrap_or(0); // Si la jugada es inválida, score = 0
         }
         
+        // Puesto entre todas las jugadas válidas (solo si la jugada lo es)
+        let rank = if score > 0 {
+            Self::round_play_scores(&mut self.play_scores, engine, tournament_id, round)
+                .map(|cached| PlayRank::among(&cached.scores, score))
+        } else {
+            None
+        };
+        
         // Get optimal play score for this round (should already be calculated)
         let optimal_score = round.optimal_play.as_ref()
             .map(|op| op.score)
@@ -647,9 +1377,9 @@ impl TournamentManager {
         let play = PlayerPlay {
             round_number,
             word: if is_late { 
-                format!("{} (TIEMPO EXCEDIDO)", word) 
+                format!("{}{}", word, LATE_MARK) 
             } else if score == 0 && !word.is_empty() {
-                format!("{} (INVÁLIDA)", word)
+                format!("{}{}", word, INVALID_MARK)
             } else { 
                 word 
             },
@@ -660,6 +1390,7 @@ impl TournamentManager {
             cumulative_score,
             difference_from_optimal,
             cumulative_difference,
+            rank,
         };
         
         // Remover jugada existente si hay una
@@ -671,13 +1402,14 @@ impl TournamentManager {
         player.plays.push(play.clone());
         player.total_score = cumulative_score;
         
-        // Save tournament state after player submission
-        use crate::persistence::PersistenceManager;
-        let tournament_clone = tournament.clone();
-        if let Err(e) = PersistenceManager::save_tournament(&tournament_clone, self, vec![]) {
-            eprintln!("Failed to save tournament after player submission: {}", e);
+        // La jugada queda en el diario; el snapshot completo solo cada CHECKPOINT_INTERVAL entradas
+        let seq = self.record(tournament_id, JournalEvent::PlaySubmitted { player_id: *player_id, play });
+        if seq.is_multiple_of(journal::CHECKPOINT_INTERVAL) {
+            self.checkpoint(tournament_id);
         }
         
+        self.publish_submission_count(tournament_id, round_number);
+        
         // Return only confirmation, not the percentage
         Ok(PlaySubmissionResponse {
             success: true,
@@ -721,8 +1453,8 @@ impl TournamentManager {
         
         if let Some(play) = player_play {
             // Player submitted a play
-            let late_submission = play.word.contains("TIEMPO EXCEDIDO");
-            let invalid_play = play.word.contains("INVÁLIDA");
+            let late_submission = play.is_late();
+            let invalid_play = play.is_invalid();
             
             // Format coordinate for display
             let coord_str = if play.position.down {
@@ -742,19 +1474,24 @@ impl TournamentManager {
                 feedback_message: if late_submission {
                     "Causa: Tiempo excedido - 0% del óptimo".to_string()
                 } else if invalid_play {
-                    // Extract original word from "(INVÁLIDA)" format
-                    let original_word = play.word.replace(" (INVÁLIDA)", "");
-                    format!("Causa: Jugada inválida '{}' en {} - 0% del óptimo", original_word, coord_str)
-                } else if play.percentage_of_optimal >= 100.0 {
-                    "¡Excelente! Encontraste la jugada óptima".to_string()
-                } else if play.percentage_of_optimal >= 80.0 {
-                    format!("Muy buena jugada - {}% del óptimo", play.percentage_of_optimal.round() as i32)
-                } else if play.percentage_of_optimal >= 60.0 {
-                    format!("Buena jugada - {}% del óptimo", play.percentage_of_optimal.round() as i32)
+                    format!("Causa: Jugada inválida '{}' en {} - 0% del óptimo", play.played_word(), coord_str)
                 } else {
-                    format!("Jugada registrada - {}% del óptimo", play.percentage_of_optimal.round() as i32)
+                    let message = if play.percentage_of_optimal >= 100.0 {
+                        "¡Excelente! Encontraste la jugada óptima".to_string()
+                    } else if play.percentage_of_optimal >= 80.0 {
+                        format!("Muy buena jugada - {}% del óptimo", play.percentage_of_optimal.round() as i32)
+                    } else if play.percentage_of_optimal >= 60.0 {
+                        format!("Buena jugada - {}% del óptimo", play.percentage_of_optimal.round() as i32)
+                    } else {
+                        format!("Jugada registrada - {}% del óptimo", play.percentage_of_optimal.round() as i32)
+                    };
+                    match &play.rank {
+                        Some(rank) => format!("{} · {}", message, describe_rank(rank)),
+                        None => message,
+                    }
                 },
                 late_submission,
+                rank: play.rank.clone(),
             })
         } else {
             // Player didn't submit
@@ -768,10 +1505,108 @@ impl TournamentManager {
                 optimal_score,
                 feedback_message: "Causa: No jugó - 0% del óptimo".to_string(),
                 late_submission: false,
+                rank: None,
             })
         }
     }
     
+    /// Estado de envío de cada jugador en la ronda; las palabras no se incluyen
+    pub fn round_progress(&self, tournament_id: &Uuid, round_number: u32) -> Result<RoundProgress, String> {
+        let tournament = self.tournaments.get(tournament_id)
+            .ok_or("Tournament not found")?;
+        
+        let round = tournament.rounds.iter()
+            .find(|r| r.number == round_number)
+            .ok_or("Round not found")?;
+        
+        let seconds_remaining = round.timer_started.map(|started| {
+            let elapsed = Utc::now().signed_duration_since(started).num_seconds();
+            (events::ROUND_TIMER_SECS - elapsed).max(0)
+        });
+        
+        let players: Vec<PlayerSubmissionStatus> = tournament.players.iter().map(|player| {
+            let play = player.plays.iter().find(|p| p.round_number == round_number);
+            let state = match play {
+                None => SubmissionState::Pending,
+                Some(p) if p.is_late() => SubmissionState::Late,
+                Some(p) if p.is_invalid() => SubmissionState::Invalid,
+                Some(_) => SubmissionState::Submitted,
+            };
+            PlayerSubmissionStatus {
+                player_id: player.id,
+                name: player.name.clone(),
+                state,
+                submitted_at: play.map(|p| p.submitted_at),
+            }
+        }).collect();
+        
+        let count = |state: SubmissionState| players.iter().filter(|p| p.state == state).count();
+        let pending = count(SubmissionState::Pending);
+        
+        Ok(RoundProgress {
+            round_number,
+            timer_started: round.timer_started,
+            seconds_remaining,
+            submitted: players.len() - pending,
+            pending,
+            late: count(SubmissionState::Late),
+            invalid: count(SubmissionState::Invalid),
+            players,
+        })
+    }
+    
+    /// Estadísticas de una ronda ya revelada
+    pub fn round_stats(&self, tournament_id: &Uuid, round_number: u32) -> Result<RoundStats, String> {
+        let tournament = self.tournaments.get(tournament_id)
+            .ok_or("Tournament not found")?;
+        if !tournament.rounds.iter().any(|r| r.number == round_number) {
+            return Err("Round not found".to_string());
+        }
+        round_stats::compute(tournament, round_number)
+            .ok_or_else(|| "La jugada óptima aún no ha sido revelada".to_string())
+    }
+    
+    /// Estadísticas de todas las rondas reveladas, para el histórico de la partida
+    pub fn round_stats_history(&self, tournament_id: &Uuid) -> Result<Vec<RoundStats>, String> {
+        let tournament = self.tournaments.get(tournament_id)
+            .ok_or("Tournament not found")?;
+        Ok(round_stats::history(tournament))
+    }
+    
+    /// Avisa a los jugadores que aún no enviaron; solo en los últimos 30 segundos
+    pub fn nudge_pending_players(&self, tournament_id: &Uuid, round_number: u32) -> Result<Vec<Uuid>, String> {
+        let progress = self.round_progress(tournament_id, round_number)?;
+        
+        let seconds_remaining = progress.seconds_remaining
+            .ok_or("El temporizador de la ronda no ha iniciado")?;
+        if seconds_remaining > events::TIMER_WARNING_SECS {
+            return Err(format!("Solo se puede avisar en los últimos {} segundos", events::TIMER_WARNING_SECS));
+        }
+        if seconds_remaining == 0 {
+            return Err("El tiempo de la ronda ya terminó".to_string());
+        }
+        
+        let player_ids: Vec<Uuid> = progress.players.iter()
+            .filter(|p| p.state == SubmissionState::Pending)
+            .map(|p| p.player_id)
+            .collect();
+        
+        if !player_ids.is_empty() {
+            use crate::persistence::PersistenceManager;
+            let _ = PersistenceManager::log_event(
+                &tournament_id.to_string(),
+                &format!("Aviso de tiempo a {} jugadores en ronda {}", player_ids.len(), round_number),
+            );
+            self.events.publish(tournament_id, TournamentEvent::Nudge {
+                round_number,
+                player_ids: player_ids.clone(),
+                seconds_remaining,
+            });
+        }
+        
+        Ok(player_ids)
+    }
+    
     pub fn finish_tournament_manually(&mut self, tournament_id: &Uuid) -> Result<(), String> {
         let tournament = self.tournaments.get_mut(tournament_id)
             .ok_or("Tournament not found")?;
@@ -792,22 +1627,29 @@ impl TournamentManager {
         
         // Cambiar status a terminado
         tournament.status = TournamentStatus::Finished;
+        let last_round_number = tournament.rounds.last().map(|r| r.number);
         
         // Obtener nombre del torneo antes de clonar
         let tournament_name = tournament.name.clone();
         
         // Guardar estado del torneo
-        use crate::persistence::PersistenceManager;
-        let tournament_clone = tournament.clone();
-        drop(tournament); // Liberar el préstamo mutable
+        self.record(tournament_id, JournalEvent::TournamentFinished);
         
-        if let Err(e) = PersistenceManager::save_tournament(&tournament_clone, self, vec![]) {
+        if let Err(e) = self.persist(tournament_id) {
             eprintln!("Failed to save tournament after manual finish: {}", e);
         }
         
         println!("Tournament '{}' finished manually after {} rounds", 
                  tournament_name, completed_rounds);
         
+        if let Some(round_number) = last_round_number {
+            self.events.cancel_timer(tournament_id, round_number);
+        }
+        
+        self.events.publish(tournament_id, TournamentEvent::TournamentFinished {
+            reason: Some("Terminado manualmente por el árbitro".to_string()),
+        });
+        
         Ok(())
     }
     
@@ -870,6 +1712,7 @@ impl TournamentManager {
                     master_word: master_play.map(|mp| mp.word.clone()).unwrap_or_default(),
                     master_score: master_play.map(|mp| mp.score).unwrap_or(0),
                     master_cumulative,
+                    rank: play.rank.clone(),
                 });
             }
         }
@@ -880,6 +1723,92 @@ impl TournamentManager {
         })
     }
     
+    /// Análisis del rendimiento del jugador en las rondas con master colocado
+    pub fn player_analytics(&self, tournament_id: &Uuid, player_id: &Uuid) -> Result<PlayerAnalytics, String> {
+        let tournament = self.tournaments.get(tournament_id)
+            .ok_or("Tournament not found")?;
+        player_analytics::compute(tournament, player_id)
+    }
+    
+    /// Activa o desactiva el modo entrenamiento (análisis por equity)
+    pub fn set_training(&mut self, tournament_id: &Uuid, enabled: bool) -> Result<(), String> {
+        let tournament = self.tournaments.get_mut(tournament_id)
+            .ok_or("Tournament not found")?;
+        tournament.training = enabled;
+        let tournament = tournament.clone();
+        
+        self.record(tournament_id, JournalEvent::Checkpoint { tournament });
+        if let Err(e) = self.persist(tournament_id) {
+            eprintln!("Failed to save tournament after changing training mode: {}", e);
+        }
+        Ok(())
+    }
+    
+    /// Prepara el análisis por equity de una ronda revelada; con `player_id`,
+    /// compara además el resto de ese jugador. La generación de jugadas se hace
+    /// en CoachingJob::run, sin el lock del manager. No modifica el torneo.
+    pub fn coaching_job(&self, tournament_id: &Uuid, round_number: u32, player_id: Option<&Uuid>) -> Result<CoachingJob, String> {
+        let tournament = self.tournaments.get(tournament_id)
+            .ok_or("Tournament not found")?;
+        if !tournament.training {
+            return Err("El análisis por equity solo está disponible en torneos de entrenamiento".to_string());
+        }
+        let round = tournament.rounds.iter()
+            .find(|r| r.number == round_number)
+            .ok_or("Round not found")?;
+        if !round.optimal_revealed {
+            return Err("La jugada óptima aún no ha sido revelada".to_string());
+        }
+        
+        // Tablero anterior al master: si ya se colocó, se reconstruye
+        let (board, master_word, master_position, master_score) = match gcg::master_turns(tournament).into_iter()
+            .find(|t| t.master.round_number == round_number) {
+            Some(turn) => (turn.board_before(), turn.master.word.clone(), turn.master.position.clone(), turn.master.score),
+            None => {
+                let optimal = round.optimal_play.as_ref()
+                    .ok_or("No optimal play calculated for this round")?;
+                let word: String = optimal.tiles_used.iter().enumerate().map(|(i, tile)| {
+                    if !tile.is_empty() {
+                        return tile.clone();
+                    }
+                    let (row, col) = if optimal.position.down {
+                        (optimal.position.row as usize + i, optimal.position.col as usize)
+                    } else {
+                        (optimal.position.row as usize, optimal.position.col as usize + i)
+                    };
+                    round.board_state.tiles.get(row * 15 + col).cloned().unwrap_or_default()
+                }).collect();
+                (round.board_state.clone(), word, optimal.position.clone(), optimal.score)
+            }
+        };
+        
+        let player_play = match player_id {
+            Some(player_id) => Some(tournament.players.iter()
+                .find(|p| &p.id == player_id)
+                .ok_or("Player not found")?
+                .plays.iter()
+                .find(|p| p.round_number == round_number)
+                .cloned()),
+            None => None,
+        };
+        
+        let engine = self.coaching_engine.clone()
+            .ok_or("Engine not initialized")?;
+        
+        Ok(CoachingJob {
+            tournament_id: *tournament_id,
+            round_number,
+            rack: round.rack.clone(),
+            board,
+            master_word,
+            master_position,
+            master_score,
+            player_play,
+            engine,
+            cache: self.coaching_cache.clone(),
+        })
+    }
+    
     fn apply_play_to_board(board: &mut BoardState, play: &OptimalPlay) -> Result<(), String> {
         let start_idx = if play.position.down {
             play.position.row as usize * 15 + play.position.col as usize
@@ -1372,11 +2301,23 @@ impl TournamentManager {
         let result = tournament.rounds[round_idx].clone();
         
         // Save tournament state after rack rejection
-        use crate::persistence::PersistenceManager;
-        let tournament_clone = tournament.clone();
-        if let Err(e) = PersistenceManager::save_tournament(&tournament_clone, self, vec![]) {
+        self.record(tournament_id, JournalEvent::RackRejected {
+            round_number,
+            rack: result.rack.clone(),
+            rejection_reason: result.rejection_reason.clone(),
+            tiles_remaining,
+        });
+        if let Err(e) = self.persist(tournament_id) {
             eprintln!("Failed to save tournament after rack rejection: {}", e);
         }
+        self.events.cancel_timer(tournament_id, round_number);
+        
+        self.events.publish(tournament_id, TournamentEvent::RackRejected {
+            round_number,
+            rack: result.rack.clone(),
+            reason: result.rejection_reason.clone(),
+        });
+        self.refresh_play_scores(tournament_id);
         
         Ok(result)
     }
@@ -1388,18 +2329,29 @@ impl TournamentManager {
         let round = tournament.rounds.iter_mut()
             .find(|r| r.number == round_number)
             .ok_or("Round not found")?;
+        if round.status != RoundStatus::Active || round.optimal_revealed {
+            return Err("La ronda ya no está activa".to_string());
+        }
         
-        round.timer_started = Some(Utc::now());
+        let started_at = Utc::now();
+        round.timer_started = Some(started_at);
         
-        eprintln!("Timer iniciado para ronda {} a las {}", round_number, round.timer_started.unwrap());
+        eprintln!("Timer iniciado para ronda {} a las {}", round_number, started_at);
         
         // Save tournament state after starting timer
-        use crate::persistence::PersistenceManager;
-        let tournament_clone = tournament.clone();
-        if let Err(e) = PersistenceManager::save_tournament(&tournament_clone, self, vec![]) {
+        self.record(tournament_id, JournalEvent::TimerStarted { round_number, started_at });
+        if let Err(e) = self.persist(tournament_id) {
             eprintln!("Failed to save tournament after starting timer: {}", e);
         }
         
+        self.events.publish(tournament_id, TournamentEvent::TimerPhase {
+            round_number,
+            phase: TimerPhase::Started,
+            started_at,
+            seconds_remaining: events::ROUND_TIMER_SECS,
+        });
+        self.events.spawn_timer_phases(*tournament_id, round_number, started_at);
+        
         Ok(())
     }
     
@@ -1414,11 +2366,20 @@ impl TournamentManager {
         round.optimal_revealed = true;
         
         // Save tournament state after revealing optimal play
-        use crate::persistence::PersistenceManager;
         let tournament_clone = tournament.clone();
-        if let Err(e) = PersistenceManager::save_tournament(&tournament_clone, self, vec![]) {
+        self.record(tournament_id, JournalEvent::MasterRevealed { round_number });
+        if let Err(e) = self.persist(tournament_id) {
             eprintln!("Failed to save tournament after revealing optimal play: {}", e);
         }
+        // Con el master revelado la ronda ya no admite jugadas: sin aviso de fin
+        self.events.cancel_timer(tournament_id, round_number);
+        
+        if let Some(master) = tournament_clone.rounds.iter()
+            .find(|r| r.number == round_number)
+            .and_then(|r| r.optimal_play.clone()) {
+            self.events.publish(tournament_id, TournamentEvent::MasterRevealed { round_number, master });
+        }
+        self.publish_round_stats(tournament_id, round_number);
         
         Ok(())
     }
@@ -1475,7 +2436,7 @@ impl TournamentManager {
             cumulative_score,
         };
         
-        tournament.master_plays.push(master_play);
+        tournament.master_plays.push(master_play.clone());
         
         let round = tournament.rounds.iter_mut()
             .find(|r| r.number == round_number)
@@ -1483,6 +2444,7 @@ impl TournamentManager {
             
         round.optimal_revealed = true;
         round.status = RoundStatus::Completed;
+        self.events.cancel_timer(tournament_id, round_number);
         
         // Apply the optimal play to the board state
         if let Some(optimal_play) = &round.optimal_play {
@@ -1498,32 +2460,107 @@ impl TournamentManager {
             eprintln!("Failed to log optimal play: {}", e);
         }
         
+        self.events.publish(tournament_id, TournamentEvent::MasterRevealed { round_number, master: optimal_play_clone.clone() });
+        self.publish_round_stats(tournament_id, round_number);
+        if let Some(round) = self.tournaments.get(tournament_id)
+            .and_then(|t| t.rounds.iter().find(|r| r.number == round_number)) {
+            self.events.publish(tournament_id, TournamentEvent::BoardUpdated {
+                round_number,
+                board: round.board_state.clone(),
+            });
+        }
+        self.publish_leaderboard(tournament_id);
+        
         // Verificar si el juego debe terminar
+        let mut finished = false;
         match self.check_game_end_condition(tournament_id) {
             Ok((should_end, reason)) => {
                 if should_end {
                     // Marcar el torneo como terminado
                     if let Some(tournament) = self.tournaments.get_mut(tournament_id) {
                         tournament.status = TournamentStatus::Finished;
-                        eprintln!("Torneo terminado: {}", reason.unwrap_or_default());
+                        eprintln!("Torneo terminado: {}", reason.clone().unwrap_or_default());
                     }
+                    finished = true;
+                    self.events.publish(tournament_id, TournamentEvent::TournamentFinished { reason });
                 }
             }
             Err(e) => eprintln!("Error al verificar condición de fin: {}", e),
         }
         
+        if let Some(board) = self.tournaments.get(tournament_id)
+            .and_then(|t| t.rounds.iter().find(|r| r.number == round_number))
+            .map(|r| r.board_state.clone()) {
+            self.record(tournament_id, JournalEvent::MasterPlaced {
+                round_number,
+                optimal_play: optimal_play_clone,
+                master_play,
+                board,
+                finished,
+            });
+        }
+        
         // Save tournament state after placing optimal play
-        if let Some(tournament) = self.tournaments.get(tournament_id) {
-            use crate::persistence::PersistenceManager;
-            let tournament_clone = tournament.clone();
-            if let Err(e) = PersistenceManager::save_tournament(&tournament_clone, self, vec![]) {
-                eprintln!("Failed to save tournament after placing optimal play: {}", e);
-            }
+        if let Err(e) = self.persist(tournament_id) {
+            eprintln!("Failed to save tournament after placing optimal play: {}", e);
         }
         
         Ok(())
     }
     
+    /// Bolsa = fichas completas - fichas en el tablero - fichas del atril fuera de la bolsa
+    fn rebuild_bag(engine: &WolgesEngine, tournament: &Tournament) -> Result<bag::Bag, String> {
+        let alphabet = engine.get_alphabet();
+        let rack_reader = alphabet::AlphabetReader::new_for_racks(alphabet);
+        let parse_tiles = |text: &str| -> Result<Vec<u8>, String> {
+            let internal = text.to_uppercase()
+                .replace("[CH]", "Ç")
+                .replace("[LL]", "K")
+                .replace("[RR]", "W");
+            let bytes = internal.as_bytes();
+            let mut tiles = Vec::new();
+            let mut idx = 0;
+            while idx < bytes.len() {
+                let (tile, next_idx) = rack_reader.next_tile(bytes, idx)
+                    .ok_or_else(|| format!("Ficha no reconocida en '{}'", text))?;
+                tiles.push(tile);
+                idx = next_idx;
+            }
+            Ok(tiles)
+        };
+        
+        let mut out_of_bag = Vec::new();
+        if let Some(last_round) = tournament.rounds.last() {
+            // En el tablero, los comodines se guardan en minúscula
+            for tile_str in last_round.board_state.tiles.iter().filter(|t| !t.is_empty()) {
+                if tile_str.chars().any(|c| c.is_lowercase()) {
+                    out_of_bag.push(0);
+                } else {
+                    out_of_bag.extend(parse_tiles(tile_str)?);
+                }
+            }
+            
+            // Atril activo completo, o el residuo si la ronda ya se completó
+            match (&last_round.status, &last_round.optimal_play) {
+                (RoundStatus::Completed, Some(optimal)) => out_of_bag.extend(
+                    Self::get_remaining_rack_tiles(engine, &last_round.rack, &optimal.tiles_used, &optimal.blank_positions)?
+                ),
+                _ => out_of_bag.extend(parse_tiles(&last_round.rack)?),
+            }
+        }
+        
+        let mut bag = bag::Bag::new(alphabet);
+        for tile in out_of_bag {
+            let pos = bag.0.iter().position(|&t| t == tile)
+                .ok_or_else(|| format!("Hay más fichas '{}' en juego que en la bolsa completa",
+                    alphabet.of_board(tile).unwrap_or("?")))?;
+            bag.0.swap_remove(pos);
+        }
+        
+        bag.shuffle(&mut rand::rng());
+        Ok(bag)
+    }
+    
     pub fn get_bag_tiles(&self, tournament_id: &Uuid) -> Result<Vec<(String, bool)>, String> {
         let engine = self.engine.as_ref()
             .ok_or("Engine not initialized")?;
@@ -1675,9 +2712,9 @@ impl TournamentManager {
         let log_date = tournament.created_at.format("%Y%m%d_%H%M%S");
         
         // Save tournament state after undo
-        use crate::persistence::PersistenceManager;
-        let tournament_clone = tournament.clone();
-        if let Err(e) = PersistenceManager::save_tournament(&tournament_clone, self, vec![]) {
+        self.record(tournament_id, JournalEvent::RoundUndone { round_number: last_round_number });
+        self.events.cancel_timer(tournament_id, last_round_number);
+        if let Err(e) = self.persist(tournament_id) {
             eprintln!("Failed to save tournament after undo: {}", e);
         }
         
@@ -1692,6 +2729,11 @@ impl TournamentManager {
             let _ = writeln!(file, "");
         }
         
+        // Los clientes conectados se resincronizan con el estado completo
+        if let Ok(snapshot) = self.state_snapshot(tournament_id) {
+            self.events.publish(tournament_id, TournamentEvent::State(snapshot));
+        }
+        
         Ok(())
     }
     
@@ -1745,20 +2787,225 @@ impl TournamentManager {
         Ok(())
     }
     
-    pub fn restore_tournament(&mut self, tournament: Tournament) {
-        let tournament_id = tournament.id.clone();
+    pub fn restore_tournament(&mut self, loaded: LoadedTournament) {
+        let mut tournament = loaded.tournament;
+        let tournament_id = tournament.id;
         
-        // Recrear la bolsa basándose en las fichas restantes
+        // Recrear la bolsa a partir del tablero y el atril en juego
+        let mut reconciled = false;
         if let Some(engine) = &self.engine {
-            let alphabet = engine.get_alphabet();
-            let mut bag = bag::Bag::new(alphabet);
+            let bag = match Self::rebuild_bag(engine, &tournament) {
+                Ok(bag) => bag,
+                Err(e) => {
+                    log::error!("No se pudo reconstruir la bolsa del torneo {}: {} - se usa una bolsa completa", tournament_id, e);
+                    let mut bag = bag::Bag::new(engine.get_alphabet());
+                    bag.shuffle(&mut rand::rng());
+                    bag
+                }
+            };
             
-            // TODO: Reconstruir el estado exacto de la bolsa basándose en las fichas usadas
-            // Por ahora, simplemente guardamos el torneo
+            if bag.0.len() != tournament.tiles_remaining as usize {
+                log::warn!("Torneo {}: el snapshot indica {} fichas en la bolsa pero la reconstrucción da {}",
+                    tournament_id, tournament.tiles_remaining, bag.0.len());
+                tournament.tiles_remaining = bag.0.len() as u8;
+                reconciled = true;
+            }
             self.bags.insert(tournament_id, bag);
         }
         
+        let without_token = tournament.players.iter()
+            .filter(|p| {
+                let player_id = p.id.to_string();
+                loaded.player_sessions.iter().find(|s| s.player_id == player_id)
+                    .is_none_or(|s| s.token_hash.is_none() && s.token_issued_at.is_none())
+            })
+            .count();
+        if without_token > 0 {
+            log::warn!("Torneo {}: {} jugador(es) sin token de acceso; el árbitro debe emitirlos con POST /tournament/{}/player_tokens",
+                tournament_id, without_token, tournament_id);
+        }
+        self.player_sessions.insert(tournament_id, loaded.player_sessions);
+        self.arbiter_accounts.insert(tournament_id, loaded.arbiter_accounts);
+        if let Some(policy) = loaded.device_policy {
+            self.device_policies.insert(tournament_id, policy);
+        }
+        self.device_alerts.insert(tournament_id, loaded.device_alerts);
+        self.round_devices.insert(tournament_id, loaded.round_devices.into_iter()
+            .map(|e| ((e.player_id, e.round_number), e.devices))
+            .collect());
+        self.journal_seqs.insert(tournament_id, loaded.journal_seq);
+        
+        use crate::persistence::PersistenceManager;
+        if let Some(record) = PersistenceManager::load_template(&tournament_id.to_string()) {
+            match crate::record::parse(&record) {
+                Ok(template) => {
+                    self.templates.insert(tournament_id, template);
+                }
+                Err(e) => log::warn!("Plantilla ilegible del torneo {}: {}", tournament_id, e),
+            }
+        }
+        
+        // Torneos sin diario: la primera entrada es el estado actual. Si se
+        // corrigió la bolsa, un nuevo punto de partida evita que el diario
+        // vuelva al valor anterior. El torneo ya debe estar cargado para que
+        // record pueda entregarlo al almacenamiento.
+        let checkpoint = (loaded.journal_seq == 0 || reconciled).then(|| tournament.clone());
         self.tournaments.insert(tournament_id, tournament);
+        if let Some(tournament) = checkpoint {
+            self.record(&tournament_id, JournalEvent::Checkpoint { tournament });
+        }
+        if reconciled {
+            self.checkpoint(&tournament_id);
+        }
+        self.refresh_play_scores(&tournament_id);
+    }
+    
+    pub fn journal_seq(&self, tournament_id: &Uuid) -> u64 {
+        self.journal_seqs.get(tournament_id).copied().unwrap_or(0)
+    }
+    
+    /// Agrega un evento al diario del torneo y devuelve su número de secuencia
+    fn record(&mut self, tournament_id: &Uuid, event: JournalEvent) -> u64 {
+        let seq = self.journal_seqs.entry(*tournament_id).or_insert(0);
+        *seq += 1;
+        let entry = JournalEntry {
+            seq: *seq,
+            recorded_at: Utc::now(),
+            event,
+            mac: None,
+        };
+        
+        if let Some(tournament) = self.tournaments.get(tournament_id) {
+            let state = EventState {
+                tournament,
+                player_sessions: self.player_sessions.get(tournament_id).map(Vec::as_slice).unwrap_or_default(),
+            };
+            if let Err(e) = self.storage.record_event(state, &entry) {
+                eprintln!("Failed to append journal entry {}: {}", entry.seq, e);
+            }
+        }
+        entry.seq
+    }
+    
+    /// Estado completo del torneo tal como se guarda en cada backend
+    pub fn tournament_state(&self, tournament_id: &Uuid) -> Option<LoadedTournament> {
+        let tournament = self.tournaments.get(tournament_id)?;
+        Some(LoadedTournament {
+            tournament: tournament.clone(),
+            player_sessions: self.sessions_for(tournament_id),
+            arbiter_accounts: self.arbiter_accounts_for(tournament_id),
+            device_policy: self.configured_device_policy(tournament_id),
+            device_alerts: self.device_alerts_for(tournament_id),
+            round_devices: self.round_devices_for(tournament_id),
+            journal_seq: self.journal_seq(tournament_id),
+        })
+    }
+    
+    /// Vuelve a subir cada torneo cargado (p. ej. al recuperarse la nube): las
+    /// rondas, revelaciones y totales cambiados durante el corte solo están en local
+    pub fn resync_storage(&self) {
+        for tournament_id in self.tournaments.keys() {
+            if let Some(state) = self.tournament_state(tournament_id) {
+                if let Err(e) = self.storage.resync(&state) {
+                    log::warn!("No se pudo resincronizar el torneo {}: {}", tournament_id, e);
+                }
+            }
+        }
+    }
+    
+    /// Guarda un snapshot completo en el almacenamiento configurado
+    fn persist(&self, tournament_id: &Uuid) -> Result<(), String> {
+        let state = self.tournament_state(tournament_id)
+            .ok_or("Tournament not found")?;
+        self.storage.save_snapshot(&state)
+    }
+    
+    /// Escribe un snapshot completo como punto de control del diario
+    fn checkpoint(&self, tournament_id: &Uuid) {
+        if let Err(e) = self.persist(tournament_id) {
+            eprintln!("Failed to write journal checkpoint: {}", e);
+        }
+    }
+    
+    /// Corrige el puntaje de una jugada ya registrada (p. ej. tras revisar una planilla)
+    pub fn adjust_player_score(&mut self, tournament_id: &Uuid, player_id: &Uuid, round_number: u32, score: i32, reason: String) -> Result<(), String> {
+        let event = JournalEvent::ScoreAdjusted { player_id: *player_id, round_number, score, reason };
+        let tournament = self.tournaments.get_mut(tournament_id)
+            .ok_or("Tournament not found")?;
+        journal::apply(tournament, &event)?;
+        
+        self.record(tournament_id, event);
+        self.checkpoint(tournament_id);
+        self.publish_leaderboard(tournament_id);
+        Ok(())
+    }
+    
+    /// Estado actual que se envía a los clientes al conectarse al stream de eventos
+    pub fn state_snapshot(&self, tournament_id: &Uuid) -> Result<StateSnapshot, String> {
+        let tournament = self.tournaments.get(tournament_id)
+            .ok_or("Tournament not found")?;
+        
+        let current_round = tournament.rounds.last();
+        let board = current_round
+            .map(|r| r.board_state.clone())
+            .unwrap_or(BoardState { tiles: vec![String::new(); 225] });
+        let submitted = current_round
+            .map(|r| Self::count_submissions(tournament, r.number))
+            .unwrap_or(0);
+        
+        Ok(StateSnapshot {
+            tournament_id: *tournament_id,
+            name: tournament.name.clone(),
+            status: tournament.status.clone(),
+            tiles_remaining: tournament.tiles_remaining,
+            current_round: current_round.map(RoundView::from_round),
+            board,
+            submitted,
+            total_players: tournament.players.len(),
+            leaderboard: Self::leaderboard_entries(tournament),
+        })
+    }
+    
+    fn count_submissions(tournament: &Tournament, round_number: u32) -> usize {
+        tournament.players.iter()
+            .filter(|p| p.plays.iter().any(|play| play.round_number == round_number))
+            .count()
+    }
+    
+    fn leaderboard_entries(tournament: &Tournament) -> Vec<LeaderboardEntry> {
+        let mut entries: Vec<LeaderboardEntry> = tournament.players.iter()
+            .map(|p| LeaderboardEntry {
+                player_id: p.id,
+                name: p.name.clone(),
+                total_score: p.total_score,
+            })
+            .collect();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.total_score));
+        entries
+    }
+    
+    fn publish_submission_count(&self, tournament_id: &Uuid, round_number: u32) {
+        if let Some(tournament) = self.tournaments.get(tournament_id) {
+            self.events.publish(tournament_id, TournamentEvent::SubmissionCount {
+                round_number,
+                submitted: Self::count_submissions(tournament, round_number),
+                total: tournament.players.len(),
+            });
+        }
+    }
+    
+    fn publish_round_stats(&self, tournament_id: &Uuid, round_number: u32) {
+        if let Some(stats) = self.tournaments.get(tournament_id).and_then(|t| round_stats::compute(t, round_number)) {
+            self.events.publish(tournament_id, TournamentEvent::RoundStats { stats });
+        }
+    }
+    
+    fn publish_leaderboard(&self, tournament_id: &Uuid) {
+        if let Some(tournament) = self.tournaments.get(tournament_id) {
+            self.events.publish(tournament_id, TournamentEvent::LeaderboardChanged {
+                leaderboard: Self::leaderboard_entries(tournament),
+            });
+        }
     }
     
     fn log_optimal_play(&self, tournament_id: &Uuid, round_number: u32) -> Result<(), String> {
@@ -1839,7 +3086,24 @@ impl TournamentManager {
     }
 }
 
-fn format_coordinate(position: &Position) -> String {
+/// "4ª mejor de 1.820 jugadas (3 puntúan más)"
+fn describe_rank(rank: &PlayRank) -> String {
+    let digits = rank.total_plays.to_string();
+    let mut total = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            total.push('.');
+        }
+        total.push(digit);
+    }
+    match rank.plays_higher {
+        0 => format!("la mejor de {} jugadas posibles", total),
+        1 => format!("{}ª mejor de {} jugadas posibles (1 puntúa más)", rank.rank, total),
+        n => format!("{}ª mejor de {} jugadas posibles ({} puntúan más)", rank.rank, total, n),
+    }
+}
+
+pub(crate) fn format_coordinate(position: &Position) -> String {
     let letters = "ABCDEFGHIJKLMNO";
     let row_letter = letters.chars().nth(position.row as usize).unwrap_or('?');
     
@@ -1850,4 +3114,200 @@ fn format_coordinate(position: &Position) -> String {
         // Horizontal: fila + columna
         format!("{}{}", row_letter, position.col + 1)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::InMemoryStorage;
+
+    fn new_tournament(status: TournamentStatus) -> Tournament {
+        Tournament {
+            id: Uuid::new_v4(),
+            name: "Restaurado".to_string(),
+            created_at: Utc::now(),
+            status,
+            rounds: Vec::new(),
+            players: Vec::new(),
+            tiles_remaining: 100,
+            master_plays: Vec::new(),
+            training: false,
+        }
+    }
+
+    fn loaded(tournament: Tournament, journal_seq: u64) -> LoadedTournament {
+        LoadedTournament {
+            tournament,
+            player_sessions: Vec::new(),
+            arbiter_accounts: Vec::new(),
+            device_policy: None,
+            device_alerts: Vec::new(),
+            round_devices: Vec::new(),
+            journal_seq,
+        }
+    }
+
+    fn manager_with_memory_storage() -> (TournamentManager, Arc<InMemoryStorage>) {
+        let storage = Arc::new(InMemoryStorage::default());
+        let mut manager = TournamentManager::new(std::net::IpAddr::from([127, 0, 0, 1]));
+        manager.set_storage(storage.clone());
+        (manager, storage)
+    }
+
+    #[test]
+    fn test_restore_without_journal_records_checkpoint() {
+        let (mut manager, storage) = manager_with_memory_storage();
+        let tournament = new_tournament(TournamentStatus::Created);
+        let id = tournament.id;
+
+        manager.restore_tournament(loaded(tournament, 0));
+        let journal = storage.journal(&id);
+        assert_eq!(journal.len(), 1);
+        assert_eq!(journal[0].seq, 1);
+        assert!(matches!(journal[0].event, JournalEvent::Checkpoint { .. }));
+        assert_eq!(manager.journal_seq(&id), 1);
+        assert_eq!(journal::replay(&journal, None).unwrap().id, id);
+
+        // Con diario previo no se añade otro punto de partida
+        let (mut manager, storage) = manager_with_memory_storage();
+        let tournament = new_tournament(TournamentStatus::Created);
+        let id = tournament.id;
+        manager.restore_tournament(loaded(tournament, 5));
+        assert!(storage.journal(&id).is_empty());
+        assert_eq!(manager.journal_seq(&id), 5);
+    }
+
+    #[test]
+    fn test_arbiter_access_requires_accounts_or_server_credential() {
+        let (mut manager, _) = manager_with_memory_storage();
+        manager.configure_access(Some("llave-servidor"), false);
+        let open = new_tournament(TournamentStatus::Created);
+        let guarded = new_tournament(TournamentStatus::Created);
+        let (open_id, guarded_id) = (open.id, guarded.id);
+        manager.restore_tournament(loaded(open, 0));
+        let mut with_accounts = loaded(guarded, 0);
+        with_accounts.arbiter_accounts.push(ArbiterAccount::new("Principal".to_string(), ArbiterRole::HeadArbiter, "secreto"));
+        manager.restore_tournament(with_accounts);
+        let token = manager.arbiter_login(&guarded_id, None, "secreto").unwrap().token;
+
+        // Sin cuentas y sin modo abierto el torneo no queda libre
+        assert!(manager.authorize_arbiter(Some(&open_id), None, ArbiterAction::Undo).is_err());
+        assert!(manager.authorize_arbiter(Some(&open_id), Some("llave-servidor"), ArbiterAction::Undo).is_ok());
+
+        // Una sesión de árbitro principal no alcanza para acciones globales
+        assert!(manager.authorize_arbiter(Some(&guarded_id), Some(&token), ArbiterAction::Undo).unwrap().is_some());
+        assert!(manager.authorize_arbiter(None, Some(&token), ArbiterAction::ConfigureServer).is_err());
+        assert!(manager.authorize_arbiter(None, Some("llave-servidor"), ArbiterAction::ConfigureServer).is_ok());
+        assert!(manager.create_tournament("Sin frase".to_string(), Vec::new(), None).is_err());
+
+        manager.configure_access(Some("llave-servidor"), true);
+        assert!(manager.authorize_arbiter(Some(&open_id), None, ArbiterAction::Undo).is_ok());
+        assert!(manager.authorize_arbiter(None, None, ArbiterAction::ConfigureServer).is_err());
+    }
+
+    #[test]
+    fn test_legacy_players_get_tokens_but_revoked_stay_locked() {
+        let (mut manager, _) = manager_with_memory_storage();
+        let mut tournament = new_tournament(TournamentStatus::InProgress);
+        let id = tournament.id;
+        let player = |name: &str| Player {
+            id: Uuid::new_v4(), name: name.to_string(), total_score: 0, plays: Vec::new(),
+            category: None, club: None, federation_id: None,
+        };
+        let (legacy, revoked) = (player("Ana"), player("Luis"));
+        tournament.players = vec![legacy.clone(), revoked.clone()];
+        let mut state = loaded(tournament, 1);
+        state.player_sessions = [&legacy, &revoked].iter().map(|p| PlayerSession {
+            player_id: p.id.to_string(),
+            name: p.name.clone(),
+            ip_address: "Unknown".to_string(),
+            user_agent: "Unknown".to_string(),
+            hardware_id: None,
+            enrolled_at: Utc::now(),
+            last_seen: Utc::now(),
+            token_hash: None,
+            token_issued_at: None,
+        }).collect();
+        manager.restore_tournament(state);
+        manager.reissue_player_token(&id, &revoked.id).unwrap();
+        manager.revoke_player_token(&id, &revoked.id).unwrap();
+
+        // La sesión sin token de un snapshot anterior a 1.1.0 recibe uno; la revocada no
+        assert!(manager.verify_player_token(&id, &legacy.id, Some("x")).unwrap_err().contains("aún no tiene token"));
+        let tokens = manager.issue_missing_player_tokens(&id).unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].player_id, legacy.id);
+        assert!(manager.verify_player_token(&id, &legacy.id, Some(&tokens[0].token)).is_ok());
+        assert!(manager.verify_player_token(&id, &revoked.id, Some("x")).unwrap_err().contains("revocado"));
+        assert!(manager.issue_missing_player_tokens(&id).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_revealed_round_drops_pending_timer() {
+        let (mut manager, _) = manager_with_memory_storage();
+        let mut tournament = new_tournament(TournamentStatus::InProgress);
+        let id = tournament.id;
+        tournament.rounds.push(crate::models::test_support::active_round(1, "CASADOS"));
+        manager.restore_tournament(loaded(tournament, 1));
+
+        manager.start_round_timer(&id, 1).unwrap();
+        assert!(manager.events.timer_pending(&id, 1));
+        manager.reveal_optimal_play(&id, 1).unwrap();
+        assert!(!manager.events.timer_pending(&id, 1));
+        assert!(manager.start_round_timer(&id, 1).is_err());
+    }
+
+    #[test]
+    fn test_describe_rank_groups_thousands() {
+        let rank = |scores: &[i32], score| describe_rank(&PlayRank::among(scores, score));
+        assert_eq!(rank(&[30, 24, 24, 10], 30), "la mejor de 4 jugadas posibles");
+        assert_eq!(rank(&[30, 24, 24, 10], 24), "2ª mejor de 4 jugadas posibles (1 puntúa más)");
+
+        let mut scores = vec![50, 40, 40, 40];
+        scores.extend(std::iter::repeat_n(5, 1816));
+        assert_eq!(rank(&scores, 5), "5ª mejor de 1.820 jugadas posibles (4 puntúan más)");
+        scores.extend(std::iter::repeat_n(1, 1_234_567 - scores.len()));
+        assert_eq!(rank(&scores, 50), "la mejor de 1.234.567 jugadas posibles");
+    }
+
+    #[test]
+    fn test_round_devices_survive_restore() {
+        let (mut manager, _) = manager_with_memory_storage();
+        let tournament = new_tournament(TournamentStatus::InProgress);
+        let id = tournament.id;
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut state = loaded(tournament, 1);
+        state.round_devices.push(RoundDevices { player_id: first, round_number: 1, devices: vec!["hw:tablet".to_string()] });
+        manager.restore_tournament(state);
+        assert_eq!(manager.tournament_state(&id).unwrap().round_devices.len(), 1);
+
+        // El dispositivo visto antes de reiniciar sigue contando para detectar envíos compartidos
+        assert!(manager.check_submission_device(&id, &second, 1, "hw:tablet").is_ok());
+        manager.record_submission_device(&id, &second, 1, "hw:tablet");
+        let alerts = manager.device_alerts_for(&id);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, DeviceAlertKind::SharedSubmission);
+    }
+
+    #[test]
+    fn test_coaching_without_klv_compares_points_and_caches_round() {
+        let (mut manager, _) = manager_with_memory_storage();
+        manager.load_dictionary("FISE2016_converted.kwg", None).unwrap();
+        let mut tournament = new_tournament(TournamentStatus::InProgress);
+        tournament.training = true;
+        tournament.rounds.push(crate::models::test_support::active_round(1, "CASADOS"));
+        let id = tournament.id;
+        manager.restore_tournament(loaded(tournament, 0));
+        manager.calculate_optimal_play(&id, 1).unwrap();
+        manager.tournaments.get_mut(&id).unwrap().rounds[0].optimal_revealed = true;
+
+        // Sin KLV los restos valen 0: la mejor por equity puntúa como el master
+        let coaching = manager.coaching_job(&id, 1, None).unwrap().run().unwrap();
+        assert!(!coaching.leave_values);
+        assert!(coaching.master_is_best_equity);
+        assert_eq!(coaching.best_equity.score, coaching.master.score);
+        assert!(manager.coaching_cache.lock().unwrap().contains_key(&(id, 1)));
+
+        let again = manager.coaching_job(&id, 1, None).unwrap().run().unwrap();
+        assert_eq!(again.best_equity.word, coaching.best_equity.word);
+    }
+}
@@ -2,9 +2,12 @@ use wolges::{
     alphabet, game_config, klv, kwg, movegen,
 };
 use wolges::kwg::Node;
-use crate::models::{BoardState, OptimalPlay, Position};
+use crate::models::{BoardState, EquityPlay, OptimalPlay, Position};
 use std::fs;
 
+/// Ruta del KLV (valores de resto) que se carga al iniciar, para el análisis por equity
+pub const KLV_PATH_ENV: &str = "KLV_PATH";
+
 /// Convert digraphs to internal representation
 fn convert_digraphs_to_internal(s: &str) -> String {
     // IMPORTANT: Only convert explicitly marked digraphs with brackets
@@ -31,11 +34,21 @@ fn convert_internal_to_digraphs(s: &str) -> String {
 }
 
 
+// Mejor candidata hasta el momento en best_equity_play
+struct EquityCandidate {
+    equity: f32,
+    score: i32,
+    position: Position,
+    word: Vec<u8>,  // Bytes internos, 0 donde pasa por una ficha del tablero
+    leave: Vec<u8>,  // Resto como recuento por ficha
+}
+
 pub struct WolgesEngine {
     kwg: kwg::Kwg<kwg::Node22>,
     klv: klv::Klv<kwg::Node22>,
     game_config: game_config::GameConfig,
     move_generator: movegen::KurniaMoveGenerator,
+    has_leaves: bool,  // Si se cargó un KLV (sin él todos los restos valen 0)
 }
 
 impl WolgesEngine {
@@ -70,6 +83,7 @@ impl WolgesEngine {
             klv,
             game_config,
             move_generator,
+            has_leaves: klv_path.is_some(),
         })
     }
     
@@ -112,68 +126,11 @@ impl WolgesEngine {
     ) -> Result<OptimalPlay, String> {
         eprintln!("DEBUG: find_optimal_play called with rack: '{}'", rack);
         
-        // Convert rack digraphs to internal representation
-        let internal_rack = convert_digraphs_to_internal(rack);
-        eprintln!("DEBUG: internal_rack: '{}'", internal_rack);
-        
-        // Convert board state to tiles
+        // Tablero y atril en fichas internas (dígrafos incluidos)
+        let board_tiles = self.board_tiles(board_state);
+        let rack_tiles = self.rack_tiles(rack)?;
         let alphabet = self.game_config.alphabet();
-        let alphabet_reader = alphabet::AlphabetReader::new_for_plays(alphabet);
-        let mut board_tiles = vec![0u8; 225];
-        
-        for (i, tile_str) in board_state.tiles.iter().enumerate() {
-            if !tile_str.is_empty() {
-                // Convert digraphs to internal format before parsing
-                let internal_tile = convert_digraphs_to_internal(tile_str);
-                let tile_bytes = internal_tile.as_bytes();
-                if let Some((tile, _)) = alphabet_reader.next_tile(tile_bytes, 0) {
-                    board_tiles[i] = tile;
-                } else {
-                    eprintln!("DEBUG: Failed to parse board tile at position {}: '{}'", i, tile_str);
-                }
-            }
-        }
-        
-        // Convert rack to tiles using internal representation
-        let rack_bytes = internal_rack.as_bytes();
-        let rack_reader = alphabet::AlphabetReader::new_for_racks(alphabet);
-        let mut rack_tiles = Vec::new();
-        let mut rack_blanks = Vec::new(); // Track which tiles are blanks
-        let mut idx = 0;
-        
-        eprintln!("DEBUG: Converting internal rack to tiles, rack_bytes.len() = {}", rack_bytes.len());
-        use std::io::Write;
-        std::io::stderr().flush().unwrap();
-        eprintln!("DEBUG: rack_bytes = {:?}", rack_bytes);
-        std::io::stderr().flush().unwrap();
-        
-        while idx < rack_bytes.len() {
-            // Check if this is a lowercase letter (blank)
-            let is_blank = if idx < rack_bytes.len() {
-                let byte = rack_bytes[idx];
-                // Check for lowercase letters (including special chars)
-                (byte >= b'a' && byte <= b'z') || 
-                // Check for lowercase ç (UTF-8: 195, 167)
-                (idx + 1 < rack_bytes.len() && rack_bytes[idx] == 195 && rack_bytes[idx + 1] == 167) ||
-                // Check for lowercase ñ (UTF-8: 195, 177)  
-                (idx + 1 < rack_bytes.len() && rack_bytes[idx] == 195 && rack_bytes[idx + 1] == 177)
-            } else {
-                false
-            };
-            
-            if let Some((tile, next_idx)) = rack_reader.next_tile(rack_bytes, idx) {
-                eprintln!("DEBUG: Found tile {} at idx {}, next_idx = {}, is_blank = {}", tile, idx, next_idx, is_blank);
-                rack_tiles.push(tile);
-                rack_blanks.push(is_blank);
-                idx = next_idx;
-            } else {
-                eprintln!("DEBUG: Failed to parse at idx {}, byte = {:?}", idx, rack_bytes.get(idx));
-                return Err(format!("Invalid rack character at position {}: {:?}", idx, 
-                    std::str::from_utf8(&rack_bytes[idx..idx+1]).unwrap_or("?")));
-            }
-        }
         
-        eprintln!("DEBUG: rack_tiles = {:?}", rack_tiles);
         eprintln!("DEBUG: rack_tiles as strings: {:?}", 
             rack_tiles.iter().map(|&t| 
                 if t == 0 { "?".to_string() } 
@@ -497,6 +454,219 @@ impl WolgesEngine {
         ))
     }
     
+    /// Tablero en fichas internas de wolges (0 = casilla vacía)
+    fn board_tiles(&self, board_state: &BoardState) -> Vec<u8> {
+        let alphabet_reader = alphabet::AlphabetReader::new_for_plays(self.game_config.alphabet());
+        let mut board_tiles = vec![0u8; 225];
+        for (i, tile_str) in board_state.tiles.iter().enumerate() {
+            if !tile_str.is_empty() {
+                let internal_tile = convert_digraphs_to_internal(tile_str);
+                if let Some((tile, _)) = alphabet_reader.next_tile(internal_tile.as_bytes(), 0) {
+                    board_tiles[i] = tile;
+                }
+            }
+        }
+        board_tiles
+    }
+    
+    fn rack_tiles(&self, rack: &str) -> Result<Vec<u8>, String> {
+        let internal_rack = convert_digraphs_to_internal(rack);
+        let rack_bytes = internal_rack.as_bytes();
+        let rack_reader = alphabet::AlphabetReader::new_for_racks(self.game_config.alphabet());
+        let mut rack_tiles = Vec::new();
+        let mut idx = 0;
+        while idx < rack_bytes.len() {
+            let (tile, next_idx) = rack_reader.next_tile(rack_bytes, idx)
+                .ok_or_else(|| format!("Invalid rack character at position {}", idx))?;
+            rack_tiles.push(tile);
+            idx = next_idx;
+        }
+        Ok(rack_tiles)
+    }
+    
+    /// Puntuaciones de todas las jugadas válidas para este tablero y atril, de
+    /// mayor a menor. No se guardan las jugadas (pueden ser decenas de miles
+    /// con comodines): se anota la puntuación de cada una según se genera.
+    pub fn all_play_scores(&mut self, board_state: &BoardState, rack: &str) -> Result<Vec<i32>, String> {
+        let board_tiles = self.board_tiles(board_state);
+        let rack_tiles = self.rack_tiles(rack)?;
+        
+        let board_snapshot = movegen::BoardSnapshot {
+            board_tiles: &board_tiles,
+            game_config: &self.game_config,
+            kwg: &self.kwg,
+            klv: &self.klv,
+        };
+        let gen_moves_params = movegen::GenMovesParams {
+            board_snapshot: &board_snapshot,
+            rack: &rack_tiles,
+            max_gen: usize::MAX,
+            num_exchanges_by_this_player: 0,
+            always_include_pass: false,
+        };
+        
+        let mut scores = Vec::new();
+        self.move_generator.gen_moves_filtered(
+            &gen_moves_params,
+            |down: bool, lane: i8, idx: i8, word: &[u8], score: i32| {
+                // Mismo filtro que find_optimal_play: descartar jugadas que pisan otra ficha
+                let overwrites = word.iter().enumerate().any(|(i, &tile)| {
+                    let (row, col) = if down {
+                        (idx as usize + i, lane as usize)
+                    } else {
+                        (lane as usize, idx as usize + i)
+                    };
+                    let board_tile = board_tiles.get(row * 15 + col).copied().unwrap_or(0);
+                    tile != 0 && board_tile != 0 && (board_tile & 0x7F) != (tile & 0x7F)
+                });
+                if !overwrites {
+                    scores.push(score);
+                }
+                false
+            },
+            |leave_value: f32| leave_value,
+            |_equity: f32, _play: &movegen::Play| false,
+        );
+        self.move_generator.plays.clear();
+        
+        scores.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(scores)
+    }
+    
+    pub fn has_leave_values(&self) -> bool {
+        self.has_leaves
+    }
+    
+    fn rack_tally(&self, rack_tiles: &[u8]) -> Vec<u8> {
+        let mut tally = vec![0u8; self.game_config.alphabet().len() as usize];
+        for &tile in rack_tiles {
+            tally[tile as usize] += 1;
+        }
+        tally
+    }
+    
+    /// Resto del atril en notación de pantalla (comodín como '?', al final)
+    fn leave_label(&self, tally: &[u8]) -> String {
+        let alphabet = self.game_config.alphabet();
+        let mut leave = String::new();
+        for tile in (1..tally.len()).chain(std::iter::once(0)) {
+            let label = if tile == 0 { "?" } else { alphabet.of_rack(tile as u8).unwrap_or("?") };
+            for _ in 0..tally[tile] {
+                leave.push_str(&convert_internal_to_digraphs(label));
+            }
+        }
+        leave
+    }
+    
+    /// Resto del atril y su valor en el KLV tras una jugada dada; la palabra
+    /// puede traer entre paréntesis las fichas que ya estaban en el tablero
+    pub fn play_leave(&self, board_state: &BoardState, rack: &str, position: &Position, word: &str) -> Result<(String, f32), String> {
+        let mut tally = self.rack_tally(&self.rack_tiles(rack)?);
+        
+        let internal_word = convert_digraphs_to_internal(&word.replace(['(', ')'], ""));
+        let word_bytes = internal_word.as_bytes();
+        let reader = alphabet::AlphabetReader::new_for_plays(self.game_config.alphabet());
+        let (mut idx, mut offset) = (0, 0);
+        while idx < word_bytes.len() {
+            let (tile, next_idx) = reader.next_tile(word_bytes, idx)
+                .ok_or_else(|| format!("Ficha no reconocida en '{}'", word))?;
+            let (row, col) = if position.down {
+                (position.row as usize + offset, position.col as usize)
+            } else {
+                (position.row as usize, position.col as usize + offset)
+            };
+            if row >= 15 || col >= 15 {
+                return Err(format!("'{}' se sale del tablero", word));
+            }
+            if board_state.tiles[row * 15 + col].is_empty() {
+                // Comodín: se descuenta el '?' del atril
+                let rack_tile = if tile & 0x80 != 0 { 0 } else { tile as usize };
+                if tally[rack_tile] == 0 {
+                    return Err(format!("'{}' usa fichas que no están en el atril {}", word, rack));
+                }
+                tally[rack_tile] -= 1;
+            }
+            idx = next_idx;
+            offset += 1;
+        }
+        
+        Ok((self.leave_label(&tally), self.klv.leave_value_from_tally(&tally)))
+    }
+    
+    /// Mejor jugada por equity (puntos más valor del resto según el KLV). Es
+    /// solo para análisis: no toca la jugada óptima de la ronda.
+    pub fn best_equity_play(&mut self, board_state: &BoardState, rack: &str) -> Result<EquityPlay, String> {
+        let board_tiles = self.board_tiles(board_state);
+        let rack_tiles = self.rack_tiles(rack)?;
+        let rack_tally = self.rack_tally(&rack_tiles);
+        
+        let board_snapshot = movegen::BoardSnapshot {
+            board_tiles: &board_tiles,
+            game_config: &self.game_config,
+            kwg: &self.kwg,
+            klv: &self.klv,
+        };
+        let gen_moves_params = movegen::GenMovesParams {
+            board_snapshot: &board_snapshot,
+            rack: &rack_tiles,
+            max_gen: usize::MAX,
+            num_exchanges_by_this_player: 0,
+            always_include_pass: false,
+        };
+        
+        let mut best: Option<EquityCandidate> = None;
+        let klv = &self.klv;
+        self.move_generator.gen_moves_filtered(
+            &gen_moves_params,
+            |down: bool, lane: i8, idx: i8, word: &[u8], score: i32| {
+                let mut leave = rack_tally.clone();
+                for (i, &tile) in word.iter().enumerate() {
+                    if tile == 0 {
+                        continue;
+                    }
+                    let (row, col) = if down {
+                        (idx as usize + i, lane as usize)
+                    } else {
+                        (lane as usize, idx as usize + i)
+                    };
+                    // Mismo filtro que find_optimal_play: descartar jugadas que pisan otra ficha
+                    let board_tile = board_tiles.get(row * 15 + col).copied().unwrap_or(0);
+                    if board_tile != 0 && (board_tile & 0x7F) != (tile & 0x7F) {
+                        return false;
+                    }
+                    let rack_tile = if tile & 0x80 != 0 { 0 } else { tile as usize };
+                    leave[rack_tile] = leave[rack_tile].saturating_sub(1);
+                }
+                let equity = score as f32 + klv.leave_value_from_tally(&leave);
+                let better = best.as_ref().is_none_or(|b| {
+                    equity > b.equity || (equity == b.equity && score > b.score)
+                });
+                if better {
+                    let position = Position {
+                        row: if down { idx as u8 } else { lane as u8 },
+                        col: if down { lane as u8 } else { idx as u8 },
+                        down,
+                    };
+                    best = Some(EquityCandidate { equity, score, position, word: word.to_vec(), leave });
+                }
+                false
+            },
+            |leave_value: f32| leave_value,
+            |_equity: f32, _play: &movegen::Play| false,
+        );
+        self.move_generator.plays.clear();
+        
+        let EquityCandidate { equity, score, position, word, leave } = best.ok_or("No valid plays found")?;
+        Ok(EquityPlay {
+            word: self.format_play_word(board_state, &word, &position)?,
+            position,
+            score,
+            leave: self.leave_label(&leave),
+            leave_value: equity - score as f32,
+            equity,
+        })
+    }
+    
     pub fn get_alphabet(&self) -> &alphabet::Alphabet {
         self.game_config.alphabet()
     }
@@ -585,4 +755,21 @@ impl WolgesEngine {
         
         bag.iter().take(7).map(|&s| s).collect::<Vec<_>>().join("")
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_support::empty_board;
+
+    #[test]
+    fn test_optimal_play_matches_best_score() {
+        let mut engine = WolgesEngine::new("FISE2016_converted.kwg", None).unwrap();
+        let board = empty_board();
+        for rack in ["CASEROS", "[CH]AR?OS"] {
+            let optimal = engine.find_optimal_play(&board, rack).unwrap();
+            let scores = engine.all_play_scores(&board, rack).unwrap();
+            assert_eq!(Some(&optimal.score), scores.first(), "rack {}", rack);
+        }
+    }
+}
@@ -1,5 +1,5 @@
 -- Verificación de la estructura de la tabla player_plays
--- Ejecutar este script después de aplicar el fix para confirmar que la estructura es correcta
+-- Ejecutar este script para confirmar la estructura tras las migraciones del servidor
 
 -- Verificar que la tabla existe y tiene las columnas correctas
 SELECT column_name, data_type, is_nullable, column_default